@@ -0,0 +1,232 @@
+use rodio::dynamic_mixer::{self, DynamicMixerController};
+use rodio::source::Zero;
+use rodio::Source;
+use crate::effects::{ChainUpdates, EffectChain, EffectChainSource, SidechainSend, SmoothedValue};
+use crate::modulation::EffectModulation;
+use crate::state::State;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Stereo channel count of the bus
+const STEREO: u16 = 2;
+
+/// Frames between reads of the master volume
+const VOLUME_UPDATE_INTERVAL: usize = 32;
+
+/// Output bus every note plays on, summing all of them so notes and tracks sound at the same time
+///
+/// Each track has an input of its own, whose sum runs through the track's effects once and is
+/// measured for sidechain keying. The track outputs are summed into the mix, which the master
+/// effects and the limiter then process as a whole. Cloning the bus gives another handle to the same mix.
+#[derive(Clone)]
+pub struct MixBus {
+    tracks: Vec<TrackInput>,    // One input per track, in track order
+    master: ChainUpdates,       // Edits of the master effects, for the copy playing on the mix
+    master_volume: Arc<AtomicU32>, // f32 bits of the master volume, read by the mix every block
+    generation: Arc<AtomicU32>, // Bumped by `stop_all`, notes started before it end
+}
+
+impl MixBus {
+    /// Create the bus and the source that plays the mix, to be appended to the output sink once
+    ///
+    /// # Parameters
+    /// - `sample_rate`: Rate the mix runs at; notes at other rates are converted
+    /// - `state`: Tracks to give an input each, and the master track processing their sum
+    pub fn new(sample_rate: u32, state: &State) -> (Self, Box<dyn Source<Item = f32> + Send>) {
+        let (controller, mix) = dynamic_mixer::mixer(STEREO, sample_rate);
+
+        let tracks = state
+            .tracks
+            .iter()
+            .map(|track| {
+                let (track_controller, track_output) = dynamic_mixer::mixer(STEREO, sample_rate);
+
+                // Silence keeps the track running between notes, it would end with the last one otherwise
+                track_controller.add(Zero::<f32>::new(STEREO, sample_rate));
+
+                // The effects start empty and pick up their first copy like any later edit, and
+                // keep running between notes so delay and reverb tails ring out
+                let updates = ChainUpdates::default();
+                updates.follow(&track.effects, state.tempo_bpm);
+
+                // One send measures the whole track, so voices never overwrite each other's level
+                let send = SidechainSend::new(track.sidechain_send.clone(), sample_rate);
+                let effects = EffectChainSource::new(track_output, EffectChain::new(sample_rate))
+                    .with_updates(updates.clone())
+                    .with_sidechain_send(send);
+                controller.add(effects);
+                TrackInput { mixer: track_controller, updates }
+            })
+            .collect();
+
+        let master = ChainUpdates::default();
+        master.follow(&state.master_track.effects, state.tempo_bpm);
+        let master_effects = EffectChainSource::new(mix, EffectChain::new(sample_rate)).with_updates(master.clone());
+
+        // The limiter comes last, so nothing the chains do can leave above its ceiling
+        let master_volume = Arc::new(AtomicU32::new(state.master_track.volume.to_bits()));
+        let volume = MasterVolume::new(master_effects, Arc::clone(&master_volume), sample_rate);
+        let output = EffectChainSource::new(volume, state.master_track.limiter.clone());
+
+        let bus = Self {
+            tracks,
+            master,
+            master_volume,
+            generation: Arc::new(AtomicU32::new(0)),
+        };
+        (bus, Box::new(output))
+    }
+
+    /// Pass changes to the effects, the tempo and the master volume on to the mix playing on the bus
+    pub fn sync(&self, state: &State) {
+        for (input, track) in self.tracks.iter().zip(&state.tracks) {
+            input.updates.follow(&track.effects, state.tempo_bpm);
+        }
+        self.master.follow(&state.master_track.effects, state.tempo_bpm);
+        self.master_volume.store(state.master_track.volume.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Start playing a note on a track alongside everything already on the bus
+    ///
+    /// # Parameters
+    /// - `track_id`: Track whose input and effects the note plays through
+    /// - `source`: The note's audio
+    /// - `modulation`: The note's mod matrix routings, which take over the track's effect settings
+    pub fn play<S>(&self, track_id: usize, source: S, modulation: EffectModulation)
+    where
+        S: Source<Item = f32> + Send + 'static,
+    {
+        let Some(track) = self.tracks.get(track_id) else {
+            return;
+        };
+        track.updates.modulate(modulation);
+        track.mixer.add(BusInput {
+            source,
+            generation: Arc::clone(&self.generation),
+            started: self.generation.load(Ordering::Relaxed),
+        });
+    }
+
+    /// Cut every note playing on the bus, and the effect tails they left behind
+    pub fn stop_all(&self) {
+        self.generation.fetch_add(1, Ordering::Relaxed);
+        for track in &self.tracks {
+            track.updates.reset();
+        }
+        self.master.reset();
+    }
+}
+
+/// A track's input on the bus and the hand-over of edits to its effects
+#[derive(Clone)]
+struct TrackInput {
+    mixer: Arc<DynamicMixerController<f32>>,
+    updates: ChainUpdates,
+}
+
+/// The mix at the master volume, which it reads every VOLUME_UPDATE_INTERVAL frames and glides to
+struct MasterVolume<S> {
+    source: S,
+    volume: Arc<AtomicU32>, // f32 bits of the volume, stored by the UI
+    gain: SmoothedValue,    // Glides to the volume so changes do not click
+    current_gain: f32,      // Gain of the frame being emitted
+    countdown: usize,       // Frames until the volume is read again
+    channel: u16,           // Channel of the next sample within its frame
+}
+
+impl<S> MasterVolume<S>
+where
+    S: Source<Item = f32>,
+{
+    fn new(source: S, volume: Arc<AtomicU32>, sample_rate: u32) -> Self {
+        let gain = SmoothedValue::new(f32::from_bits(volume.load(Ordering::Relaxed)), sample_rate);
+        Self { source, volume, gain, current_gain: 0.0, countdown: 0, channel: 0 }
+    }
+}
+
+impl<S> Iterator for MasterVolume<S>
+where
+    S: Source<Item = f32>,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let sample = self.source.next()?;
+
+        // Both channels of a frame get the same gain
+        if self.channel == 0 {
+            if self.countdown == 0 {
+                self.gain.set(f32::from_bits(self.volume.load(Ordering::Relaxed)));
+                self.countdown = VOLUME_UPDATE_INTERVAL;
+            }
+            self.countdown -= 1;
+            self.current_gain = self.gain.next();
+        }
+        self.channel = (self.channel + 1) % self.source.channels().max(1);
+        Some(sample * self.current_gain)
+    }
+}
+
+impl<S> Source for MasterVolume<S>
+where
+    S: Source<Item = f32>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.source.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.source.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.source.total_duration()
+    }
+}
+
+/// A note on the bus, ending early when the bus is stopped
+struct BusInput<S> {
+    source: S,
+    generation: Arc<AtomicU32>,
+    started: u32, // Bus generation when the note started
+}
+
+impl<S> Iterator for BusInput<S>
+where
+    S: Source<Item = f32>,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.generation.load(Ordering::Relaxed) != self.started {
+            return None;
+        }
+        self.source.next()
+    }
+}
+
+impl<S> Source for BusInput<S>
+where
+    S: Source<Item = f32>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.source.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.source.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.source.total_duration()
+    }
+}
@@ -1,23 +1,24 @@
-use rodio::{Source, Sink};
+use crate::audio::bus::MixBus;
 use std::time::Duration;
-use crate::state::{State, Track, MasterTrack, RecordedNote};
-use crate::waveforms::{Waveform, AMPLITUDE};
-use crate::waveforms::adsr_envelope::ADSREnvelope;
-use crate::waveforms::sine_wave::SineWave;
-use crate::waveforms::square_wave::SquareWave;
-use crate::waveforms::triangle_wave::TriangleWave;
-use crate::waveforms::sawtooth_wave::SawtoothWave;
-use crate::effects::AudioEffect;
+use crate::state::{State, Track};
+use crate::waveforms::{AMPLITUDE, SAMPLE_RATE};
+use crate::waveforms::adsr_envelope::EnvelopeGenerator;
+use crate::waveforms::oscillator::Oscillator;
+use crate::filters::{cutoff_from_normalized, key_tracked_cutoff, EnvelopedFilter, MultimodeFilter};
+use crate::modulation::Lfo;
+use crate::audio::voice::{Voice, VoiceHandle};
 use crate::music_theory::note::Note;
+use crate::music_theory::MAX_VELOCITY;
 
 /// Multi-track audio mixer that handles playback of all tracks
 pub struct MultiTrackMixer {
     sample_rate: u32,
+    tempo_bpm: f32,
 }
 
 impl MultiTrackMixer {
-    pub fn new(sample_rate: u32) -> Self {
-        Self { sample_rate }
+    pub fn new(sample_rate: u32, tempo_bpm: f32) -> Self {
+        Self { sample_rate, tempo_bpm }
     }
     
     /// Play a note on a specific track
@@ -25,100 +26,117 @@ impl MultiTrackMixer {
         &self,
         track: &Track,
         note: Note,
-        sink: &mut Sink,
+        velocity: u8,
+        bus: &MixBus,
     ) {
-        let base_frequency = note.frequency(track.octave);
-        
-        // Create waveform based on track settings
-        let synth = match track.waveform {
-            Waveform::SINE => {
-                let sine_wave = SineWave::new(base_frequency);
-                let adsr_envelope = ADSREnvelope::new(
-                    sine_wave,
-                    track.attack as f32 / 99.0 * 2.0,
-                    track.decay as f32 / 99.0 * 2.0,
-                    track.sustain as f32 / 99.0,
-                    track.release as f32 / 99.0 * 2.0
-                );
-                Box::new(adsr_envelope) as Box<dyn Source<Item=f32> + 'static + Send>
-            },
-            Waveform::SQUARE => {
-                let square_wave = SquareWave::new(base_frequency);
-                let adsr_envelope = ADSREnvelope::new(
-                    square_wave,
-                    track.attack as f32 / 99.0 * 2.0,
-                    track.decay as f32 / 99.0 * 2.0,
-                    track.sustain as f32 / 99.0,
-                    track.release as f32 / 99.0 * 2.0
-                );
-                Box::new(adsr_envelope) as Box<dyn Source<Item=f32> + 'static + Send>
-            },
-            Waveform::TRIANGLE => {
-                let triangle_wave = TriangleWave::new(base_frequency);
-                let adsr_envelope = ADSREnvelope::new(
-                    triangle_wave,
-                    track.attack as f32 / 99.0 * 2.0,
-                    track.decay as f32 / 99.0 * 2.0,
-                    track.sustain as f32 / 99.0,
-                    track.release as f32 / 99.0 * 2.0
-                );
-                Box::new(adsr_envelope) as Box<dyn Source<Item=f32> + 'static + Send>
-            },
-            Waveform::SAWTOOTH => {
-                let sawtooth_wave = SawtoothWave::new(base_frequency);
-                let adsr_envelope = ADSREnvelope::new(
-                    sawtooth_wave,
-                    track.attack as f32 / 99.0 * 2.0,
-                    track.decay as f32 / 99.0 * 2.0,
-                    track.sustain as f32 / 99.0,
-                    track.release as f32 / 99.0 * 2.0
-                );
-                Box::new(adsr_envelope) as Box<dyn Source<Item=f32> + 'static + Send>
-            },
-        };
-        
-        // Apply track volume and pan
-        let source_with_volume = synth.amplify(AMPLITUDE * track.volume);
+        let voice = self.build_voice(track, note.frequency(track.octave), velocity, false, 0.0);
+        let modulation = voice.effect_modulation();
+
+        // Add to the bus, alongside the notes already playing, where the track's effects run
+        bus.play(track.id, voice, modulation);
+    }
+
+    /// Play a note that sustains until it is released through the returned handle
+    ///
+    /// # Parameters
+    /// - `track`: Track whose settings shape the voice
+    /// - `note`: Note to play in the track's octave
+    /// - `velocity`: How hard the note was played (0-127)
+    /// - `start_level`: Envelope level the attack starts from, non-zero when taking over from a sounding note
+    /// - `glide_from`: Pitch of the note being taken over, which the new note glides from
+    /// - `bus`: Bus to play the voice on
+    pub fn play_held_note_on_track(
+        &self,
+        track: &Track,
+        note: Note,
+        velocity: u8,
+        start_level: f32,
+        glide_from: Option<f32>,
+        bus: &MixBus,
+    ) -> VoiceHandle {
+        let voice = self.build_voice(track, note.frequency(track.octave), velocity, true, start_level)
+            .with_glide(glide_from, track.glide_time());
+        let (voice, handle) = voice.with_handle();
+        let modulation = voice.effect_modulation();
+
+        bus.play(track.id, voice, modulation);
+        handle
+    }
+
+    /// Build the voice for a note from the track settings
+    fn build_voice(&self, track: &Track, base_frequency: f32, velocity: u8, held: bool, start_level: f32) -> Voice {
+        let voice_sample_rate = self.sample_rate;
         
-        // Apply track-specific effects
-        let source_with_effects = self.apply_track_effects(source_with_volume, track);
+        // Create oscillator based on track settings
+        let oscillator = Oscillator::new(track.waveform, SAMPLE_RATE);
+
+        // Apply the track's DAHDSR envelope - envelope handles its own termination
+        let mut envelope = EnvelopeGenerator::new(&track.amp_envelope(), voice_sample_rate).starting_at(start_level);
+        if held {
+            envelope = envelope.without_auto_release();
+        }
         
-        // Add to sink
-        sink.append(source_with_effects);
+        // Apply track volume and pan, then the track's response to how hard the note was played
+        let mut voice = Voice::new(oscillator, base_frequency, envelope, AMPLITUDE * track.volume, track.pan, voice_sample_rate)
+            .with_velocity(velocity as f32 / MAX_VELOCITY as f32, track.velocity_to_amp, track.velocity_to_filter);
+
+        // Shape the raw oscillator with the track's resonant filter
+        if let Some(filter) = self.track_filter(track, base_frequency, voice_sample_rate, held) {
+            voice = voice.with_filter(filter);
+        }
+
+        // Run every track LFO so the mod matrix can use it; only enabled ones are routed directly
+        for lfo_settings in &track.lfos {
+            let lfo = Lfo::new(lfo_settings, self.tempo_bpm, SAMPLE_RATE);
+            let depth = if lfo_settings.enabled { lfo_settings.depth } else { 0.0 };
+            voice = voice.with_lfo(lfo, lfo_settings.destination, depth);
+        }
+        voice.with_mod_matrix(&track.mod_matrix)
     }
     
-    /// Apply effects to a track's audio source
-    fn apply_track_effects<S>(&self, source: S, track: &Track) -> Box<dyn Source<Item=f32> + Send>
-    where
-        S: Source<Item=f32> + Send + 'static,
-    {
-        // For now, just return the source as-is since we need to implement effects processing
-        // TODO: Implement proper track-specific effects processing
-        if track.delay_enabled || track.reverb_enabled || track.flanger_enabled {
-            // Effects are enabled but we need to implement the processor
-            Box::new(source)
-        } else {
-            Box::new(source)
+    /// Build a track's resonant multimode filter when active,
+    /// with the cutoff following the note pitch and the track's filter envelope
+    fn track_filter(&self, track: &Track, note_frequency: f32, sample_rate: u32, held: bool) -> Option<EnvelopedFilter> {
+        if !track.lpf_active {
+            return None;
+        }
+
+        let base_cutoff = key_tracked_cutoff(
+            cutoff_from_normalized(track.filter_cutoff),
+            note_frequency,
+            track.filter_key_tracking,
+        );
+        let filter = MultimodeFilter::new(
+            track.filter_mode,
+            track.filter_slope,
+            base_cutoff,
+            track.filter_resonance,
+            sample_rate,
+        );
+        let mut envelope = EnvelopeGenerator::new(&track.filter_envelope(), sample_rate);
+        if held {
+            envelope = envelope.without_auto_release();
         }
+        Some(EnvelopedFilter::new(filter, base_cutoff, track.filter_resonance, envelope, track.filter_env_amount))
     }
     
     /// Play back recorded notes from multiple tracks simultaneously
     pub fn play_multi_track_sequence(
         &self,
         state: &State,
-        sink: &mut Sink,
+        bus: &MixBus,
         playback_time: f32,
     ) {
         let playing_tracks = state.playing_tracks();
         
         for track_id in playing_tracks {
             let track = &state.tracks[track_id];
-            self.play_track_at_time(track, sink, playback_time);
+            self.play_track_at_time(track, bus, playback_time);
         }
     }
     
     /// Play a specific track's notes at a given time
-    fn play_track_at_time(&self, track: &Track, sink: &mut Sink, playback_time: f32) {
+    fn play_track_at_time(&self, track: &Track, bus: &MixBus, playback_time: f32) {
         let frame_time_threshold = 0.05; // 50ms threshold
         
         for recorded_note in &track.recorded_notes {
@@ -126,17 +144,10 @@ impl MultiTrackMixer {
             
             // Check if this note should start playing now
             if playback_time >= note_start && playback_time < note_start + frame_time_threshold {
-                self.play_note_on_track(track, recorded_note.note, sink);
+                self.play_note_on_track(track, recorded_note.note, recorded_note.velocity, bus);
             }
         }
     }
-    
-    /// Calculate final master mix with master effects
-    pub fn apply_master_effects(&self, _master_track: &MasterTrack, sample: f32) -> f32 {
-        // Apply master volume
-        sample * _master_track.volume
-        // TODO: Apply master effects (delay, reverb, flanger)
-    }
 }
 
 /// Panning utility function
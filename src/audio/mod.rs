@@ -1,3 +1,6 @@
+pub mod bus;
 pub mod mixer;
+pub mod voice;
 
+pub use bus::MixBus;
 pub use mixer::MultiTrackMixer;
\ No newline at end of file
@@ -0,0 +1,376 @@
+use rodio::Source;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use crate::audio::mixer::apply_pan;
+use crate::filters::{EnvelopedFilter, KEY_TRACKING_REFERENCE_HZ};
+use crate::modulation::{EffectModulation, Lfo, ModDestination, ModSlot, ModSourceValues, ModulationOffsets, LFOS_PER_TRACK, MOD_NOTE_RANGE_OCTAVES};
+use crate::modulation::matrix::note_on_random;
+use crate::waveforms::adsr_envelope::EnvelopeGenerator;
+use crate::waveforms::oscillator::Oscillator;
+
+/// Stereo channel count of a voice
+const STEREO: u16 = 2;
+
+/// An LFO routed to one voice parameter
+struct VoiceLfo {
+    lfo: Lfo,
+    destination: ModDestination,
+    depth: f32,
+}
+
+/// Note events passed from the UI thread to a playing voice
+#[derive(Debug, Default)]
+struct VoiceControl {
+    released: AtomicBool,
+    stopped: AtomicBool,         // Cut the note at once, as when a new note takes over
+    finished: AtomicBool,
+    level: AtomicU32,            // Bits of the most recent envelope level
+    frequency: AtomicU32,        // Bits of the current pitch in Hz, including any glide in progress
+    legato_frequency: AtomicU32, // Bits of a pending pitch change, zero when none is pending
+}
+
+/// Shared handle to a voice that is playing on the bus, used to end or re-pitch the note
+/// after it has been handed to the audio thread
+#[derive(Debug, Clone, Default)]
+pub struct VoiceHandle {
+    control: Arc<VoiceControl>,
+}
+
+impl VoiceHandle {
+    /// Start the release of the voice, as when its key is let go
+    pub fn release(&self) {
+        self.control.released.store(true, Ordering::Relaxed);
+    }
+
+    /// End the voice on its next sample, for a note taking over from it at the same level
+    pub fn stop(&self) {
+        self.control.stopped.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether the key of the voice is still held
+    pub fn is_held(&self) -> bool {
+        !self.control.released.load(Ordering::Relaxed) && !self.is_finished()
+    }
+
+    /// Whether the voice has gone silent and left the bus
+    pub fn is_finished(&self) -> bool {
+        self.control.finished.load(Ordering::Relaxed)
+    }
+
+    /// Most recent amplitude envelope level of the voice, 0.0 once it has finished
+    pub fn level(&self) -> f32 {
+        if self.is_finished() {
+            0.0
+        } else {
+            f32::from_bits(self.control.level.load(Ordering::Relaxed))
+        }
+    }
+
+    /// Current pitch of the voice in Hz, part way between notes while gliding
+    pub fn frequency(&self) -> f32 {
+        f32::from_bits(self.control.frequency.load(Ordering::Relaxed))
+    }
+
+    /// Move the held voice to a new pitch without restarting its envelopes, gliding there if the voice has a glide time
+    pub fn legato(&self, frequency: f32) {
+        self.control.legato_frequency.store(frequency.to_bits(), Ordering::Relaxed);
+    }
+}
+
+/// A single sounding note: oscillator -> filter -> amplitude envelope -> gain -> pan.
+/// The pitch can be changed while the note is playing, optionally gliding to the new note.
+///
+/// All per-sample modulation happens here so that the LFOs and the mod matrix can reach
+/// every stage of the signal path. Output is interleaved stereo.
+pub struct Voice {
+    oscillator: Oscillator,
+    frequency: f32,             // Target pitch of the note in Hz
+    glide_samples: usize,       // Time to slide between notes, zero for an instant change
+    glide_octaves: f32,         // Remaining distance to the target pitch
+    glide_step: f32,            // Octaves covered per sample while gliding
+    filter: Option<EnvelopedFilter>,
+    envelope: EnvelopeGenerator,
+    envelope_level: f32,        // Most recent amplitude envelope output, exposed as a modulation source
+    lfos: Vec<VoiceLfo>,        // In track order, so LFO 1 and 2 of the mod matrix can be looked up
+    mod_slots: Vec<ModSlot>,
+    effect_modulation: EffectModulation, // Mod matrix routings to the track's effects
+    velocity: f32,              // 0.0 - 1.0
+    velocity_cutoff: f32,       // Cutoff offset from velocity, as a fraction of full-scale cutoff modulation
+    note: f32,                  // Note pitch relative to C4 as a modulation source (-1.0 - 1.0)
+    random: f32,                // Per-note random modulation source (-1.0 - 1.0)
+    gain: f32,
+    pan: f32,                   // -1.0 (left) to 1.0 (right)
+    pending_right: Option<f32>, // Right channel sample waiting to be emitted
+    sample_rate: u32,
+    control: Option<Arc<VoiceControl>>,
+}
+
+impl Voice {
+    /// Create a new voice
+    ///
+    /// # Parameters
+    /// - `oscillator`: Oscillator generating the raw waveform
+    /// - `frequency`: Note frequency in Hz
+    /// - `envelope`: Amplitude envelope, ends the voice when its release has finished
+    /// - `gain`: Output gain
+    /// - `pan`: Stereo position from -1.0 (left) to 1.0 (right)
+    /// - `sample_rate`: Audio sample rate
+    pub fn new(oscillator: Oscillator, frequency: f32, envelope: EnvelopeGenerator, gain: f32, pan: f32, sample_rate: u32) -> Self {
+        Self {
+            oscillator,
+            frequency,
+            glide_samples: 0,
+            glide_octaves: 0.0,
+            glide_step: 0.0,
+            filter: None,
+            envelope,
+            envelope_level: 0.0,
+            lfos: Vec::new(),
+            mod_slots: Vec::new(),
+            effect_modulation: EffectModulation::default(),
+            velocity: 1.0,
+            velocity_cutoff: 0.0,
+            note: note_source(frequency),
+            random: note_on_random(),
+            gain,
+            pan: pan.clamp(-1.0, 1.0),
+            pending_right: None,
+            sample_rate,
+            control: None,
+        }
+    }
+
+    /// Set how hard the note was played and how much that affects its level and filter cutoff
+    ///
+    /// # Parameters
+    /// - `velocity`: Note velocity from 0.0 to 1.0, also available to the mod matrix
+    /// - `amp_sensitivity`: 0.0 plays every note at full gain, 1.0 scales the gain by the velocity
+    /// - `filter_sensitivity`: 0.0 leaves the cutoff alone, 1.0 closes it by the full cutoff modulation range for the softest notes
+    pub fn with_velocity(mut self, velocity: f32, amp_sensitivity: f32, filter_sensitivity: f32) -> Self {
+        self.velocity = velocity.clamp(0.0, 1.0);
+        self.gain *= 1.0 - amp_sensitivity.clamp(0.0, 1.0) * (1.0 - self.velocity);
+        self.velocity_cutoff = filter_sensitivity.clamp(0.0, 1.0) * (self.velocity - 1.0);
+        self
+    }
+
+    /// Slide pitch between notes over `glide_time` seconds: from `from_frequency` when taking over
+    /// from a sounding note, and on every legato pitch change
+    pub fn with_glide(mut self, from_frequency: Option<f32>, glide_time: f32) -> Self {
+        self.glide_samples = (glide_time.max(0.0) * self.sample_rate as f32) as usize;
+        if let Some(from_frequency) = from_frequency {
+            self.glide_from(from_frequency);
+        }
+        self
+    }
+
+    /// Start a glide from `from_frequency` to the target pitch
+    fn glide_from(&mut self, from_frequency: f32) {
+        if self.glide_samples == 0 || from_frequency <= 0.0 {
+            self.glide_octaves = 0.0;
+            return;
+        }
+        self.glide_octaves = (from_frequency / self.frequency).log2();
+        self.glide_step = self.glide_octaves.abs() / self.glide_samples as f32;
+    }
+
+    /// Current pitch in Hz, moving one sample further along any glide in progress
+    fn next_glide_frequency(&mut self) -> f32 {
+        if self.glide_octaves == 0.0 {
+            return self.frequency;
+        }
+
+        let frequency = self.frequency * 2.0f32.powf(self.glide_octaves);
+        self.glide_octaves = if self.glide_octaves > 0.0 {
+            (self.glide_octaves - self.glide_step).max(0.0)
+        } else {
+            (self.glide_octaves + self.glide_step).min(0.0)
+        };
+        frequency
+    }
+
+    /// Hold the note until it is released through the returned handle
+    pub fn with_handle(mut self) -> (Self, VoiceHandle) {
+        let handle = VoiceHandle::default();
+        self.control = Some(Arc::clone(&handle.control));
+        (self, handle)
+    }
+
+    /// Apply the note events sent through the voice handle since the previous sample,
+    /// returning false once the voice has been stopped
+    fn poll_control(&mut self) -> bool {
+        let Some(control) = &self.control else {
+            return true;
+        };
+        if control.stopped.load(Ordering::Relaxed) {
+            return false;
+        }
+
+        let legato_frequency = control.legato_frequency.swap(0, Ordering::Relaxed);
+        let released = control.released.load(Ordering::Relaxed);
+
+        if legato_frequency != 0 {
+            let current_frequency = self.frequency * 2.0f32.powf(self.glide_octaves);
+            self.frequency = f32::from_bits(legato_frequency);
+            self.note = note_source(self.frequency);
+            self.glide_from(current_frequency);
+        }
+
+        if released && !self.envelope.is_released() {
+            self.envelope.release();
+            if let Some(filter) = &mut self.filter {
+                filter.release();
+            }
+        }
+
+        true
+    }
+
+    /// Publish the envelope level and pitch, or that the voice has ended
+    fn update_control(&self, frequency: f32, finished: bool) {
+        if let Some(control) = &self.control {
+            control.level.store(self.envelope_level.to_bits(), Ordering::Relaxed);
+            control.frequency.store(frequency.to_bits(), Ordering::Relaxed);
+            if finished {
+                control.finished.store(true, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Run the oscillator through a filter before the amplitude envelope
+    pub fn with_filter(mut self, filter: EnvelopedFilter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// Add the next LFO of the track, routed directly to one of the voice parameters.
+    /// A depth of zero leaves it running for the mod matrix only.
+    pub fn with_lfo(mut self, lfo: Lfo, destination: ModDestination, depth: f32) -> Self {
+        self.lfos.push(VoiceLfo {
+            lfo,
+            destination,
+            depth: depth.clamp(0.0, 1.0),
+        });
+        self
+    }
+
+    /// Feed the voice parameters and the track's effect settings from the track's mod matrix
+    pub fn with_mod_matrix(mut self, slots: &[ModSlot]) -> Self {
+        self.mod_slots = slots.to_vec();
+        self.effect_modulation = EffectModulation::new(slots);
+        self
+    }
+
+    /// Routings of the mod matrix to effect settings, for the chain that processes the voice
+    pub fn effect_modulation(&self) -> EffectModulation {
+        self.effect_modulation.clone()
+    }
+
+    /// Render the next mono sample and the pan position to place it at
+    fn render_sample(&mut self) -> Option<(f32, f32)> {
+        if !self.poll_control() {
+            self.update_control(self.frequency, true);
+            return None;
+        }
+
+        let mut offsets = ModulationOffsets::default();
+        offsets.add(ModDestination::Cutoff, self.velocity_cutoff);
+        let mut lfo_values = [0.0; LFOS_PER_TRACK];
+
+        for (i, voice_lfo) in self.lfos.iter_mut().enumerate() {
+            let value = voice_lfo.lfo.next_value();
+            if let Some(slot) = lfo_values.get_mut(i) {
+                *slot = value;
+            }
+
+            let modulation = match voice_lfo.destination {
+                // Tremolo dips the level down from full volume rather than boosting it
+                ModDestination::Amplitude => voice_lfo.depth * 0.5 * (value - 1.0),
+                _ => value * voice_lfo.depth,
+            };
+            offsets.add(voice_lfo.destination, modulation);
+        }
+
+        // Envelope sources lag one sample behind, as they are computed further down the path
+        let source_values = ModSourceValues {
+            lfos: lfo_values,
+            amp_envelope: self.envelope_level,
+            filter_envelope: self.filter.as_ref().map_or(0.0, |filter| filter.envelope_level()),
+            velocity: self.velocity,
+            note: self.note,
+            random: self.random,
+        };
+        offsets.add_slots(&self.mod_slots, &source_values);
+        if !self.effect_modulation.is_empty() {
+            self.effect_modulation.publish(&self.mod_slots, &source_values);
+        }
+
+        let note_frequency = self.next_glide_frequency();
+        let frequency = note_frequency * 2.0f32.powf(offsets.pitch_semitones / 12.0);
+        let pulse_width = (0.5 + offsets.pulse_width).clamp(0.05, 0.95);
+        let raw = self.oscillator.next_sample(frequency, pulse_width);
+
+        let filtered = match &mut self.filter {
+            Some(filter) => filter.process_sample(raw, offsets.cutoff_octaves, offsets.resonance),
+            None => raw,
+        };
+
+        self.envelope_level = self.envelope.next_amplitude();
+
+        // End the voice once the release has run out or faded to silence
+        if self.envelope.is_finished() || (self.envelope_level < 0.0001 && self.envelope.is_released()) {
+            self.update_control(note_frequency, true);
+            return None;
+        }
+        self.update_control(note_frequency, false);
+
+        let pan = (self.pan + offsets.pan).clamp(-1.0, 1.0);
+        Some((filtered * self.envelope_level * offsets.amplitude * self.gain, pan))
+    }
+}
+
+/// Marks the handle finished when the bus drops the voice early, e.g. on `MixBus::stop_all`
+impl Drop for Voice {
+    fn drop(&mut self) {
+        self.update_control(self.frequency, true);
+    }
+}
+
+/// Note pitch relative to C4 as a modulation source (-1.0 - 1.0)
+fn note_source(frequency: f32) -> f32 {
+    ((frequency / KEY_TRACKING_REFERENCE_HZ).log2() / MOD_NOTE_RANGE_OCTAVES).clamp(-1.0, 1.0)
+}
+
+impl Iterator for Voice {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(right) = self.pending_right.take() {
+            return Some(right);
+        }
+
+        let (sample, pan) = self.render_sample()?;
+        let (left, right) = apply_pan(sample, pan);
+        self.pending_right = Some(right);
+
+        Some(left)
+    }
+}
+
+impl Source for Voice {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        STEREO
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None // Voice length depends on the envelope
+    }
+}
@@ -0,0 +1,77 @@
+use super::{AudioEffect, EffectLfo, EffectParameter, ParameterUnit, SmoothedValue};
+use crate::modulation::lfo::{LfoShape, SyncDivision};
+use std::f32::consts::{FRAC_PI_4, SQRT_2};
+
+/// Auto-pan effect: an LFO sweeps the signal between the left and right channels
+#[derive(Debug, Clone)]
+pub struct AutoPanEffect {
+    lfo: EffectLfo,
+    depth: SmoothedValue, // 0.0 - 1.0
+}
+
+impl AutoPanEffect {
+    /// Create a new auto-pan effect
+    ///
+    /// # Parameters
+    /// - `shape`: LFO waveform
+    /// - `rate_hz`: LFO rate in Hz when not synced
+    /// - `depth`: How far from the centre the sweep reaches (0.0 - 1.0)
+    /// - `sample_rate`: Audio sample rate
+    pub fn new(shape: LfoShape, rate_hz: f32, depth: f32, sample_rate: u32) -> Self {
+        Self {
+            lfo: EffectLfo::new(shape, rate_hz, sample_rate),
+            depth: SmoothedValue::new(depth.clamp(0.0, 1.0), sample_rate),
+        }
+    }
+
+    /// Lock one sweep to a note length at the project tempo
+    pub fn with_sync(mut self, division: SyncDivision) -> Self {
+        self.lfo.set_sync(Some(division));
+        self
+    }
+}
+
+impl AudioEffect for AutoPanEffect {
+    fn process_sample(&mut self, input: f32) -> f32 {
+        let (left, right) = self.process_stereo(input, input);
+        (left + right) * 0.5
+    }
+
+    fn process_stereo(&mut self, left: f32, right: f32) -> (f32, f32) {
+        let pan = self.lfo.next_value() * self.depth.next();
+
+        // Equal-power pan law, normalised so the centre position is unity gain
+        let angle = (pan + 1.0) * FRAC_PI_4;
+        (left * angle.cos() * SQRT_2, right * angle.sin() * SQRT_2)
+    }
+
+    fn set_tempo(&mut self, tempo_bpm: f32) {
+        self.lfo.set_tempo(tempo_bpm);
+    }
+
+    fn parameters(&self) -> Vec<EffectParameter> {
+        let mut parameters = self.lfo.parameters().to_vec();
+        parameters.push(EffectParameter::new("DEPTH", self.depth.target(), 0.0, 1.0, ParameterUnit::Percent));
+        parameters
+    }
+
+    fn set_parameter(&mut self, index: usize, value: f32) {
+        match index {
+            0..=2 => self.lfo.set_parameter(index, value),
+            3 => self.depth.set(value.clamp(0.0, 1.0)),
+            _ => {}
+        }
+    }
+
+    fn snap(&mut self) {
+        self.depth.jump();
+    }
+
+    fn reset(&mut self) {
+        self.lfo.reset();
+    }
+
+    fn name(&self) -> &str {
+        "Auto-Pan"
+    }
+}
@@ -0,0 +1,128 @@
+use super::{AudioEffect, EffectParameter, ParameterUnit, SmoothedValue};
+use crate::modulation::random::Xorshift;
+
+/// Lowest and highest bit depth of the quantizer
+pub const MIN_BIT_DEPTH: f32 = 1.0;
+pub const MAX_BIT_DEPTH: f32 = 16.0;
+
+/// Largest sample-rate reduction factor
+pub const MAX_DOWNSAMPLE_FACTOR: f32 = 64.0;
+
+/// Lo-fi effect reducing the bit depth and holding samples to lower the sample rate
+#[derive(Debug, Clone)]
+pub struct BitcrusherEffect {
+    // Sample & hold state
+    held_sample: f32,
+    hold_phase: f32,       // Advances by 1.0 / downsample factor, a new sample is held when it wraps
+
+    // Parameters
+    bit_depth: SmoothedValue,  // Quantizer resolution in bits (1.0 - 16.0), fractional depths blend smoothly
+    downsample: SmoothedValue, // Sample-rate reduction factor (1.0 = off - 64.0)
+    dither: bool,              // Add triangular noise before quantizing to decorrelate the error
+    mix: SmoothedValue,        // Dry/wet mix (0.0 - 1.0)
+
+    random: Xorshift,      // Source of the dither noise
+}
+
+impl BitcrusherEffect {
+    /// Create a new bitcrusher effect
+    ///
+    /// # Parameters
+    /// - `bit_depth`: Quantizer resolution in bits (1.0 - 16.0)
+    /// - `downsample`: Sample-rate reduction factor (1.0 - 64.0)
+    /// - `dither`: Whether to dither before quantizing
+    /// - `mix`: Dry/wet mix (0.0 - 1.0)
+    /// - `sample_rate`: Audio sample rate
+    pub fn new(bit_depth: f32, downsample: f32, dither: bool, mix: f32, sample_rate: u32) -> Self {
+        Self {
+            held_sample: 0.0,
+            hold_phase: 1.0, // Hold the very first sample
+            bit_depth: SmoothedValue::new(bit_depth.clamp(MIN_BIT_DEPTH, MAX_BIT_DEPTH), sample_rate),
+            downsample: SmoothedValue::new(downsample.clamp(1.0, MAX_DOWNSAMPLE_FACTOR), sample_rate),
+            dither,
+            mix: SmoothedValue::new(mix.clamp(0.0, 1.0), sample_rate),
+            random: Xorshift::new(0x2545_F491),
+        }
+    }
+
+    /// Set the quantizer resolution in bits (1.0 - 16.0)
+    pub fn set_bit_depth(&mut self, bit_depth: f32) {
+        self.bit_depth.set(bit_depth.clamp(MIN_BIT_DEPTH, MAX_BIT_DEPTH));
+    }
+
+    /// Set the sample-rate reduction factor (1.0 - 64.0)
+    pub fn set_downsample(&mut self, downsample: f32) {
+        self.downsample.set(downsample.clamp(1.0, MAX_DOWNSAMPLE_FACTOR));
+    }
+
+    /// Set dry/wet mix (0.0 - 1.0)
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix.set(mix.clamp(0.0, 1.0));
+    }
+
+    /// Round a sample to the quantizer grid
+    fn quantize(&mut self, sample: f32, bit_depth: f32) -> f32 {
+        // Steps per unit amplitude for a bipolar signal
+        let steps = 2.0f32.powf(bit_depth - 1.0);
+
+        // Triangular dither spanning one step either side
+        let noise = if self.dither {
+            (self.random.next_bipolar() + self.random.next_bipolar()) * 0.5 / steps
+        } else {
+            0.0
+        };
+
+        ((sample + noise) * steps).round() / steps
+    }
+}
+
+impl AudioEffect for BitcrusherEffect {
+    fn process_sample(&mut self, input: f32) -> f32 {
+        let bit_depth = self.bit_depth.next();
+        let mix = self.mix.next();
+
+        // Only pick up a new input sample once per downsampled period
+        self.hold_phase += 1.0 / self.downsample.next();
+        if self.hold_phase >= 1.0 {
+            self.hold_phase -= 1.0;
+            self.held_sample = self.quantize(input, bit_depth);
+        }
+
+        // Mix dry and wet signals
+        input * (1.0 - mix) + self.held_sample * mix
+    }
+
+    fn snap(&mut self) {
+        self.bit_depth.jump();
+        self.downsample.jump();
+        self.mix.jump();
+    }
+
+    fn reset(&mut self) {
+        self.held_sample = 0.0;
+        self.hold_phase = 1.0;
+    }
+
+    fn parameters(&self) -> Vec<EffectParameter> {
+        vec![
+            EffectParameter::new("BITS", self.bit_depth.target(), MIN_BIT_DEPTH, MAX_BIT_DEPTH, ParameterUnit::Bits),
+            EffectParameter::new("RATE", self.downsample.target(), 1.0, MAX_DOWNSAMPLE_FACTOR, ParameterUnit::Factor).with_exponential(),
+            EffectParameter::switch("DITHER", self.dither),
+            EffectParameter::new("MIX", self.mix.target(), 0.0, 1.0, ParameterUnit::Percent),
+        ]
+    }
+
+    fn set_parameter(&mut self, index: usize, value: f32) {
+        match index {
+            0 => self.set_bit_depth(value),
+            1 => self.set_downsample(value),
+            2 => self.dither = value >= 0.5,
+            3 => self.set_mix(value),
+            _ => {}
+        }
+    }
+
+    fn name(&self) -> &str {
+        "Bitcrusher"
+    }
+}
@@ -0,0 +1,168 @@
+use super::{read_interpolated, AudioEffect, EffectParameter, ParameterUnit, SmoothedValue};
+use crate::modulation::lfo::{MAX_LFO_RATE_HZ, MIN_LFO_RATE_HZ};
+use std::f32::consts::PI;
+
+/// Number of modulated delay voices in the chorus
+pub const CHORUS_VOICES: usize = 3;
+
+/// Stereo chorus built from several modulated delay lines spread across the stereo field
+#[derive(Debug, Clone)]
+pub struct ChorusEffect {
+    buffer: Vec<f32>,
+    write_index: usize,
+
+    // One LFO phase per voice, spread evenly so the voices never move together
+    lfo_phases: [f32; CHORUS_VOICES],
+    lfo_rate: f32,        // LFO frequency in Hz
+
+    // Parameters
+    voice_delays: [f32; CHORUS_VOICES], // Centre delay of every voice in samples
+    delay_range: f32,     // Modulation range in samples at full depth
+    depth: SmoothedValue, // Effect depth (0.0 - 1.0)
+    mix: SmoothedValue,   // Dry/wet mix (0.0 - 1.0)
+
+    sample_rate: u32,
+}
+
+impl ChorusEffect {
+    /// Create a new chorus effect
+    ///
+    /// # Parameters
+    /// - `lfo_rate`: LFO rate in Hz (typically 0.1 - 3.0)
+    /// - `depth`: Effect depth (0.0 - 1.0)
+    /// - `mix`: Dry/wet mix (0.0 - 1.0)
+    /// - `sample_rate`: Audio sample rate
+    pub fn new(lfo_rate: f32, depth: f32, mix: f32, sample_rate: u32) -> Self {
+        // Chorus voices sit around 12-18ms, well past the comb filtering range of a flanger
+        let ms = sample_rate as f32 / 1000.0;
+        let voice_delays = [12.0 * ms, 15.0 * ms, 18.0 * ms];
+        let delay_range = 5.0 * ms; // +/- 5ms modulation range
+
+        // Buffer size needs to accommodate the longest voice at full sweep
+        let buffer_size = ((voice_delays[CHORUS_VOICES - 1] + delay_range) as usize + 2).max(1024);
+
+        let mut lfo_phases = [0.0; CHORUS_VOICES];
+        for (i, phase) in lfo_phases.iter_mut().enumerate() {
+            *phase = i as f32 / CHORUS_VOICES as f32;
+        }
+
+        Self {
+            buffer: vec![0.0; buffer_size],
+            write_index: 0,
+            lfo_phases,
+            lfo_rate: lfo_rate.max(0.01), // Prevent division by zero
+            voice_delays,
+            delay_range,
+            depth: SmoothedValue::new(depth.clamp(0.0, 1.0), sample_rate),
+            mix: SmoothedValue::new(mix.clamp(0.0, 1.0), sample_rate),
+            sample_rate,
+        }
+    }
+
+    /// Set LFO rate in Hz
+    pub fn set_lfo_rate(&mut self, rate: f32) {
+        self.lfo_rate = rate.max(0.01);
+    }
+
+    /// Set effect depth (0.0 - 1.0)
+    pub fn set_depth(&mut self, depth: f32) {
+        self.depth.set(depth.clamp(0.0, 1.0));
+    }
+
+    /// Set dry/wet mix (0.0 - 1.0)
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix.set(mix.clamp(0.0, 1.0));
+    }
+
+    /// Pan position of a voice from -1.0 (left) to 1.0 (right)
+    fn voice_pan(voice: usize) -> f32 {
+        voice as f32 / (CHORUS_VOICES - 1) as f32 * 2.0 - 1.0
+    }
+
+    /// Write a mono input sample and return the wet left and right voices
+    fn process_voices(&mut self, input: f32) -> (f32, f32) {
+        let mut wet_left = 0.0;
+        let mut wet_right = 0.0;
+        let depth = self.depth.next();
+
+        for voice in 0..CHORUS_VOICES {
+            // Sine LFO sweeps the voice around its centre delay
+            let lfo_value = (self.lfo_phases[voice] * 2.0 * PI).sin();
+            let delay = self.voice_delays[voice] + lfo_value * self.delay_range * depth;
+            let sample = read_interpolated(&self.buffer, self.write_index, delay);
+
+            // Equal power pan keeps the spread voices as loud as the centre one
+            let pan = Self::voice_pan(voice);
+            wet_left += sample * ((1.0 - pan) / 2.0).sqrt();
+            wet_right += sample * ((1.0 + pan) / 2.0).sqrt();
+
+            self.lfo_phases[voice] += self.lfo_rate / self.sample_rate as f32;
+            if self.lfo_phases[voice] >= 1.0 {
+                self.lfo_phases[voice] -= 1.0;
+            }
+        }
+
+        self.buffer[self.write_index] = input;
+        self.write_index = (self.write_index + 1) % self.buffer.len();
+
+        // Scale so the summed voices stay close to the level of the dry signal
+        let voice_gain = 2.0 / CHORUS_VOICES as f32;
+        (wet_left * voice_gain, wet_right * voice_gain)
+    }
+}
+
+impl AudioEffect for ChorusEffect {
+    fn process_sample(&mut self, input: f32) -> f32 {
+        let (wet_left, wet_right) = self.process_voices(input);
+        let wet = (wet_left + wet_right) * 0.5;
+        let mix = self.mix.next();
+
+        // Mix dry and wet signals
+        input * (1.0 - mix) + wet * mix
+    }
+
+    fn process_stereo(&mut self, left: f32, right: f32) -> (f32, f32) {
+        let (wet_left, wet_right) = self.process_voices((left + right) * 0.5);
+        let mix = self.mix.next();
+
+        // Mix dry and wet signals per channel, the dry signal keeps its own panning
+        (
+            left * (1.0 - mix) + wet_left * mix,
+            right * (1.0 - mix) + wet_right * mix,
+        )
+    }
+
+    fn snap(&mut self) {
+        self.depth.jump();
+        self.mix.jump();
+    }
+
+    fn reset(&mut self) {
+        self.buffer.fill(0.0);
+        self.write_index = 0;
+        for (i, phase) in self.lfo_phases.iter_mut().enumerate() {
+            *phase = i as f32 / CHORUS_VOICES as f32;
+        }
+    }
+
+    fn parameters(&self) -> Vec<EffectParameter> {
+        vec![
+            EffectParameter::new("RATE", self.lfo_rate, MIN_LFO_RATE_HZ, MAX_LFO_RATE_HZ, ParameterUnit::Hertz).with_exponential(),
+            EffectParameter::new("DEPTH", self.depth.target(), 0.0, 1.0, ParameterUnit::Percent),
+            EffectParameter::new("MIX", self.mix.target(), 0.0, 1.0, ParameterUnit::Percent),
+        ]
+    }
+
+    fn set_parameter(&mut self, index: usize, value: f32) {
+        match index {
+            0 => self.set_lfo_rate(value),
+            1 => self.set_depth(value),
+            2 => self.set_mix(value),
+            _ => {}
+        }
+    }
+
+    fn name(&self) -> &str {
+        "Chorus"
+    }
+}
@@ -0,0 +1,325 @@
+use super::{AudioEffect, EffectParameter, ParameterUnit, SmoothedValue};
+use std::fmt;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+/// Release time in ms of the level a track publishes for sidechain keying
+const SIDECHAIN_RELEASE_MS: f32 = 50.0;
+
+/// Averaging window in ms of the RMS detector
+const RMS_WINDOW_MS: f32 = 10.0;
+
+/// Ranges of the editor knobs
+const MIN_THRESHOLD_DB: f32 = -60.0;
+const MAX_RATIO: f32 = 20.0;
+const MIN_ATTACK_MS: f32 = 0.1;
+const MAX_ATTACK_MS: f32 = 200.0;
+const MIN_RELEASE_MS: f32 = 5.0;
+const MAX_RELEASE_MS: f32 = 2000.0;
+const MAX_KNEE_DB: f32 = 24.0;
+const MAX_MAKEUP_DB: f32 = 24.0;
+
+/// How the compressor measures the level it reacts to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Detection {
+    Peak,
+    Rms,
+}
+
+/// Implements the [Display] trait for [Detection] using short panel labels
+impl fmt::Display for Detection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Detection::Peak => write!(f, "PEAK"),
+            Detection::Rms => write!(f, "RMS"),
+        }
+    }
+}
+
+/// Level published by a track and the number of times it was published
+#[derive(Debug, Default)]
+struct KeyLevel {
+    level: AtomicU32,   // Bits of the most recent peak level
+    updates: AtomicU32, // Bumped on every publish, so readers notice when the track goes quiet
+}
+
+/// Output level of a track shared with the compressors keyed to it
+///
+/// The track's send publishes its level from the audio thread; every clone reads the same value.
+#[derive(Debug, Clone, Default)]
+pub struct SidechainLevel {
+    shared: Arc<KeyLevel>,
+}
+
+impl SidechainLevel {
+    /// Store the current level of the key signal
+    pub fn publish(&self, level: f32) {
+        self.shared.level.store(level.to_bits(), Ordering::Relaxed);
+        self.shared.updates.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Most recent level of the key signal
+    pub fn level(&self) -> f32 {
+        f32::from_bits(self.shared.level.load(Ordering::Relaxed))
+    }
+
+    /// Number of levels published so far, wrapping around
+    pub fn updates(&self) -> u32 {
+        self.shared.updates.load(Ordering::Relaxed)
+    }
+}
+
+/// Pass-through effect at the end of a track chain that publishes the track's level for sidechain keying
+#[derive(Debug, Clone)]
+pub struct SidechainSend {
+    level: SidechainLevel,
+    envelope: f32,
+    release_coefficient: f32,
+}
+
+impl SidechainSend {
+    pub fn new(level: SidechainLevel, sample_rate: u32) -> Self {
+        Self {
+            level,
+            envelope: 0.0,
+            release_coefficient: time_coefficient(SIDECHAIN_RELEASE_MS, sample_rate),
+        }
+    }
+
+    /// Follow the peak level with an instant attack and a short release
+    fn follow(&mut self, peak: f32) {
+        self.envelope = peak.max(self.envelope * self.release_coefficient);
+        self.level.publish(self.envelope);
+    }
+}
+
+impl AudioEffect for SidechainSend {
+    fn process_sample(&mut self, input: f32) -> f32 {
+        self.follow(input.abs());
+        input
+    }
+
+    fn process_stereo(&mut self, left: f32, right: f32) -> (f32, f32) {
+        self.follow(left.abs().max(right.abs()));
+        (left, right)
+    }
+
+    fn reset(&mut self) {
+        self.envelope = 0.0;
+        self.level.publish(0.0);
+    }
+
+    fn name(&self) -> &str {
+        "Sidechain Send"
+    }
+}
+
+/// A send that stops playing leaves no level behind for the compressors keyed to it
+impl Drop for SidechainSend {
+    fn drop(&mut self) {
+        self.level.publish(0.0);
+    }
+}
+
+/// Dynamic range compressor with soft knee and optional sidechain key
+#[derive(Debug, Clone)]
+pub struct CompressorEffect {
+    // Detector and gain smoothing state
+    mean_square: f32,
+    gain_reduction_db: f32,   // Current gain reduction, zero or negative
+
+    // Parameters
+    threshold_db: SmoothedValue, // Level above which the signal is compressed
+    ratio: SmoothedValue,        // Input/output ratio above the threshold (1.0 - 20.0)
+    attack_ms: f32,
+    release_ms: f32,
+    attack_coefficient: f32,
+    release_coefficient: f32,
+    knee_db: SmoothedValue,   // Width of the soft knee around the threshold
+    makeup_db: SmoothedValue, // Gain added after compression
+    detection: Detection,
+    sidechain: Option<SidechainLevel>, // Level of another track to react to instead of the input
+    sidechain_key: f32,                // Key level, decaying on its own while the key track publishes nothing
+    sidechain_updates: u32,            // Publish count of the key level when it was last read
+    sidechain_release_coefficient: f32,
+
+    rms_coefficient: f32,
+    sample_rate: u32,
+}
+
+impl CompressorEffect {
+    /// Create a new compressor effect with a 6 dB soft knee and peak detection
+    ///
+    /// # Parameters
+    /// - `threshold_db`: Threshold in dBFS (typically -40.0 - 0.0)
+    /// - `ratio`: Compression ratio (1.0 - 20.0)
+    /// - `attack_ms`: Time to reach the gain reduction in milliseconds
+    /// - `release_ms`: Time to recover from gain reduction in milliseconds
+    /// - `makeup_db`: Makeup gain in dB
+    /// - `sample_rate`: Audio sample rate
+    pub fn new(threshold_db: f32, ratio: f32, attack_ms: f32, release_ms: f32, makeup_db: f32, sample_rate: u32) -> Self {
+        Self {
+            mean_square: 0.0,
+            gain_reduction_db: 0.0,
+            threshold_db: SmoothedValue::new(threshold_db.min(0.0), sample_rate),
+            ratio: SmoothedValue::new(ratio.clamp(1.0, MAX_RATIO), sample_rate),
+            attack_ms,
+            release_ms,
+            attack_coefficient: time_coefficient(attack_ms, sample_rate),
+            release_coefficient: time_coefficient(release_ms, sample_rate),
+            knee_db: SmoothedValue::new(6.0, sample_rate),
+            makeup_db: SmoothedValue::new(makeup_db, sample_rate),
+            detection: Detection::Peak,
+            sidechain: None,
+            sidechain_key: 0.0,
+            sidechain_updates: 0,
+            sidechain_release_coefficient: time_coefficient(SIDECHAIN_RELEASE_MS, sample_rate),
+            rms_coefficient: time_coefficient(RMS_WINDOW_MS, sample_rate),
+            sample_rate,
+        }
+    }
+
+    /// Set the width of the soft knee in dB (0.0 = hard knee)
+    pub fn with_knee(mut self, knee_db: f32) -> Self {
+        self.knee_db = SmoothedValue::new(knee_db.max(0.0), self.sample_rate);
+        self
+    }
+
+    /// Measure the level with peak or RMS detection
+    pub fn with_detection(mut self, detection: Detection) -> Self {
+        self.detection = detection;
+        self
+    }
+
+    /// Set the attack time in milliseconds
+    pub fn set_attack(&mut self, attack_ms: f32) {
+        self.attack_ms = attack_ms.clamp(MIN_ATTACK_MS, MAX_ATTACK_MS);
+        self.attack_coefficient = time_coefficient(self.attack_ms, self.sample_rate);
+    }
+
+    /// Set the release time in milliseconds
+    pub fn set_release(&mut self, release_ms: f32) {
+        self.release_ms = release_ms.clamp(MIN_RELEASE_MS, MAX_RELEASE_MS);
+        self.release_coefficient = time_coefficient(self.release_ms, self.sample_rate);
+    }
+
+    /// Static gain reduction in dB for a detected level, with a quadratic soft knee
+    fn gain_computer(&mut self, level_db: f32) -> f32 {
+        let overshoot = level_db - self.threshold_db.next();
+        let slope = 1.0 / self.ratio.next() - 1.0;
+        let knee_db = self.knee_db.next();
+
+        if 2.0 * overshoot < -knee_db {
+            0.0
+        } else if knee_db > 0.0 && 2.0 * overshoot.abs() <= knee_db {
+            slope * (overshoot + knee_db / 2.0).powi(2) / (2.0 * knee_db)
+        } else {
+            slope * overshoot
+        }
+    }
+
+    /// Detect the key level and return the linear gain to apply for this sample
+    fn next_gain(&mut self, input_peak: f32) -> f32 {
+        let key = match &self.sidechain {
+            Some(sidechain) => {
+                // Follow the loudest of the key track's notes and fall back to silence when none publishes
+                let decayed = self.sidechain_key * self.sidechain_release_coefficient;
+                let updates = sidechain.updates();
+                self.sidechain_key = if updates != self.sidechain_updates {
+                    self.sidechain_updates = updates;
+                    sidechain.level().max(decayed)
+                } else {
+                    decayed
+                };
+                self.sidechain_key
+            }
+            None => input_peak,
+        };
+
+        let level = match self.detection {
+            Detection::Peak => key,
+            Detection::Rms => {
+                self.mean_square = key * key + self.rms_coefficient * (self.mean_square - key * key);
+                self.mean_square.sqrt()
+            }
+        };
+
+        // Attack while the reduction deepens, release while it recovers
+        let target = self.gain_computer(20.0 * level.max(1e-6).log10());
+        let coefficient = if target < self.gain_reduction_db {
+            self.attack_coefficient
+        } else {
+            self.release_coefficient
+        };
+        self.gain_reduction_db = target + coefficient * (self.gain_reduction_db - target);
+
+        10.0f32.powf((self.gain_reduction_db + self.makeup_db.next()) / 20.0)
+    }
+}
+
+impl AudioEffect for CompressorEffect {
+    fn process_sample(&mut self, input: f32) -> f32 {
+        input * self.next_gain(input.abs())
+    }
+
+    fn set_sidechain(&mut self, sidechain: Option<SidechainLevel>) {
+        self.sidechain = sidechain;
+    }
+
+    fn process_stereo(&mut self, left: f32, right: f32) -> (f32, f32) {
+        // Linked stereo: one gain for both channels so the image does not shift
+        let gain = self.next_gain(left.abs().max(right.abs()));
+        (left * gain, right * gain)
+    }
+
+    fn snap(&mut self) {
+        self.threshold_db.jump();
+        self.ratio.jump();
+        self.knee_db.jump();
+        self.makeup_db.jump();
+    }
+
+    fn reset(&mut self) {
+        self.mean_square = 0.0;
+        self.gain_reduction_db = 0.0;
+        self.sidechain_key = 0.0;
+    }
+
+    fn parameters(&self) -> Vec<EffectParameter> {
+        let detection_index = match self.detection {
+            Detection::Peak => 0,
+            Detection::Rms => 1,
+        };
+        vec![
+            EffectParameter::new("THRESH", self.threshold_db.target(), MIN_THRESHOLD_DB, 0.0, ParameterUnit::Decibels),
+            EffectParameter::new("RATIO", self.ratio.target(), 1.0, MAX_RATIO, ParameterUnit::Ratio).with_exponential(),
+            EffectParameter::new("ATTACK", self.attack_ms, MIN_ATTACK_MS, MAX_ATTACK_MS, ParameterUnit::Milliseconds).with_exponential(),
+            EffectParameter::new("RELEASE", self.release_ms, MIN_RELEASE_MS, MAX_RELEASE_MS, ParameterUnit::Milliseconds).with_exponential(),
+            EffectParameter::new("KNEE", self.knee_db.target(), 0.0, MAX_KNEE_DB, ParameterUnit::Decibels),
+            EffectParameter::new("MAKEUP", self.makeup_db.target(), 0.0, MAX_MAKEUP_DB, ParameterUnit::Decibels),
+            EffectParameter::choice("DETECT", detection_index, 2, self.detection),
+        ]
+    }
+
+    fn set_parameter(&mut self, index: usize, value: f32) {
+        match index {
+            0 => self.threshold_db.set(value.clamp(MIN_THRESHOLD_DB, 0.0)),
+            1 => self.ratio.set(value.clamp(1.0, MAX_RATIO)),
+            2 => self.set_attack(value),
+            3 => self.set_release(value),
+            4 => self.knee_db.set(value.clamp(0.0, MAX_KNEE_DB)),
+            5 => self.makeup_db.set(value.clamp(0.0, MAX_MAKEUP_DB)),
+            6 => self.detection = if value >= 0.5 { Detection::Rms } else { Detection::Peak },
+            _ => {}
+        }
+    }
+
+    fn name(&self) -> &str {
+        "Compressor"
+    }
+}
+
+/// One-pole smoothing coefficient reaching about 63% of a step in the given time
+pub(super) fn time_coefficient(time_ms: f32, sample_rate: u32) -> f32 {
+    (-1.0 / (time_ms.max(0.01) / 1000.0 * sample_rate as f32)).exp()
+}
@@ -0,0 +1,419 @@
+use super::fft::{fft, Complex};
+use super::{read_interpolated, AudioEffect, EffectParameter, ParameterUnit, SmoothedValue, DELAY_TIME_SMOOTHING_MS};
+use crate::modulation::random::Xorshift;
+use rodio::{Decoder, Source};
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Samples per partition; the wet signal lags the input by one partition
+const PARTITION_SIZE: usize = 256;
+/// FFT length used for each partition (zero-padded to twice the block)
+const FFT_SIZE: usize = PARTITION_SIZE * 2;
+/// Longest pre-delay the effect can hold
+const MAX_PRE_DELAY_MS: f32 = 250.0;
+/// Fade applied to the end of a trimmed impulse response to avoid a click, rounded up to
+/// whole partitions
+const TRIM_FADE_MS: f32 = 10.0;
+/// Shortest length the impulse response can be trimmed to
+const MIN_IR_TRIM_SECONDS: f32 = 0.05;
+
+/// Stereo impulse response used by the convolution reverb
+#[derive(Debug, Clone)]
+pub struct ImpulseResponse {
+    left: Vec<f32>,
+    right: Vec<f32>,
+    sample_rate: u32,
+}
+
+impl ImpulseResponse {
+    /// Load an impulse response from a WAV file
+    ///
+    /// Mono files are used for both channels; files with more than two channels keep the
+    /// first two.
+    pub fn load_wav<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = BufReader::new(File::open(path)?);
+        let decoder = Decoder::new(file).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let channels = decoder.channels().max(1) as usize;
+        let sample_rate = decoder.sample_rate();
+        let samples: Vec<f32> = decoder.convert_samples::<f32>().collect();
+
+        let frames = samples.len() / channels;
+        if frames == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "impulse response is empty"));
+        }
+
+        let left: Vec<f32> = (0..frames).map(|i| samples[i * channels]).collect();
+        let right: Vec<f32> = if channels > 1 {
+            (0..frames).map(|i| samples[i * channels + 1]).collect()
+        } else {
+            left.clone()
+        };
+
+        Ok(Self { left, right, sample_rate })
+    }
+
+    /// Generate a decaying stereo noise burst as a stand-in room response
+    ///
+    /// # Parameters
+    /// - `length_seconds`: Length of the tail
+    /// - `sample_rate`: Audio sample rate
+    pub fn synthetic(length_seconds: f32, sample_rate: u32) -> Self {
+        let length = ((length_seconds * sample_rate as f32) as usize).max(1);
+        // Reach -60 dB at the end of the tail
+        let decay = (0.001f32).ln() / length as f32;
+        let mut noise = Xorshift::new(0x2545_F491);
+
+        let mut left = Vec::with_capacity(length);
+        let mut right = Vec::with_capacity(length);
+        for i in 0..length {
+            let envelope = (decay * i as f32).exp();
+            left.push(noise.next_bipolar() * envelope);
+            right.push(noise.next_bipolar() * envelope);
+        }
+
+        Self { left, right, sample_rate }
+    }
+
+    /// The same response converted to `sample_rate`, so reverbs built from it at that rate
+    /// need no conversion of their own
+    pub fn resampled(&self, sample_rate: u32) -> Self {
+        if sample_rate == self.sample_rate {
+            return self.clone();
+        }
+        Self {
+            left: self.resample_channel(&self.left, sample_rate),
+            right: self.resample_channel(&self.right, sample_rate),
+            sample_rate,
+        }
+    }
+
+    /// Resample one channel to `sample_rate`
+    fn resample_channel(&self, channel: &[f32], sample_rate: u32) -> Vec<f32> {
+        let ratio = self.sample_rate as f32 / sample_rate as f32;
+        let length = ((channel.len() as f32 / ratio) as usize).max(1);
+
+        (0..length)
+            .map(|i| {
+                let position = i as f32 * ratio;
+                let index = position as usize;
+                let frac = position - index as f32;
+                let a = channel.get(index).copied().unwrap_or(0.0);
+                let b = channel.get(index + 1).copied().unwrap_or(0.0);
+                a + (b - a) * frac
+            })
+            .collect()
+    }
+}
+
+/// Split an impulse response into FFT_SIZE spectra of PARTITION_SIZE blocks
+fn partition(ir: &[f32]) -> Vec<Vec<Complex>> {
+    ir.chunks(PARTITION_SIZE)
+        .map(|chunk| {
+            let mut spectrum = vec![Complex::default(); FFT_SIZE];
+            for (bin, &sample) in spectrum.iter_mut().zip(chunk) {
+                bin.re = sample;
+            }
+            fft(&mut spectrum, false);
+            spectrum
+        })
+        .collect()
+}
+
+/// Convolution reverb using uniformly partitioned overlap-save FFT convolution
+///
+/// The input is convolved one PARTITION_SIZE block at a time against every partition of the
+/// impulse response through a frequency-domain delay line, so the cost per block grows
+/// with the IR length but no single block ever needs a full-length FFT.
+///
+/// The whole response is partitioned once; trimming it only changes how many partitions
+/// take part, so the length can be turned while the reverb is playing.
+#[derive(Debug, Clone)]
+pub struct ConvolutionReverbEffect {
+    // Partitioned IR spectra of the whole response, shared between clones of the effect
+    left_partitions: Arc<Vec<Vec<Complex>>>,
+    right_partitions: Arc<Vec<Vec<Complex>>>,
+    // Energy of the response up to and including each partition, for normalising a trim
+    partition_energy: Arc<Vec<f32>>,
+    active_partitions: usize,
+    previous_partitions: usize, // Partitions the last block was convolved with
+    fade_partitions: usize,
+    wet_gain: SmoothedValue,
+
+    // Frequency-domain delay line of past input block spectra, as long as the whole response
+    // so changing the trim never resizes it while playing
+    input_spectra: Vec<Vec<Complex>>,
+    spectrum_index: usize,
+
+    // Previous and current input block (overlap-save window)
+    input_window: Vec<f32>,
+    block_position: usize,
+    output_left: Vec<f32>,
+    output_right: Vec<f32>,
+    // Spectra the partitions are summed into before the inverse FFT, reused for every block
+    block_left: Vec<Complex>,
+    block_right: Vec<Complex>,
+
+    // Pre-delay line
+    pre_delay_buffer: Vec<f32>,
+    pre_delay_index: usize,
+    pre_delay_samples: SmoothedValue,
+
+    // Parameters
+    ir_trim_seconds: f32,
+    mix: SmoothedValue, // 0.0 - 1.0
+    sample_rate: u32,
+}
+
+impl ConvolutionReverbEffect {
+    /// Create a new convolution reverb effect
+    ///
+    /// # Parameters
+    /// - `impulse_response`: Room response to convolve with
+    /// - `pre_delay_ms`: Gap before the reverb tail starts
+    /// - `ir_trim_seconds`: Maximum length of the impulse response to use
+    /// - `mix`: Dry/wet mix (0.0 - 1.0)
+    /// - `sample_rate`: Audio sample rate
+    pub fn new(
+        impulse_response: ImpulseResponse,
+        pre_delay_ms: f32,
+        ir_trim_seconds: f32,
+        mix: f32,
+        sample_rate: u32,
+    ) -> Self {
+        // Two extra samples for the interpolated read at the longest pre-delay
+        let max_pre_delay = (MAX_PRE_DELAY_MS / 1000.0 * sample_rate as f32) as usize + 2;
+
+        let ImpulseResponse { left, right, .. } = impulse_response.resampled(sample_rate);
+        let partition_energy = left
+            .chunks(PARTITION_SIZE)
+            .zip(right.chunks(PARTITION_SIZE))
+            .scan(0.0, |energy, (left, right)| {
+                *energy += left.iter().chain(right.iter()).map(|s| s * s).sum::<f32>() / 2.0;
+                Some(*energy)
+            })
+            .collect();
+        let fade_partitions = (TRIM_FADE_MS / 1000.0 * sample_rate as f32 / PARTITION_SIZE as f32).ceil() as usize;
+
+        let left_partitions = partition(&left);
+        let input_spectra = vec![vec![Complex::default(); FFT_SIZE]; left_partitions.len()];
+
+        let mut effect = Self {
+            left_partitions: Arc::new(left_partitions),
+            right_partitions: Arc::new(partition(&right)),
+            partition_energy: Arc::new(partition_energy),
+            active_partitions: 0,
+            previous_partitions: 0,
+            fade_partitions,
+            wet_gain: SmoothedValue::new(0.0, sample_rate),
+            input_spectra,
+            spectrum_index: 0,
+            input_window: vec![0.0; FFT_SIZE],
+            block_position: 0,
+            output_left: vec![0.0; PARTITION_SIZE],
+            output_right: vec![0.0; PARTITION_SIZE],
+            block_left: vec![Complex::default(); FFT_SIZE],
+            block_right: vec![Complex::default(); FFT_SIZE],
+            pre_delay_buffer: vec![0.0; max_pre_delay],
+            pre_delay_index: 0,
+            pre_delay_samples: SmoothedValue::with_time(0.0, DELAY_TIME_SMOOTHING_MS, sample_rate),
+            ir_trim_seconds: 0.0,
+            mix: SmoothedValue::new(mix.clamp(0.0, 1.0), sample_rate),
+            sample_rate,
+        };
+        let pre_delay_samples = effect.pre_delay_to_samples(pre_delay_ms);
+        effect.pre_delay_samples = SmoothedValue::with_time(pre_delay_samples, DELAY_TIME_SMOOTHING_MS, sample_rate);
+        effect.set_ir_trim(ir_trim_seconds);
+        effect.previous_partitions = effect.active_partitions;
+        effect.wet_gain = SmoothedValue::new(effect.wet_gain.target(), sample_rate);
+        effect
+    }
+
+    /// Pre-delay in samples for a time in milliseconds, within the pre-delay line
+    fn pre_delay_to_samples(&self, pre_delay_ms: f32) -> f32 {
+        let samples = pre_delay_ms.clamp(0.0, MAX_PRE_DELAY_MS) / 1000.0 * self.sample_rate as f32;
+        samples.min((self.pre_delay_buffer.len() - 2) as f32)
+    }
+
+    /// Length of the whole impulse response in seconds
+    fn ir_length_seconds(&self) -> f32 {
+        (self.left_partitions.len() * PARTITION_SIZE) as f32 / self.sample_rate as f32
+    }
+
+    /// Set the pre-delay in milliseconds
+    pub fn set_pre_delay(&mut self, pre_delay_ms: f32) {
+        let samples = self.pre_delay_to_samples(pre_delay_ms);
+        self.pre_delay_samples.set(samples);
+    }
+
+    /// Set the maximum length of the impulse response to use, in seconds
+    ///
+    /// Input history is kept and the next block crossfades to the new length, so the tail
+    /// grows or shrinks without restarting or clicking.
+    pub fn set_ir_trim(&mut self, ir_trim_seconds: f32) {
+        self.ir_trim_seconds = ir_trim_seconds.clamp(MIN_IR_TRIM_SECONDS, self.ir_length_seconds().max(MIN_IR_TRIM_SECONDS));
+        let partitions = ((self.ir_trim_seconds * self.sample_rate as f32) as usize)
+            .div_ceil(PARTITION_SIZE)
+            .clamp(1, self.left_partitions.len());
+
+        // Normalise to unit energy so that long and short responses sit at a similar level
+        let energy = self.partition_energy[partitions - 1];
+        self.wet_gain.set(if energy > 0.0 { 1.0 / energy.sqrt() } else { 0.0 });
+
+        self.active_partitions = partitions;
+    }
+
+    /// Set dry/wet mix (0.0 - 1.0)
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix.set(mix.clamp(0.0, 1.0));
+    }
+
+    /// Gain of a partition, fading out the last ones of a response trimmed to `partitions`
+    fn partition_gain(&self, partition: usize, partitions: usize) -> f32 {
+        let remaining = partitions - partition;
+        if remaining > self.fade_partitions {
+            1.0
+        } else {
+            remaining as f32 / (self.fade_partitions + 1) as f32
+        }
+    }
+
+    /// Convolve the block that just filled up and refill the output buffers
+    fn process_block(&mut self) {
+        let length = self.input_spectra.len();
+
+        // Newest input spectrum goes at the head of the delay line
+        self.spectrum_index = (self.spectrum_index + length - 1) % length;
+        let spectrum = &mut self.input_spectra[self.spectrum_index];
+        for (bin, &sample) in spectrum.iter_mut().zip(self.input_window.iter()) {
+            *bin = Complex::new(sample, 0.0);
+        }
+        fft(spectrum, false);
+
+        // Overlap-save: only the second half of the circular result is valid
+        self.convolve(self.active_partitions);
+        for i in 0..PARTITION_SIZE {
+            self.output_left[i] = self.block_left[PARTITION_SIZE + i].re;
+            self.output_right[i] = self.block_right[PARTITION_SIZE + i].re;
+        }
+
+        // After a length change, fade from the old tail to the new one over this block
+        if self.previous_partitions != self.active_partitions {
+            self.convolve(self.previous_partitions);
+            for i in 0..PARTITION_SIZE {
+                let fade = (i + 1) as f32 / PARTITION_SIZE as f32;
+                let old_left = self.block_left[PARTITION_SIZE + i].re;
+                let old_right = self.block_right[PARTITION_SIZE + i].re;
+                self.output_left[i] = old_left + (self.output_left[i] - old_left) * fade;
+                self.output_right[i] = old_right + (self.output_right[i] - old_right) * fade;
+            }
+            self.previous_partitions = self.active_partitions;
+        }
+
+        // Slide the window so the current block becomes the previous one
+        self.input_window.copy_within(PARTITION_SIZE.., 0);
+    }
+
+    /// Convolve the input history with the first `partitions` partitions of the response,
+    /// leaving the circular result in `block_left` and `block_right`
+    fn convolve(&mut self, partitions: usize) {
+        let length = self.input_spectra.len();
+        self.block_left.fill(Complex::default());
+        self.block_right.fill(Complex::default());
+        for partition in 0..partitions {
+            let gain = self.partition_gain(partition, partitions);
+            let input = &self.input_spectra[(self.spectrum_index + partition) % length];
+            let left_ir = &self.left_partitions[partition];
+            let right_ir = &self.right_partitions[partition];
+            for bin in 0..FFT_SIZE {
+                let input = Complex::new(input[bin].re * gain, input[bin].im * gain);
+                self.block_left[bin] = self.block_left[bin] + input * left_ir[bin];
+                self.block_right[bin] = self.block_right[bin] + input * right_ir[bin];
+            }
+        }
+
+        fft(&mut self.block_left, true);
+        fft(&mut self.block_right, true);
+    }
+}
+
+impl AudioEffect for ConvolutionReverbEffect {
+    fn process_sample(&mut self, input: f32) -> f32 {
+        let (left, right) = self.process_stereo(input, input);
+        (left + right) * 0.5
+    }
+
+    fn process_stereo(&mut self, left: f32, right: f32) -> (f32, f32) {
+        let mid = (left + right) * 0.5;
+
+        // Pre-delay
+        let length = self.pre_delay_buffer.len();
+        self.pre_delay_buffer[self.pre_delay_index] = mid;
+        let delayed = read_interpolated(&self.pre_delay_buffer, self.pre_delay_index, self.pre_delay_samples.next());
+        self.pre_delay_index = (self.pre_delay_index + 1) % length;
+
+        // Output lags the input by one block while the next one fills
+        let wet_gain = self.wet_gain.next();
+        let wet_left = self.output_left[self.block_position] * wet_gain;
+        let wet_right = self.output_right[self.block_position] * wet_gain;
+        self.input_window[PARTITION_SIZE + self.block_position] = delayed;
+        self.block_position += 1;
+        if self.block_position == PARTITION_SIZE {
+            self.block_position = 0;
+            self.process_block();
+        }
+
+        let mix = self.mix.next();
+        (
+            left * (1.0 - mix) + wet_left * mix,
+            right * (1.0 - mix) + wet_right * mix,
+        )
+    }
+
+    fn snap(&mut self) {
+        self.pre_delay_samples.jump();
+        self.wet_gain.jump();
+        self.mix.jump();
+
+        // No earlier length left to crossfade from
+        self.previous_partitions = self.active_partitions;
+    }
+
+    fn reset(&mut self) {
+        for spectrum in &mut self.input_spectra {
+            spectrum.fill(Complex::default());
+        }
+        self.spectrum_index = 0;
+        self.input_window.fill(0.0);
+        self.block_position = 0;
+        self.output_left.fill(0.0);
+        self.output_right.fill(0.0);
+        self.pre_delay_buffer.fill(0.0);
+        self.pre_delay_index = 0;
+    }
+
+    fn parameters(&self) -> Vec<EffectParameter> {
+        let pre_delay_ms = self.pre_delay_samples.target() / self.sample_rate as f32 * 1000.0;
+        let ir_length = self.ir_length_seconds().max(MIN_IR_TRIM_SECONDS);
+        vec![
+            EffectParameter::new("PRE", pre_delay_ms, 0.0, MAX_PRE_DELAY_MS, ParameterUnit::Milliseconds),
+            EffectParameter::new("LENGTH", self.ir_trim_seconds.min(ir_length), MIN_IR_TRIM_SECONDS, ir_length, ParameterUnit::Seconds)
+                .with_exponential(),
+            EffectParameter::new("MIX", self.mix.target(), 0.0, 1.0, ParameterUnit::Percent),
+        ]
+    }
+
+    fn set_parameter(&mut self, index: usize, value: f32) {
+        match index {
+            0 => self.set_pre_delay(value),
+            1 => self.set_ir_trim(value),
+            2 => self.set_mix(value),
+            _ => {}
+        }
+    }
+
+    fn name(&self) -> &str {
+        "Convolution Reverb"
+    }
+}
@@ -1,127 +1,296 @@
-use super::AudioEffect;
+use super::{read_interpolated, AudioEffect, EffectParameter, ParameterUnit, SmoothedValue, DELAY_TIME_SMOOTHING_MS};
+use crate::modulation::lfo::{SyncDivision, TEMPO_LOWER_BOUND};
 
-/// Enhanced delay effect with multiple taps and filtering
+/// Default positions of the two extra taps as a fraction of the main delay time
+pub const DEFAULT_TAP_RATIOS: [f32; 2] = [0.618, 0.382]; // Golden ratio for musicality
+
+/// Range of the delay time knobs in milliseconds
+const MIN_DELAY_MS: f32 = 1.0;
+const MAX_DELAY_MS: f32 = 2000.0;
+
+/// Highest feedback amount; repeats still die away at the top of the knob
+const MAX_FEEDBACK: f32 = 0.95;
+
+/// Enhanced stereo delay effect with multiple taps, filtering and an optional ping-pong mode
+///
+/// The delay times glide to new values and are read between samples, so sweeping them
+/// bends the pitch of the repeats like a tape delay instead of clicking.
 #[derive(Debug, Clone)]
 pub struct DelayEffect {
-    buffer: Vec<f32>,
+    left_buffer: Vec<f32>,
+    right_buffer: Vec<f32>,
     write_index: usize,
-    delay_samples: usize,
-    feedback: f32,    // Amount of delayed signal fed back (0.0 - 0.99)
-    mix: f32,         // Dry/wet mix (0.0 = dry only, 1.0 = wet only)
+    left_delay: SmoothedValue,  // Delay time in samples
+    right_delay: SmoothedValue, // Delay time in samples
+    feedback: SmoothedValue,    // Amount of delayed signal fed back (0.0 - MAX_FEEDBACK)
+    mix: SmoothedValue,         // Dry/wet mix (0.0 = dry only, 1.0 = wet only)
     sample_rate: u32,
-    // Multiple delay taps for richer sound
-    tap1_samples: usize,
-    tap2_samples: usize,
-    // High-frequency damping filter
-    damping_filter: f32,
-    damping_coefficient: f32,
+    // Multiple delay taps for richer sound, as fractions of the main delay time
+    tap_ratios: [SmoothedValue; 2],
+    // High-frequency damping filter per channel
+    left_damping_filter: f32,
+    right_damping_filter: f32,
+    damping_coefficient: SmoothedValue, // 0.0 (bright repeats) - 0.99 (dark repeats)
+    // Stereo behaviour
+    ping_pong: bool,  // Repeats bounce between the left and right channel
+    sync: Option<(SyncDivision, SyncDivision)>, // Left and right note values when synced to the tempo
+    tempo_bpm: f32,
 }
 
 impl DelayEffect {
-    /// Create a new delay effect
-    /// 
+    /// Create a new delay effect with the same time on both channels
+    ///
     /// # Parameters
     /// - `delay_time_ms`: Delay time in milliseconds
-    /// - `feedback`: Feedback amount (0.0 - 0.99)
+    /// - `feedback`: Feedback amount (0.0 - 0.95)
     /// - `mix`: Dry/wet mix (0.0 - 1.0)
-    /// - `sample_rate`: Audio sample rate (e.g., 44100)
+    /// - `sample_rate`: Audio sample rate (e.g., 48000)
     pub fn new(delay_time_ms: f32, feedback: f32, mix: f32, sample_rate: u32) -> Self {
-        let delay_samples = ((delay_time_ms / 1000.0) * sample_rate as f32) as usize;
-        let buffer_size = delay_samples.max(1024); // Ensure minimum buffer size
-        
-        // Create multiple delay taps for stereo width and richness
-        let tap1_samples = (delay_samples as f32 * 0.618) as usize; // Golden ratio for musicality
-        let tap2_samples = (delay_samples as f32 * 0.382) as usize; // Complementary ratio
-        
+        let delay_samples = (delay_time_ms / 1000.0) * sample_rate as f32;
+        // Room for the whole knob range and the slowest note value at the slowest tempo, so
+        // neither turning the time nor changing the tempo ever has to grow the buffers
+        let longest_synced_ms = 60000.0 / TEMPO_LOWER_BOUND * SyncDivision::ALL[0].beats();
+        let buffer_size = (MAX_DELAY_MS.max(longest_synced_ms) / 1000.0 * sample_rate as f32) as usize + 2;
+        let smoothed = |value: f32| SmoothedValue::new(value, sample_rate);
+
         Self {
-            buffer: vec![0.0; buffer_size],
+            left_buffer: vec![0.0; buffer_size],
+            right_buffer: vec![0.0; buffer_size],
             write_index: 0,
-            delay_samples,
-            feedback: feedback.clamp(0.0, 0.95), // Slightly higher max feedback
-            mix: mix.clamp(0.0, 1.0),
+            left_delay: SmoothedValue::with_time(delay_samples, DELAY_TIME_SMOOTHING_MS, sample_rate),
+            right_delay: SmoothedValue::with_time(delay_samples, DELAY_TIME_SMOOTHING_MS, sample_rate),
+            feedback: smoothed(feedback.clamp(0.0, MAX_FEEDBACK)),
+            mix: smoothed(mix.clamp(0.0, 1.0)),
             sample_rate,
-            tap1_samples,
-            tap2_samples,
-            damping_filter: 0.0,
-            damping_coefficient: 0.3, // Gentle high-frequency roll-off
+            tap_ratios: DEFAULT_TAP_RATIOS.map(smoothed),
+            left_damping_filter: 0.0,
+            right_damping_filter: 0.0,
+            damping_coefficient: smoothed(0.3), // Gentle high-frequency roll-off
+            ping_pong: false,
+            sync: None,
+            tempo_bpm: 120.0,
         }
     }
-    
-    /// Update delay time in milliseconds
+
+    /// Bounce the repeats between the left and right channel
+    pub fn with_ping_pong(mut self, ping_pong: bool) -> Self {
+        self.ping_pong = ping_pong;
+        self
+    }
+
+    /// Follow the tempo with a note value per channel instead of a time in milliseconds
+    pub fn with_sync(mut self, left: SyncDivision, right: SyncDivision) -> Self {
+        self.sync = Some((left, right));
+        self
+    }
+
+    /// Update delay time in milliseconds on both channels
     pub fn set_delay_time(&mut self, delay_time_ms: f32) {
-        let new_delay_samples = ((delay_time_ms / 1000.0) * self.sample_rate as f32) as usize;
-        
-        if new_delay_samples != self.delay_samples {
-            self.delay_samples = new_delay_samples;
-            
-            // Update taps
-            self.tap1_samples = (new_delay_samples as f32 * 0.618) as usize;
-            self.tap2_samples = (new_delay_samples as f32 * 0.382) as usize;
-            
-            // Resize buffer if needed
-            if new_delay_samples >= self.buffer.len() {
-                self.buffer.resize(new_delay_samples + 1024, 0.0);
-            }
+        self.set_delay_times(delay_time_ms, delay_time_ms);
+    }
+
+    /// Update the left and right delay times in milliseconds, leaving tempo sync
+    pub fn set_delay_times(&mut self, left_ms: f32, right_ms: f32) {
+        self.sync = None;
+        self.apply_delay_times(left_ms, right_ms);
+    }
+
+    fn apply_delay_times(&mut self, left_ms: f32, right_ms: f32) {
+        // The buffers already hold the longest time, this runs on the audio thread and must not grow them
+        let longest = (self.left_buffer.len() - 2) as f32;
+        let to_samples = |ms: f32| ((ms / 1000.0) * self.sample_rate as f32).min(longest);
+        self.left_delay.set(to_samples(left_ms));
+        self.right_delay.set(to_samples(right_ms));
+    }
+
+    /// Recalculate the synced delay times for the current tempo
+    fn apply_sync(&mut self) {
+        if let Some((left, right)) = self.sync {
+            let beat_ms = 60000.0 / self.tempo_bpm.max(1.0);
+            self.apply_delay_times(beat_ms * left.beats(), beat_ms * right.beats());
         }
     }
-    
-    /// Set feedback amount (0.0 - 0.99)
+
+    /// Set feedback amount (0.0 - 0.95)
     pub fn set_feedback(&mut self, feedback: f32) {
-        self.feedback = feedback.clamp(0.0, 0.99);
+        self.feedback.set(feedback.clamp(0.0, MAX_FEEDBACK));
     }
-    
+
     /// Set dry/wet mix (0.0 - 1.0)
     pub fn set_mix(&mut self, mix: f32) {
-        self.mix = mix.clamp(0.0, 1.0);
+        self.mix.set(mix.clamp(0.0, 1.0));
+    }
+
+    /// Set the positions of the two extra taps as fractions (0.0 - 1.0) of the main delay time
+    pub fn set_tap_ratios(&mut self, ratios: [f32; 2]) {
+        for (tap_ratio, ratio) in self.tap_ratios.iter_mut().zip(ratios) {
+            tap_ratio.set(ratio.clamp(0.0, 1.0));
+        }
     }
-    
-    /// Read a sample from the delay buffer at a specific tap position
-    fn read_tap(&self, tap_samples: usize) -> f32 {
-        if tap_samples == 0 || tap_samples >= self.buffer.len() {
+
+    /// Set how much high end every repeat loses (0.0 - 0.99)
+    pub fn set_damping(&mut self, damping: f32) {
+        self.damping_coefficient.set(damping.clamp(0.0, 0.99));
+    }
+
+    /// Read a sample from a delay buffer a fractional number of samples back
+    fn read_tap(&self, buffer: &[f32], tap_samples: f32) -> f32 {
+        if tap_samples < 1.0 {
             return 0.0;
         }
-        
-        let read_index = if self.write_index >= tap_samples {
-            self.write_index - tap_samples
-        } else {
-            self.buffer.len() - (tap_samples - self.write_index)
-        };
-        
-        self.buffer[read_index % self.buffer.len()]
+        read_interpolated(buffer, self.write_index, tap_samples.min((buffer.len() - 2) as f32))
+    }
+
+    /// Mix the main tap and the two extra taps of one channel
+    fn read_channel(&self, buffer: &[f32], delay_samples: f32, tap_ratios: [f32; 2]) -> f32 {
+        let main_tap = self.read_tap(buffer, delay_samples);
+        let tap1 = self.read_tap(buffer, delay_samples * tap_ratios[0]);
+        let tap2 = self.read_tap(buffer, delay_samples * tap_ratios[1]);
+
+        // Mix the taps with different amplitudes
+        main_tap * 0.6 + tap1 * 0.25 + tap2 * 0.15
+    }
+
+    /// Time knob of one channel: a note value while synced, milliseconds otherwise
+    fn time_parameter(&self, label: &'static str, delay_samples: f32, division: Option<SyncDivision>) -> EffectParameter {
+        match division {
+            Some(division) => {
+                let index = SyncDivision::ALL.iter().position(|&d| d == division).unwrap_or(0);
+                EffectParameter::choice(label, index, SyncDivision::ALL.len(), division)
+            }
+            None => {
+                let delay_ms = delay_samples / self.sample_rate as f32 * 1000.0;
+                EffectParameter::new(label, delay_ms, MIN_DELAY_MS, MAX_DELAY_MS, ParameterUnit::Milliseconds).with_exponential()
+            }
+        }
+    }
+
+    /// Change the left (channel 0) or right time knob
+    fn set_time_parameter(&mut self, channel: usize, value: f32) {
+        match self.sync {
+            Some((left, right)) => {
+                let division = SyncDivision::ALL[(value as usize).min(SyncDivision::ALL.len() - 1)];
+                self.sync = Some(if channel == 0 { (division, right) } else { (left, division) });
+                self.apply_sync();
+            }
+            None => {
+                let value = value.clamp(MIN_DELAY_MS, MAX_DELAY_MS);
+                let to_ms = |samples: f32| samples / self.sample_rate as f32 * 1000.0;
+                let (left_ms, right_ms) = (to_ms(self.left_delay.target()), to_ms(self.right_delay.target()));
+                if channel == 0 {
+                    self.set_delay_times(value, right_ms);
+                } else {
+                    self.set_delay_times(left_ms, value);
+                }
+            }
+        }
     }
 }
 
 impl AudioEffect for DelayEffect {
     fn process_sample(&mut self, input: f32) -> f32 {
+        let (left, right) = self.process_stereo(input, input);
+        (left + right) * 0.5
+    }
+
+    fn process_stereo(&mut self, left: f32, right: f32) -> (f32, f32) {
+        let left_delay = self.left_delay.next();
+        let right_delay = self.right_delay.next();
+        let feedback = self.feedback.next();
+        let mix = self.mix.next();
+        let damping = self.damping_coefficient.next();
+        let tap_ratios = [self.tap_ratios[0].next(), self.tap_ratios[1].next()];
+
         // Read from multiple delay taps for richer sound
-        let main_tap = self.read_tap(self.delay_samples);
-        let tap1 = self.read_tap(self.tap1_samples);
-        let tap2 = self.read_tap(self.tap2_samples);
-        
-        // Mix the taps with different amplitudes
-        let wet_signal = main_tap * 0.6 + tap1 * 0.25 + tap2 * 0.15;
-        
+        let wet_left = self.read_channel(&self.left_buffer, left_delay, tap_ratios);
+        let wet_right = self.read_channel(&self.right_buffer, right_delay, tap_ratios);
+
         // Apply high-frequency damping to feedback
-        self.damping_filter = wet_signal * (1.0 - self.damping_coefficient) + 
-                            self.damping_filter * self.damping_coefficient;
-        
-        // Write new sample with damped feedback
-        self.buffer[self.write_index] = input + (self.damping_filter * self.feedback);
-        
+        self.left_damping_filter = wet_left * (1.0 - damping) + self.left_damping_filter * damping;
+        self.right_damping_filter = wet_right * (1.0 - damping) + self.right_damping_filter * damping;
+
+        // Write new samples with damped feedback; ping-pong feeds the input into the left
+        // line only and crosses the feedback so every repeat lands on the other side
+        let (left_in, right_in) = if self.ping_pong {
+            ((left + right) * 0.5 + self.right_damping_filter * feedback,
+             self.left_damping_filter * feedback)
+        } else {
+            (left + self.left_damping_filter * feedback,
+             right + self.right_damping_filter * feedback)
+        };
+        self.left_buffer[self.write_index] = left_in;
+        self.right_buffer[self.write_index] = right_in;
+
         // Advance write index (circular)
-        self.write_index = (self.write_index + 1) % self.buffer.len();
-        
+        self.write_index = (self.write_index + 1) % self.left_buffer.len();
+
         // Mix dry and wet signals
-        input * (1.0 - self.mix) + wet_signal * self.mix
+        (left * (1.0 - mix) + wet_left * mix,
+         right * (1.0 - mix) + wet_right * mix)
     }
-    
+
+    fn set_tempo(&mut self, tempo_bpm: f32) {
+        self.tempo_bpm = tempo_bpm;
+        self.apply_sync();
+    }
+
+    fn parameters(&self) -> Vec<EffectParameter> {
+        let (left_division, right_division) = match self.sync {
+            Some((left, right)) => (Some(left), Some(right)),
+            None => (None, None),
+        };
+
+        vec![
+            EffectParameter::switch("SYNC", self.sync.is_some()),
+            self.time_parameter("TIME L", self.left_delay.target(), left_division),
+            self.time_parameter("TIME R", self.right_delay.target(), right_division),
+            EffectParameter::new("FDBK", self.feedback.target(), 0.0, MAX_FEEDBACK, ParameterUnit::Percent),
+            EffectParameter::new("MIX", self.mix.target(), 0.0, 1.0, ParameterUnit::Percent),
+            EffectParameter::new("DAMP", self.damping_coefficient.target(), 0.0, 0.99, ParameterUnit::Percent),
+            EffectParameter::new("TAP 1", self.tap_ratios[0].target(), 0.0, 1.0, ParameterUnit::Percent),
+            EffectParameter::new("TAP 2", self.tap_ratios[1].target(), 0.0, 1.0, ParameterUnit::Percent),
+            EffectParameter::switch("PING", self.ping_pong),
+        ]
+    }
+
+    fn set_parameter(&mut self, index: usize, value: f32) {
+        match index {
+            // Syncing starts from quarter notes; going back to milliseconds keeps the last times
+            0 => {
+                self.sync = (value >= 0.5).then(|| self.sync.unwrap_or((SyncDivision::Quarter, SyncDivision::Quarter)));
+                self.apply_sync();
+            }
+            1 | 2 => self.set_time_parameter(index - 1, value),
+            3 => self.set_feedback(value),
+            4 => self.set_mix(value),
+            5 => self.set_damping(value),
+            6 => self.set_tap_ratios([value, self.tap_ratios[1].target()]),
+            7 => self.set_tap_ratios([self.tap_ratios[0].target(), value]),
+            8 => self.ping_pong = value >= 0.5,
+            _ => {}
+        }
+    }
+
+    fn snap(&mut self) {
+        self.left_delay.jump();
+        self.right_delay.jump();
+        self.feedback.jump();
+        self.mix.jump();
+        for ratio in &mut self.tap_ratios {
+            ratio.jump();
+        }
+        self.damping_coefficient.jump();
+    }
+
     fn reset(&mut self) {
-        self.buffer.fill(0.0);
+        self.left_buffer.fill(0.0);
+        self.right_buffer.fill(0.0);
         self.write_index = 0;
-        self.damping_filter = 0.0;
+        self.left_damping_filter = 0.0;
+        self.right_damping_filter = 0.0;
     }
-    
+
     fn name(&self) -> &str {
         "Delay"
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,204 @@
+use super::{AudioEffect, EffectParameter, ParameterUnit, SmoothedValue};
+use crate::filters::{FilterMode, FilterSlope, MultimodeFilter, StateVariableFilter};
+use std::fmt;
+
+/// Oversampling factor of the waveshaper
+pub const DISTORTION_OVERSAMPLING: usize = 4;
+
+/// Highest drive gain, reached at full drive
+const MAX_DRIVE_GAIN: f32 = 50.0;
+
+/// Tone control range of the post-distortion low-pass in Hz
+const MIN_TONE_HZ: f32 = 500.0;
+const MAX_TONE_HZ: f32 = 18000.0;
+
+/// Transfer curve of the waveshaper
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DistortionShape {
+    SoftClip,
+    HardClip,
+    Foldback,
+    Tube,     // Asymmetric soft clip adding even harmonics
+}
+
+impl DistortionShape {
+    /// All curves in the order the shape knob selects them
+    pub const ALL: [DistortionShape; 4] = [
+        DistortionShape::SoftClip,
+        DistortionShape::HardClip,
+        DistortionShape::Foldback,
+        DistortionShape::Tube,
+    ];
+
+    /// Shape one driven sample
+    fn apply(self, x: f32) -> f32 {
+        match self {
+            DistortionShape::SoftClip => x.tanh(),
+            DistortionShape::HardClip => x.clamp(-1.0, 1.0),
+            // Reflect everything beyond +/-1 back into range, folding again as the drive rises
+            DistortionShape::Foldback => ((x - 1.0).rem_euclid(4.0) - 2.0).abs() - 1.0,
+            // Biasing the curve clips the positive half earlier than the negative one
+            DistortionShape::Tube => (x + 0.3).tanh() - 0.3f32.tanh(),
+        }
+    }
+}
+
+/// Implements the [Display] trait for [DistortionShape] using short panel labels
+impl fmt::Display for DistortionShape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DistortionShape::SoftClip => write!(f, "SOFT"),
+            DistortionShape::HardClip => write!(f, "HARD"),
+            DistortionShape::Foldback => write!(f, "FOLD"),
+            DistortionShape::Tube => write!(f, "TUBE"),
+        }
+    }
+}
+
+/// Waveshaping distortion, run at a multiple of the sample rate so the added harmonics do not alias
+#[derive(Debug, Clone)]
+pub struct DistortionEffect {
+    shape: DistortionShape,
+
+    // Anti-imaging filter after zero stuffing and anti-aliasing filter before decimation
+    upsample_filter: MultimodeFilter,
+    downsample_filter: MultimodeFilter,
+    tone_filter: StateVariableFilter,
+
+    // DC blocker for the asymmetric tube curve
+    dc_input: f32,
+    dc_output: f32,
+
+    // Parameters
+    drive: SmoothedValue, // Drive amount (0.0 - 1.0)
+    tone: SmoothedValue,  // Tone amount (0.0 = dark, 1.0 = bright)
+    mix: SmoothedValue,   // Dry/wet mix (0.0 - 1.0)
+
+    sample_rate: u32,
+}
+
+impl DistortionEffect {
+    /// Create a new distortion effect
+    ///
+    /// # Parameters
+    /// - `shape`: Waveshaping curve
+    /// - `drive`: Drive amount (0.0 - 1.0), from unity gain up to 50x into the curve
+    /// - `tone`: Brightness of the distorted signal (0.0 - 1.0)
+    /// - `mix`: Dry/wet mix (0.0 - 1.0)
+    /// - `sample_rate`: Audio sample rate
+    pub fn new(shape: DistortionShape, drive: f32, tone: f32, mix: f32, sample_rate: u32) -> Self {
+        // Both filters cut just below the original Nyquist frequency at the oversampled rate
+        let oversampled_rate = sample_rate * DISTORTION_OVERSAMPLING as u32;
+        let anti_alias_cutoff = sample_rate as f32 * 0.45;
+        let tone = tone.clamp(0.0, 1.0);
+
+        Self {
+            shape,
+            upsample_filter: MultimodeFilter::new(FilterMode::LowPass, FilterSlope::Db24, anti_alias_cutoff, 0.0, oversampled_rate),
+            downsample_filter: MultimodeFilter::new(FilterMode::LowPass, FilterSlope::Db24, anti_alias_cutoff, 0.0, oversampled_rate),
+            tone_filter: StateVariableFilter::new(FilterMode::LowPass, Self::tone_cutoff(tone), 0.0, sample_rate),
+            dc_input: 0.0,
+            dc_output: 0.0,
+            drive: SmoothedValue::new(drive.clamp(0.0, 1.0), sample_rate),
+            tone: SmoothedValue::new(tone, sample_rate),
+            mix: SmoothedValue::new(mix.clamp(0.0, 1.0), sample_rate),
+            sample_rate,
+        }
+    }
+
+    /// Set the waveshaping curve
+    pub fn set_shape(&mut self, shape: DistortionShape) {
+        self.shape = shape;
+    }
+
+    /// Set drive amount (0.0 - 1.0)
+    pub fn set_drive(&mut self, drive: f32) {
+        self.drive.set(drive.clamp(0.0, 1.0));
+    }
+
+    /// Set brightness of the distorted signal (0.0 - 1.0)
+    pub fn set_tone(&mut self, tone: f32) {
+        self.tone.set(tone.clamp(0.0, 1.0));
+    }
+
+    /// Set dry/wet mix (0.0 - 1.0)
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix.set(mix.clamp(0.0, 1.0));
+    }
+
+    /// Tone low-pass cutoff in Hz, exponential like the filter cutoff knob
+    fn tone_cutoff(tone: f32) -> f32 {
+        MIN_TONE_HZ * (MAX_TONE_HZ / MIN_TONE_HZ).powf(tone)
+    }
+
+    /// Gain applied before the curve
+    fn drive_gain(drive: f32) -> f32 {
+        MAX_DRIVE_GAIN.powf(drive)
+    }
+}
+
+impl AudioEffect for DistortionEffect {
+    fn process_sample(&mut self, input: f32) -> f32 {
+        let gain = Self::drive_gain(self.drive.next());
+
+        // Zero stuff to the oversampled rate, shape every sub-sample and keep the last filtered one
+        let mut shaped = 0.0;
+        for sub_sample in 0..DISTORTION_OVERSAMPLING {
+            let stuffed = if sub_sample == 0 { input * DISTORTION_OVERSAMPLING as f32 } else { 0.0 };
+            let upsampled = self.upsample_filter.process_sample(stuffed);
+            let distorted = self.shape.apply(upsampled * gain);
+            shaped = self.downsample_filter.process_sample(distorted);
+        }
+
+        // Remove the DC offset left by asymmetric curves
+        let blocked = shaped - self.dc_input + 0.995 * self.dc_output;
+        self.dc_input = shaped;
+        self.dc_output = blocked;
+
+        // The tone filter only needs new coefficients while its knob glides
+        if self.tone.is_gliding() {
+            self.tone_filter.set_cutoff(Self::tone_cutoff(self.tone.next()));
+        }
+        let wet = self.tone_filter.process_sample(blocked);
+
+        // Mix dry and wet signals
+        let mix = self.mix.next();
+        input * (1.0 - mix) + wet * mix
+    }
+
+    fn snap(&mut self) {
+        self.drive.jump();
+        self.tone.jump();
+        self.mix.jump();
+        self.tone_filter.set_cutoff(Self::tone_cutoff(self.tone.target()));
+    }
+
+    fn reset(&mut self) {
+        // Rebuilding the filters clears their state without touching the parameters
+        *self = Self::new(self.shape, self.drive.target(), self.tone.target(), self.mix.target(), self.sample_rate);
+    }
+
+    fn parameters(&self) -> Vec<EffectParameter> {
+        let shape_index = DistortionShape::ALL.iter().position(|&shape| shape == self.shape).unwrap_or(0);
+        vec![
+            EffectParameter::choice("SHAPE", shape_index, DistortionShape::ALL.len(), self.shape),
+            EffectParameter::new("DRIVE", self.drive.target(), 0.0, 1.0, ParameterUnit::Percent),
+            EffectParameter::new("TONE", self.tone.target(), 0.0, 1.0, ParameterUnit::Percent),
+            EffectParameter::new("MIX", self.mix.target(), 0.0, 1.0, ParameterUnit::Percent),
+        ]
+    }
+
+    fn set_parameter(&mut self, index: usize, value: f32) {
+        match index {
+            0 => self.set_shape(DistortionShape::ALL[(value as usize).min(DistortionShape::ALL.len() - 1)]),
+            1 => self.set_drive(value),
+            2 => self.set_tone(value),
+            3 => self.set_mix(value),
+            _ => {}
+        }
+    }
+
+    fn name(&self) -> &str {
+        "Distortion"
+    }
+}
@@ -0,0 +1,190 @@
+use super::{AudioEffect, EffectParameter, ParameterUnit, SmoothedValue};
+use crate::filters::{Biquad, BiquadShape};
+
+/// Number of bands of the parametric EQ
+pub const EQ_BANDS: usize = 4;
+
+/// Lowest and highest frequency drawn on the EQ response curve
+pub const EQ_MIN_HZ: f32 = 20.0;
+pub const EQ_MAX_HZ: f32 = 20000.0;
+
+/// Largest boost or cut of a band in dB
+pub const EQ_MAX_GAIN_DB: f32 = 18.0;
+
+/// Range of the band Q knobs
+const MIN_Q: f32 = 0.1;
+const MAX_Q: f32 = 10.0;
+
+/// Editor labels of the bands, from the low shelf up
+const BAND_LABELS: [[&str; 3]; EQ_BANDS] = [
+    ["LO F", "LO G", "LO Q"],
+    ["LM F", "LM G", "LM Q"],
+    ["HM F", "HM G", "HM Q"],
+    ["HI F", "HI G", "HI Q"],
+];
+
+/// Settings of one EQ band
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EqBand {
+    pub shape: BiquadShape,
+    pub frequency: f32, // Hz
+    pub gain_db: f32,   // -18.0 - 18.0
+    pub q: f32,         // Bandwidth of a peak, steepness of a shelf
+}
+
+/// Four band parametric EQ: low shelf, two peaking bands and a high shelf
+#[derive(Debug, Clone)]
+pub struct ParametricEqEffect {
+    bands: [EqBand; EQ_BANDS],
+
+    // Log2 frequency, gain and Q of every band, gliding towards `bands`
+    smoothed: [[SmoothedValue; 3]; EQ_BANDS],
+
+    // One filter per band and channel, so stereo material keeps separate filter states
+    left_filters: [Biquad; EQ_BANDS],
+    right_filters: [Biquad; EQ_BANDS],
+
+    sample_rate: u32,
+}
+
+impl ParametricEqEffect {
+    /// Create a new EQ with the bands at 100 Hz (low shelf), 400 Hz, 2.5 kHz (peaks) and 8 kHz (high shelf)
+    ///
+    /// # Parameters
+    /// - `gains_db`: Boost or cut of every band in dB, from the low shelf up
+    /// - `sample_rate`: Audio sample rate
+    pub fn new(gains_db: [f32; EQ_BANDS], sample_rate: u32) -> Self {
+        let layout = [
+            (BiquadShape::LowShelf, 100.0, 0.707),
+            (BiquadShape::Peaking, 400.0, 1.0),
+            (BiquadShape::Peaking, 2500.0, 1.0),
+            (BiquadShape::HighShelf, 8000.0, 0.707),
+        ];
+        let bands = std::array::from_fn(|i| EqBand {
+            shape: layout[i].0,
+            frequency: layout[i].1,
+            gain_db: gains_db[i].clamp(-EQ_MAX_GAIN_DB, EQ_MAX_GAIN_DB),
+            q: layout[i].2,
+        });
+        let filters = Self::band_filters(&bands, sample_rate);
+        let smoothed = bands.map(|band: EqBand| {
+            [band.frequency.log2(), band.gain_db, band.q].map(|value| SmoothedValue::new(value, sample_rate))
+        });
+
+        Self {
+            bands,
+            smoothed,
+            left_filters: filters.clone(),
+            right_filters: filters,
+            sample_rate,
+        }
+    }
+
+    fn band_filters(bands: &[EqBand; EQ_BANDS], sample_rate: u32) -> [Biquad; EQ_BANDS] {
+        std::array::from_fn(|i| {
+            let band = bands[i];
+            Biquad::new(band.shape, band.frequency, band.gain_db, band.q, sample_rate)
+        })
+    }
+
+    /// Settings of every band, from the low shelf up
+    pub fn bands(&self) -> &[EqBand; EQ_BANDS] {
+        &self.bands
+    }
+
+    /// Change the settings of one band, keeping its shape; the band glides to them while processing
+    pub fn set_band(&mut self, index: usize, frequency: f32, gain_db: f32, q: f32) {
+        let Some(band) = self.bands.get_mut(index) else {
+            return;
+        };
+        let nyquist = self.sample_rate as f32 / 2.0;
+        band.frequency = frequency.clamp(EQ_MIN_HZ, EQ_MAX_HZ.min(nyquist));
+        band.gain_db = gain_db.clamp(-EQ_MAX_GAIN_DB, EQ_MAX_GAIN_DB);
+        band.q = q.clamp(MIN_Q, MAX_Q);
+
+        let targets = [band.frequency.log2(), band.gain_db, band.q];
+        for (value, target) in self.smoothed[index].iter_mut().zip(targets) {
+            value.set(target);
+        }
+    }
+
+    /// Move every gliding band one sample closer to its settings
+    fn glide_bands(&mut self) {
+        for (i, values) in self.smoothed.iter_mut().enumerate() {
+            if values.iter().all(|value| !value.is_gliding()) {
+                continue;
+            }
+            let [frequency, gain_db, q] = values.each_mut().map(|value| value.next());
+            let shape = self.bands[i].shape;
+            for filter in [&mut self.left_filters[i], &mut self.right_filters[i]] {
+                filter.set_parameters(shape, frequency.exp2(), gain_db, q);
+            }
+        }
+    }
+
+    /// Combined gain of all bands in dB at a frequency, for the band settings rather than a glide in progress
+    pub fn response_db(&self, frequency: f32) -> f32 {
+        Self::band_filters(&self.bands, self.sample_rate).iter().map(|filter| filter.magnitude_db(frequency)).sum()
+    }
+}
+
+impl AudioEffect for ParametricEqEffect {
+    fn process_sample(&mut self, input: f32) -> f32 {
+        self.glide_bands();
+        self.left_filters.iter_mut().fold(input, |sample, filter| filter.process_sample(sample))
+    }
+
+    fn process_stereo(&mut self, left: f32, right: f32) -> (f32, f32) {
+        self.glide_bands();
+        let left = self.left_filters.iter_mut().fold(left, |sample, filter| filter.process_sample(sample));
+        let right = self.right_filters.iter_mut().fold(right, |sample, filter| filter.process_sample(sample));
+        (left, right)
+    }
+
+    fn snap(&mut self) {
+        for (i, values) in self.smoothed.iter_mut().enumerate() {
+            for value in values.iter_mut() {
+                value.jump();
+            }
+            let band = &self.bands[i];
+            for filter in [&mut self.left_filters[i], &mut self.right_filters[i]] {
+                filter.set_parameters(band.shape, band.frequency, band.gain_db, band.q);
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        for filter in self.left_filters.iter_mut().chain(self.right_filters.iter_mut()) {
+            filter.reset();
+        }
+    }
+
+    /// Band frequencies, then gains, then Qs, so the editor shows one band per column
+    fn parameters(&self) -> Vec<EffectParameter> {
+        let frequencies = self.bands.iter().zip(BAND_LABELS.iter()).map(|(band, labels)| {
+            EffectParameter::new(labels[0], band.frequency, EQ_MIN_HZ, EQ_MAX_HZ, ParameterUnit::Hertz).with_exponential()
+        });
+        let gains = self.bands.iter().zip(BAND_LABELS.iter()).map(|(band, labels)| {
+            EffectParameter::new(labels[1], band.gain_db, -EQ_MAX_GAIN_DB, EQ_MAX_GAIN_DB, ParameterUnit::Decibels)
+        });
+        let qs = self.bands.iter().zip(BAND_LABELS.iter()).map(|(band, labels)| {
+            EffectParameter::new(labels[2], band.q, MIN_Q, MAX_Q, ParameterUnit::Number).with_exponential()
+        });
+        frequencies.chain(gains).chain(qs).collect()
+    }
+
+    fn set_parameter(&mut self, index: usize, value: f32) {
+        let (setting, band_index) = (index / EQ_BANDS, index % EQ_BANDS);
+        let band = self.bands[band_index];
+        match setting {
+            0 => self.set_band(band_index, value, band.gain_db, band.q),
+            1 => self.set_band(band_index, band.frequency, value, band.q),
+            2 => self.set_band(band_index, band.frequency, band.gain_db, value),
+            _ => {}
+        }
+    }
+
+    fn name(&self) -> &str {
+        "EQ"
+    }
+}
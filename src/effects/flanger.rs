@@ -1,4 +1,5 @@
-use super::AudioEffect;
+use super::{read_interpolated, AudioEffect, EffectParameter, ParameterUnit, SmoothedValue};
+use crate::modulation::lfo::{MAX_LFO_RATE_HZ, MIN_LFO_RATE_HZ};
 use std::f32::consts::PI;
 
 /// Flanger effect using modulated delay line
@@ -14,9 +15,9 @@ pub struct FlangerEffect {
     // Parameters
     delay_base: f32,      // Base delay time in samples
     delay_range: f32,     // Modulation range in samples
-    depth: f32,           // Effect depth (0.0 - 1.0)
-    feedback: f32,        // Feedback amount (0.0 - 0.99)
-    mix: f32,             // Dry/wet mix (0.0 - 1.0)
+    depth: SmoothedValue,    // Effect depth (0.0 - 1.0)
+    feedback: SmoothedValue, // Feedback amount (0.0 - 0.99)
+    mix: SmoothedValue,      // Dry/wet mix (0.0 - 1.0)
     
     sample_rate: u32,
 }
@@ -45,41 +46,16 @@ impl FlangerEffect {
             lfo_rate: lfo_rate.max(0.01), // Prevent division by zero
             delay_base,
             delay_range,
-            depth: depth.clamp(0.0, 1.0),
-            feedback: feedback.clamp(0.0, 0.99),
-            mix: mix.clamp(0.0, 1.0),
+            depth: SmoothedValue::new(depth.clamp(0.0, 1.0), sample_rate),
+            feedback: SmoothedValue::new(feedback.clamp(0.0, 0.99), sample_rate),
+            mix: SmoothedValue::new(mix.clamp(0.0, 1.0), sample_rate),
             sample_rate,
         }
     }
     
-    /// Linear interpolation between two values
-    fn lerp(&self, a: f32, b: f32, t: f32) -> f32 {
-        a + t * (b - a)
-    }
-    
     /// Get interpolated sample from delay buffer
     fn get_delayed_sample(&self, delay_samples: f32) -> f32 {
-        let delay_int = delay_samples as usize;
-        let delay_frac = delay_samples - delay_int as f32;
-        
-        // Calculate read positions (circular buffer)
-        let read_index1 = if self.write_index >= delay_int {
-            self.write_index - delay_int
-        } else {
-            self.buffer.len() - (delay_int - self.write_index)
-        };
-        
-        let read_index2 = if read_index1 == 0 {
-            self.buffer.len() - 1
-        } else {
-            read_index1 - 1
-        };
-        
-        // Linear interpolation between adjacent samples
-        let sample1 = self.buffer[read_index1 % self.buffer.len()];
-        let sample2 = self.buffer[read_index2 % self.buffer.len()];
-        
-        self.lerp(sample1, sample2, delay_frac)
+        read_interpolated(&self.buffer, self.write_index, delay_samples)
     }
     
     /// Set LFO rate in Hz
@@ -89,17 +65,17 @@ impl FlangerEffect {
     
     /// Set effect depth (0.0 - 1.0)
     pub fn set_depth(&mut self, depth: f32) {
-        self.depth = depth.clamp(0.0, 1.0);
+        self.depth.set(depth.clamp(0.0, 1.0));
     }
     
     /// Set feedback amount (0.0 - 0.99)
     pub fn set_feedback(&mut self, feedback: f32) {
-        self.feedback = feedback.clamp(0.0, 0.99);
+        self.feedback.set(feedback.clamp(0.0, 0.99));
     }
     
     /// Set dry/wet mix (0.0 - 1.0)
     pub fn set_mix(&mut self, mix: f32) {
-        self.mix = mix.clamp(0.0, 1.0);
+        self.mix.set(mix.clamp(0.0, 1.0));
     }
 }
 
@@ -109,14 +85,14 @@ impl AudioEffect for FlangerEffect {
         let lfo_value = (self.lfo_phase * 2.0 * PI).sin();
         
         // Calculate modulated delay time
-        let delay_offset = (lfo_value * 0.5 + 0.5) * self.delay_range * self.depth;
+        let delay_offset = (lfo_value * 0.5 + 0.5) * self.delay_range * self.depth.next();
         let total_delay = self.delay_base + delay_offset;
         
         // Get delayed sample with interpolation
         let delayed_sample = self.get_delayed_sample(total_delay);
         
         // Write input + feedback to buffer
-        self.buffer[self.write_index] = input + delayed_sample * self.feedback;
+        self.buffer[self.write_index] = input + delayed_sample * self.feedback.next();
         
         // Advance write index (circular)
         self.write_index = (self.write_index + 1) % self.buffer.len();
@@ -128,14 +104,40 @@ impl AudioEffect for FlangerEffect {
         }
         
         // Mix dry and wet signals
-        input * (1.0 - self.mix) + delayed_sample * self.mix
+        let mix = self.mix.next();
+        input * (1.0 - mix) + delayed_sample * mix
     }
     
+    fn snap(&mut self) {
+        self.depth.jump();
+        self.feedback.jump();
+        self.mix.jump();
+    }
+
     fn reset(&mut self) {
         self.buffer.fill(0.0);
         self.write_index = 0;
         self.lfo_phase = 0.0;
     }
+
+    fn parameters(&self) -> Vec<EffectParameter> {
+        vec![
+            EffectParameter::new("RATE", self.lfo_rate, MIN_LFO_RATE_HZ, MAX_LFO_RATE_HZ, ParameterUnit::Hertz).with_exponential(),
+            EffectParameter::new("DEPTH", self.depth.target(), 0.0, 1.0, ParameterUnit::Percent),
+            EffectParameter::new("FDBK", self.feedback.target(), 0.0, 0.99, ParameterUnit::Percent),
+            EffectParameter::new("MIX", self.mix.target(), 0.0, 1.0, ParameterUnit::Percent),
+        ]
+    }
+
+    fn set_parameter(&mut self, index: usize, value: f32) {
+        match index {
+            0 => self.set_lfo_rate(value),
+            1 => self.set_depth(value),
+            2 => self.set_feedback(value),
+            3 => self.set_mix(value),
+            _ => {}
+        }
+    }
     
     fn name(&self) -> &str {
         "Flanger"
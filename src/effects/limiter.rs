@@ -0,0 +1,195 @@
+use super::compressor::time_coefficient;
+use super::{AudioEffect, EffectParameter, ParameterUnit, SmoothedValue};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// How far ahead the limiter looks; the output lags the input by this much
+const LOOKAHEAD_MS: f32 = 5.0;
+
+/// Ranges of the editor knobs
+const MIN_CEILING_DB: f32 = -12.0;
+const MIN_RELEASE_MS: f32 = 10.0;
+const MAX_RELEASE_MS: f32 = 1000.0;
+
+/// Overload flag shared between the master limiter and the clip light
+///
+/// The limiter raises it from the audio thread whenever its input goes past full scale;
+/// every clone raises the same flag.
+#[derive(Debug, Clone, Default)]
+pub struct ClipIndicator {
+    clipped: Arc<AtomicBool>,
+}
+
+impl ClipIndicator {
+    /// Record that the signal went past full scale
+    fn mark(&self) {
+        self.clipped.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether the signal went past full scale since the last call, clearing the flag
+    pub fn take(&self) -> bool {
+        self.clipped.swap(false, Ordering::Relaxed)
+    }
+}
+
+/// Look-ahead brickwall limiter that keeps every output sample at or below the ceiling
+///
+/// The input is delayed by LOOKAHEAD_MS while the gain needed for the loudest sample in that
+/// window is found, then the gain ramps down over the window so it has fully reached the
+/// required reduction when the peak comes out. Both channels share one gain.
+#[derive(Debug, Clone)]
+pub struct LimiterEffect {
+    // Look-ahead delay line
+    left_buffer: Vec<f32>,
+    right_buffer: Vec<f32>,
+    write_index: usize,
+
+    // Gain each sample in the look-ahead window needs, as (sample number, gain) with rising gains
+    required_gains: VecDeque<(u64, f32)>,
+    // Running average of the window minimum, which turns steps into ramps
+    ramp_buffer: Vec<f32>,
+    ramp_sum: f64,
+    sample_count: u64,
+    gain: f32, // Gain applied to the current output sample
+
+    // Parameters
+    ceiling_db: SmoothedValue, // Highest output level in dBFS
+    release_ms: f32,
+    release_coefficient: f32,
+    clip_indicator: ClipIndicator,
+    sample_rate: u32,
+}
+
+impl LimiterEffect {
+    /// Create a new limiter
+    ///
+    /// # Parameters
+    /// - `ceiling_db`: Highest output level in dBFS (-12.0 - 0.0)
+    /// - `release_ms`: Time to recover from gain reduction in milliseconds
+    /// - `clip_indicator`: Flag raised whenever the input goes past full scale
+    /// - `sample_rate`: Audio sample rate
+    pub fn new(ceiling_db: f32, release_ms: f32, clip_indicator: ClipIndicator, sample_rate: u32) -> Self {
+        let lookahead = ((LOOKAHEAD_MS / 1000.0 * sample_rate as f32) as usize).max(1);
+        let release_ms = release_ms.clamp(MIN_RELEASE_MS, MAX_RELEASE_MS);
+
+        Self {
+            left_buffer: vec![0.0; lookahead],
+            right_buffer: vec![0.0; lookahead],
+            write_index: 0,
+            required_gains: VecDeque::with_capacity(lookahead + 1),
+            ramp_buffer: vec![1.0; lookahead],
+            ramp_sum: lookahead as f64,
+            sample_count: 0,
+            gain: 1.0,
+            ceiling_db: SmoothedValue::new(ceiling_db.clamp(MIN_CEILING_DB, 0.0), sample_rate),
+            release_ms,
+            release_coefficient: time_coefficient(release_ms, sample_rate),
+            clip_indicator,
+            sample_rate,
+        }
+    }
+
+    /// Set the highest output level in dBFS
+    pub fn set_ceiling(&mut self, ceiling_db: f32) {
+        self.ceiling_db.set(ceiling_db.clamp(MIN_CEILING_DB, 0.0));
+    }
+
+    /// Set the release time in milliseconds
+    pub fn set_release(&mut self, release_ms: f32) {
+        self.release_ms = release_ms.clamp(MIN_RELEASE_MS, MAX_RELEASE_MS);
+        self.release_coefficient = time_coefficient(self.release_ms, self.sample_rate);
+    }
+
+    /// Take in the peak of the newest frame and return the gain for the frame leaving the delay line
+    fn next_gain(&mut self, peak: f32, ceiling: f32) -> f32 {
+        if peak > 1.0 {
+            self.clip_indicator.mark();
+        }
+
+        // Keep the smallest gain any sample in the look-ahead window needs at the front
+        let lookahead = self.ramp_buffer.len() as u64;
+        let required = if peak > ceiling { ceiling / peak } else { 1.0 };
+        while self.required_gains.back().is_some_and(|&(_, gain)| gain >= required) {
+            self.required_gains.pop_back();
+        }
+        self.required_gains.push_back((self.sample_count, required));
+        while self.required_gains.front().is_some_and(|&(sample, _)| sample + lookahead < self.sample_count) {
+            self.required_gains.pop_front();
+        }
+        let window_minimum = self.required_gains.front().map_or(1.0, |&(_, gain)| gain);
+
+        // Averaging the minimum over the window ramps the gain down to it by the time the peak is output
+        let index = (self.sample_count % lookahead) as usize;
+        self.ramp_sum += window_minimum as f64 - self.ramp_buffer[index] as f64;
+        self.ramp_buffer[index] = window_minimum;
+        self.sample_count += 1;
+        let ramped = (self.ramp_sum / lookahead as f64) as f32;
+
+        // Follow reductions at once and recover with the release time
+        self.gain = if ramped < self.gain {
+            ramped
+        } else {
+            ramped + self.release_coefficient * (self.gain - ramped)
+        };
+        self.gain
+    }
+}
+
+impl AudioEffect for LimiterEffect {
+    fn process_sample(&mut self, input: f32) -> f32 {
+        let (left, right) = self.process_stereo(input, input);
+        (left + right) * 0.5
+    }
+
+    fn process_stereo(&mut self, left: f32, right: f32) -> (f32, f32) {
+        let ceiling = 10.0f32.powf(self.ceiling_db.next() / 20.0);
+        let gain = self.next_gain(left.abs().max(right.abs()), ceiling);
+
+        // The buffers hold exactly the look-ahead, so the oldest frame sits where the newest goes
+        let delayed_left = std::mem::replace(&mut self.left_buffer[self.write_index], left);
+        let delayed_right = std::mem::replace(&mut self.right_buffer[self.write_index], right);
+        self.write_index = (self.write_index + 1) % self.left_buffer.len();
+
+        // Rounding in the running average must never let a sample through above the ceiling
+        (
+            (delayed_left * gain).clamp(-ceiling, ceiling),
+            (delayed_right * gain).clamp(-ceiling, ceiling),
+        )
+    }
+
+    fn snap(&mut self) {
+        self.ceiling_db.jump();
+    }
+
+    fn reset(&mut self) {
+        self.left_buffer.fill(0.0);
+        self.right_buffer.fill(0.0);
+        self.write_index = 0;
+        self.required_gains.clear();
+        self.ramp_buffer.fill(1.0);
+        self.ramp_sum = self.ramp_buffer.len() as f64;
+        self.sample_count = 0;
+        self.gain = 1.0;
+    }
+
+    fn parameters(&self) -> Vec<EffectParameter> {
+        vec![
+            EffectParameter::new("CEIL", self.ceiling_db.target(), MIN_CEILING_DB, 0.0, ParameterUnit::Decibels),
+            EffectParameter::new("RELEASE", self.release_ms, MIN_RELEASE_MS, MAX_RELEASE_MS, ParameterUnit::Milliseconds)
+                .with_exponential(),
+        ]
+    }
+
+    fn set_parameter(&mut self, index: usize, value: f32) {
+        match index {
+            0 => self.set_ceiling(value),
+            1 => self.set_release(value),
+            _ => {}
+        }
+    }
+
+    fn name(&self) -> &str {
+        "Limiter"
+    }
+}
@@ -1,18 +1,165 @@
 use rodio::Source;
+use std::any::Any;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
 use std::time::Duration;
+use crate::modulation::EffectModulation;
+use crate::modulation::lfo::{Lfo, LfoShape, SyncDivision, MAX_LFO_RATE_HZ, MIN_LFO_RATE_HZ};
 
 pub mod delay;
 pub mod reverb;
 pub mod flanger;
+pub mod chorus;
+pub mod phaser;
+pub mod distortion;
+pub mod bitcrusher;
+pub mod compressor;
+pub mod eq;
+pub mod convolution;
+pub mod tremolo;
+pub mod autopan;
+pub mod limiter;
+pub mod parameter;
+mod fft;
 
 pub use delay::DelayEffect;
 pub use reverb::ReverbEffect;
 pub use flanger::FlangerEffect;
+pub use chorus::ChorusEffect;
+pub use phaser::PhaserEffect;
+pub use bitcrusher::BitcrusherEffect;
+pub use eq::ParametricEqEffect;
+pub use tremolo::TremoloEffect;
+pub use autopan::AutoPanEffect;
+pub use convolution::{ConvolutionReverbEffect, ImpulseResponse};
+pub use compressor::{CompressorEffect, Detection, SidechainLevel, SidechainSend};
+pub use distortion::{DistortionEffect, DistortionShape};
+pub use limiter::{ClipIndicator, LimiterEffect};
+pub use parameter::{EffectParameter, ParameterUnit};
+
+/// Effect types every track and the master bus can switch on
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EffectKind {
+    Delay,
+    Reverb,
+    Flanger,
+    Chorus,
+    Phaser,
+    Distortion,
+    Bitcrusher,
+    Compressor,
+    Eq,
+    ConvolutionReverb,
+    Tremolo,
+    AutoPan,
+    Limiter, // Only as the master bus's last stage, so it is not one of ALL
+}
+
+impl EffectKind {
+    /// All effect types in the order of the effects buttons
+    pub const ALL: [EffectKind; 12] = [
+        EffectKind::Delay,
+        EffectKind::Reverb,
+        EffectKind::Flanger,
+        EffectKind::Chorus,
+        EffectKind::Phaser,
+        EffectKind::Distortion,
+        EffectKind::Bitcrusher,
+        EffectKind::Compressor,
+        EffectKind::Eq,
+        EffectKind::ConvolutionReverb,
+        EffectKind::Tremolo,
+        EffectKind::AutoPan,
+    ];
+
+    /// Button color when the effect is enabled
+    pub fn color(self) -> u32 {
+        match self {
+            EffectKind::Delay => 0xFF4444FF,   // Blue for delay
+            EffectKind::Reverb => 0xFF44FF44,  // Green for reverb
+            EffectKind::Flanger => 0xFFFF4444, // Red for flanger
+            EffectKind::Chorus => 0xFFAA44FF,  // Purple for chorus
+            EffectKind::Phaser => 0xFFFF8800,  // Orange for phaser
+            EffectKind::Distortion => 0xFFDDDD22, // Yellow for distortion
+            EffectKind::Bitcrusher => 0xFF22CCCC, // Teal for bitcrusher
+            EffectKind::Compressor => 0xFF88AAFF, // Light blue for compressor
+            EffectKind::Eq => 0xFFFF88CC, // Pink for EQ
+            EffectKind::ConvolutionReverb => 0xFF55CCAA, // Sea green for convolution reverb
+            EffectKind::Tremolo => 0xFFCC66DD, // Magenta for tremolo
+            EffectKind::AutoPan => 0xFF66CCFF, // Sky blue for auto-pan
+            EffectKind::Limiter => 0xFFCC4444, // Dark red for the limiter
+        }
+    }
+}
+
+/// Implements the [Display] trait for [EffectKind] using the short labels shown on the effects buttons
+impl fmt::Display for EffectKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EffectKind::Delay => write!(f, "DLY"),
+            EffectKind::Reverb => write!(f, "REV"),
+            EffectKind::Flanger => write!(f, "FLG"),
+            EffectKind::Chorus => write!(f, "CHO"),
+            EffectKind::Phaser => write!(f, "PHA"),
+            EffectKind::Distortion => write!(f, "DST"),
+            EffectKind::Bitcrusher => write!(f, "BIT"),
+            EffectKind::Compressor => write!(f, "CMP"),
+            EffectKind::Eq => write!(f, "EQ"),
+            EffectKind::ConvolutionReverb => write!(f, "CNV"),
+            EffectKind::Tremolo => write!(f, "TRM"),
+            EffectKind::AutoPan => write!(f, "PAN"),
+            EffectKind::Limiter => write!(f, "LIM"),
+        }
+    }
+}
+
+/// Most effects a single track or the master bus can hold
+pub const MAX_EFFECT_SLOTS: usize = 8;
+
+/// Length of the crossfade when a slot is bypassed or brought back
+const BYPASS_FADE_MS: f32 = 10.0;
+
+/// Frames between pickups of chain updates and of effect settings driven by the mod matrix
+const EFFECT_UPDATE_INTERVAL: usize = 32;
+
+/// Time a smoothed parameter takes to cover about 63% of a change
+const PARAMETER_SMOOTHING_MS: f32 = 20.0;
+/// Delay times glide slower than the other parameters, so a jump bends the pitch gently
+const DELAY_TIME_SMOOTHING_MS: f32 = 150.0;
 
 /// Trait that all audio effects must implement
-pub trait AudioEffect: Send + Sync {
+pub trait AudioEffect: Send + Sync + fmt::Debug + EffectObject {
     /// Process a single audio sample
     fn process_sample(&mut self, input: f32) -> f32;
+
+    /// Process one stereo frame
+    ///
+    /// Mono effects process the mid signal and leave the stereo side untouched,
+    /// so panned voices keep their position. Stereo effects override this.
+    fn process_stereo(&mut self, left: f32, right: f32) -> (f32, f32) {
+        let mid = self.process_sample((left + right) * 0.5);
+        let side = (left - right) * 0.5;
+        (mid + side, mid - side)
+    }
+
+    /// Follow the project tempo, for effects with tempo-synced times
+    fn set_tempo(&mut self, _tempo_bpm: f32) {}
+
+    /// Key dynamics to another track's level instead of the input, for effects that support it
+    fn set_sidechain(&mut self, _sidechain: Option<SidechainLevel>) {}
+
+    /// Editable settings in the order the effect editor shows them
+    fn parameters(&self) -> Vec<EffectParameter> {
+        Vec::new()
+    }
+
+    /// Change the setting at `index` of `parameters` to `value`, given in the parameter's unit
+    fn set_parameter(&mut self, _index: usize, _value: f32) {}
+
+    /// Jump every gliding setting straight to its value, for stored chains whose copies
+    /// should start playing at the knob positions instead of gliding there
+    fn snap(&mut self) {}
     
     /// Reset the effect's internal state
     fn reset(&mut self);
@@ -21,6 +168,28 @@ pub trait AudioEffect: Send + Sync {
     fn name(&self) -> &str;
 }
 
+/// Cloning and downcasting of boxed effects, implemented for every effect type automatically
+pub trait EffectObject {
+    fn clone_box(&self) -> Box<dyn AudioEffect>;
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: AudioEffect + Clone + 'static> EffectObject for T {
+    fn clone_box(&self) -> Box<dyn AudioEffect> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl Clone for Box<dyn AudioEffect> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
 /// Wrapper that applies an effect to any audio source
 pub struct EffectWrapper<S, E> 
 where
@@ -75,32 +244,667 @@ where
     }
 }
 
-/// Chain multiple effects together
+/// Bypass state and parameter values of a slot, shared by the chain a track keeps as its
+/// template and the copy playing on the bus, so edits reach it while it plays
+#[derive(Debug)]
+struct SlotControls {
+    bypassed: AtomicBool,
+    parameters: Vec<AtomicU32>, // Bits of every parameter value
+    revision: AtomicU32,        // Bumped after every change
+}
+
+/// One position in an effect chain
+#[derive(Debug, Clone)]
+pub struct EffectSlot {
+    pub kind: EffectKind,
+    pub bypassed: bool, // Skipped while processing but kept in place with its settings
+    effect: Box<dyn AudioEffect>,
+    controls: Arc<SlotControls>,
+    revision: u32,  // Last control revision applied to this copy
+    wet: f32,       // Bypass crossfade position, 0.0 (bypassed) - 1.0 (active)
+    fade_step: f32, // Change of `wet` per sample while crossfading
+}
+
+impl EffectSlot {
+    fn new(kind: EffectKind, effect: Box<dyn AudioEffect>, sample_rate: u32) -> Self {
+        let parameters = effect.parameters().iter().map(|parameter| AtomicU32::new(parameter.value.to_bits())).collect();
+        Self {
+            kind,
+            bypassed: false,
+            effect,
+            controls: Arc::new(SlotControls {
+                bypassed: AtomicBool::new(false),
+                parameters,
+                revision: AtomicU32::new(0),
+            }),
+            revision: 0,
+            wet: 1.0,
+            fade_step: 1000.0 / (BYPASS_FADE_MS * sample_rate as f32),
+        }
+    }
+
+    /// The slot's effect as its concrete type, if it is one
+    pub fn effect<T: AudioEffect + 'static>(&self) -> Option<&T> {
+        self.effect.as_any().downcast_ref::<T>()
+    }
+
+    /// Editable settings of the slot's effect
+    pub fn parameters(&self) -> Vec<EffectParameter> {
+        self.effect.parameters()
+    }
+
+    /// Share the bypass state and parameter values with every playing copy of the slot
+    fn publish(&mut self) {
+        self.controls.bypassed.store(self.bypassed, Ordering::Relaxed);
+        for (stored, parameter) in self.controls.parameters.iter().zip(self.effect.parameters()) {
+            stored.store(parameter.value.to_bits(), Ordering::Relaxed);
+        }
+        self.revision = self.controls.revision.fetch_add(1, Ordering::Release).wrapping_add(1);
+    }
+
+    /// Pick up changes published since the last sample; the effect smooths the new values itself
+    fn apply_controls(&mut self) {
+        let revision = self.controls.revision.load(Ordering::Acquire);
+        if revision == self.revision {
+            return;
+        }
+        self.revision = revision;
+        self.bypassed = self.controls.bypassed.load(Ordering::Relaxed);
+        // In order, so switches such as tempo sync land before the settings they change the meaning of
+        for (index, stored) in self.controls.parameters.iter().enumerate() {
+            self.effect.set_parameter(index, f32::from_bits(stored.load(Ordering::Relaxed)));
+        }
+    }
+
+    /// Move one setting away from its knob position by `amount` of the knob's travel
+    fn modulate(&mut self, parameter: usize, amount: f32) {
+        let Some(stored) = self.controls.parameters.get(parameter) else {
+            return;
+        };
+        let Some(mut setting) = self.effect.parameters().into_iter().nth(parameter) else {
+            return;
+        };
+        setting.value = f32::from_bits(stored.load(Ordering::Relaxed));
+        self.effect.set_parameter(parameter, setting.value_at(setting.normalized() + amount));
+    }
+
+    /// Move the bypass crossfade one sample towards its target and return the wet amount,
+    /// or None once the slot is fully bypassed and the effect can be skipped
+    fn next_wet(&mut self) -> Option<f32> {
+        self.apply_controls();
+        let target = if self.bypassed { 0.0 } else { 1.0 };
+        if self.wet == target {
+            return (!self.bypassed).then_some(1.0);
+        }
+        let step = self.fade_step;
+        self.wet = if self.wet < target { (self.wet + step).min(target) } else { (self.wet - step).max(target) };
+        Some(self.wet)
+    }
+
+    fn process_sample(&mut self, input: f32) -> f32 {
+        match self.next_wet() {
+            Some(wet) => {
+                let output = self.effect.process_sample(input);
+                input + (output - input) * wet
+            }
+            None => input,
+        }
+    }
+
+    fn process_stereo(&mut self, left: f32, right: f32) -> (f32, f32) {
+        match self.next_wet() {
+            Some(wet) => {
+                let (wet_left, wet_right) = self.effect.process_stereo(left, right);
+                (left + (wet_left - left) * wet, right + (wet_right - right) * wet)
+            }
+            None => (left, right),
+        }
+    }
+}
+
+/// Ordered chain of effect slots that can be added, removed, reordered and bypassed
+///
+/// Tracks and the master bus keep one chain each as the template for playback;
+/// the bus plays a copy of it, replaced through [ChainUpdates] when slots change.
+#[derive(Debug, Clone)]
 pub struct EffectChain {
-    effects: Vec<Box<dyn AudioEffect>>,
+    slots: Vec<EffectSlot>,
+    sample_rate: u32, // Rate the chain's effects are built for, which times the bypass crossfade
+    layout: u32,      // Bumped whenever the slots change in a way their controls cannot carry
+    tempo_bpm: f32,
+    sidechain: Option<SidechainLevel>,
 }
 
 impl EffectChain {
-    pub fn new() -> Self {
+    /// Create an empty chain
+    ///
+    /// # Parameters
+    /// - `sample_rate`: Audio sample rate the effects added to it run at
+    pub fn new(sample_rate: u32) -> Self {
         Self {
-            effects: Vec::new(),
+            slots: Vec::new(),
+            sample_rate,
+            layout: 0,
+            tempo_bpm: 120.0,
+            sidechain: None,
         }
     }
     
-    pub fn add_effect(&mut self, effect: Box<dyn AudioEffect>) {
-        self.effects.push(effect);
+    /// Append an effect at the end of the chain
+    pub fn add_effect(&mut self, kind: EffectKind, effect: Box<dyn AudioEffect>) {
+        self.slots.push(EffectSlot::new(kind, effect, self.sample_rate));
+        self.layout = self.layout.wrapping_add(1);
     }
-    
+
+    /// Swap the effect in the slot at `index` for another of the same kind, keeping its
+    /// place and bypass state
+    pub fn replace_effect(&mut self, index: usize, effect: Box<dyn AudioEffect>) {
+        if let Some(slot) = self.slots.get_mut(index) {
+            // New controls, so the copy on the bus takes the new effect instead of keeping its own
+            let bypassed = slot.bypassed;
+            *slot = EffectSlot::new(slot.kind, effect, self.sample_rate);
+            slot.bypassed = bypassed;
+            slot.wet = if bypassed { 0.0 } else { 1.0 };
+            slot.publish();
+            self.layout = self.layout.wrapping_add(1);
+        }
+    }
+
+    /// Take the slot at `index` out of the chain
+    pub fn remove(&mut self, index: usize) {
+        if index < self.slots.len() {
+            self.slots.remove(index);
+            self.layout = self.layout.wrapping_add(1);
+        }
+    }
+
+    /// Move the slot at `from` so that it ends up at position `to`
+    pub fn move_slot(&mut self, from: usize, to: usize) {
+        if from < self.slots.len() && to < self.slots.len() {
+            let slot = self.slots.remove(from);
+            self.slots.insert(to, slot);
+            self.layout = self.layout.wrapping_add(1);
+        }
+    }
+
+    /// Take over the running effects of the chain this copy replaces on the bus, so delay
+    /// lines and reverb tails of the slots both share carry on
+    fn adopt(&mut self, playing: &mut EffectChain) {
+        for slot in &mut self.slots {
+            let Some(running) = playing.slots.iter_mut().find(|running| Arc::ptr_eq(&running.controls, &slot.controls)) else {
+                continue;
+            };
+            std::mem::swap(&mut slot.effect, &mut running.effect);
+            slot.revision = running.revision;
+            slot.wet = running.wet;
+
+            // Settings that do not go through the controls follow this chain
+            slot.effect.set_tempo(self.tempo_bpm);
+            slot.effect.set_sidechain(self.sidechain.clone());
+        }
+    }
+
+    /// Flip whether the slot at `index` is skipped
+    pub fn toggle_bypass(&mut self, index: usize) {
+        if let Some(slot) = self.slots.get_mut(index) {
+            slot.bypassed = !slot.bypassed;
+            // Copies made from now on start without a fade, playing ones crossfade
+            slot.wet = if slot.bypassed { 0.0 } else { 1.0 };
+            slot.publish();
+        }
+    }
+
+    /// Change one setting of the effect in the slot at `index`
+    ///
+    /// The stored effect takes the new value at once, so copies made from it start there;
+    /// copies already playing glide to it.
+    pub fn set_parameter(&mut self, index: usize, parameter: usize, value: f32) {
+        if let Some(slot) = self.slots.get_mut(index) {
+            slot.effect.set_parameter(parameter, value);
+            slot.effect.snap();
+            slot.publish();
+        }
+    }
+
+    /// Move one setting of the effect in the slot at `index` away from its knob position,
+    /// by `amount` of the knob's travel (-1.0 - 1.0)
+    pub fn modulate_parameter(&mut self, index: usize, parameter: usize, amount: f32) {
+        if let Some(slot) = self.slots.get_mut(index) {
+            slot.apply_controls();
+            slot.modulate(parameter, amount);
+        }
+    }
+
+    /// Slots in processing order
+    pub fn slots(&self) -> &[EffectSlot] {
+        &self.slots
+    }
+
+    /// Index of the first slot holding an effect of `kind`
+    pub fn position(&self, kind: EffectKind) -> Option<usize> {
+        self.slots.iter().position(|slot| slot.kind == kind)
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Whether the chain has no effects to run
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
     pub fn process_sample(&mut self, mut input: f32) -> f32 {
-        for effect in &mut self.effects {
-            input = effect.process_sample(input);
+        for slot in &mut self.slots {
+            input = slot.process_sample(input);
         }
         input
     }
+
+    /// Process one stereo frame through every slot in order, skipping bypassed ones
+    pub fn process_stereo(&mut self, mut left: f32, mut right: f32) -> (f32, f32) {
+        for slot in &mut self.slots {
+            (left, right) = slot.process_stereo(left, right);
+        }
+        (left, right)
+    }
+
+    /// Pass the project tempo to every effect
+    pub fn set_tempo(&mut self, tempo_bpm: f32) {
+        self.tempo_bpm = tempo_bpm;
+        for slot in &mut self.slots {
+            slot.effect.set_tempo(tempo_bpm);
+        }
+    }
+
+    /// Jump every effect to its settings, so a copy about to play starts there instead of
+    /// gliding from the stored chain's values
+    pub fn snap(&mut self) {
+        for slot in &mut self.slots {
+            slot.effect.snap();
+        }
+    }
+
+    /// Key every dynamics effect in the chain to another track's level, or back to its input
+    pub fn set_sidechain(&mut self, sidechain: Option<SidechainLevel>) {
+        for slot in &mut self.slots {
+            slot.effect.set_sidechain(sidechain.clone());
+        }
+        self.sidechain = sidechain;
+        self.layout = self.layout.wrapping_add(1);
+    }
     
     pub fn reset(&mut self) {
-        for effect in &mut self.effects {
-            effect.reset();
+        for slot in &mut self.slots {
+            slot.effect.reset();
+        }
+    }
+}
+
+/// Changes to a chain waiting for the source playing it
+#[derive(Debug, Default)]
+struct PendingChain {
+    incoming: Option<EffectChain>, // Copy of the edited template, built off the audio thread
+    retired: Option<EffectChain>,  // Copy it replaced, dropped off the audio thread too
+    sent: Option<(u32, u32)>,      // Layout and tempo bits of the last copy sent
+    modulation: Option<EffectModulation>, // Mod matrix routings of the newest note on the chain
+    retired_modulation: Option<EffectModulation>, // Routings they replaced
+    reset: bool,                   // Clear the playing effects' tails
+}
+
+/// Hand-over of an edited chain to the [EffectChainSource] playing it on the bus
+///
+/// Parameter edits reach the playing copy through the slot controls; this carries what they
+/// cannot, such as added, removed or reordered slots, tempo changes and the mod matrix of the
+/// newest note. The audio thread only ever tries the lock, so it never waits for the UI.
+#[derive(Debug, Clone, Default)]
+pub struct ChainUpdates {
+    pending: Arc<Mutex<PendingChain>>,
+}
+
+impl ChainUpdates {
+    /// Send a copy of `chain` at `tempo_bpm` to the playing source, unless it already has
+    /// the same layout and tempo
+    pub fn follow(&self, chain: &EffectChain, tempo_bpm: f32) {
+        let sent = Some((chain.layout, tempo_bpm.to_bits()));
+        let (retired, retired_modulation, up_to_date) = {
+            let mut pending = self.lock();
+            (pending.retired.take(), pending.retired_modulation.take(), pending.sent == sent)
+        };
+        // What the source last replaced is freed here rather than on the audio thread
+        drop((retired, retired_modulation));
+        if up_to_date {
+            return;
+        }
+
+        // Copied outside the lock, as cloning a delay line can take a while
+        let mut copy = chain.clone();
+        copy.set_tempo(tempo_bpm);
+        copy.snap();
+
+        let mut pending = self.lock();
+        pending.incoming = Some(copy);
+        pending.sent = sent;
+    }
+
+    /// Let a newly played note's mod matrix move the playing chain's settings, in place of the
+    /// note before it
+    pub fn modulate(&self, modulation: EffectModulation) {
+        self.lock().modulation = Some(modulation);
+    }
+
+    /// Silence the tails of the playing effects
+    pub fn reset(&self) {
+        self.lock().reset = true;
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, PendingChain> {
+        self.pending.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Swap a waiting copy in for `playing` and waiting routings in for `modulation`,
+    /// if the UI is not holding the lock
+    fn receive(&self, playing: &mut EffectChain, modulation: &mut EffectModulation) {
+        let Ok(mut pending) = self.pending.try_lock() else {
+            return;
+        };
+        if let Some(mut chain) = pending.incoming.take() {
+            chain.adopt(playing);
+            pending.retired = Some(std::mem::replace(playing, chain));
+        }
+        if let Some(incoming) = pending.modulation.take() {
+            // Settings only the previous note moved go back to their knob positions
+            for (slot, parameter, _) in modulation.amounts() {
+                playing.modulate_parameter(slot, parameter, 0.0);
+            }
+            pending.retired_modulation = Some(std::mem::replace(modulation, incoming));
+        }
+        if pending.reset {
+            pending.reset = false;
+            playing.reset();
+        }
+    }
+}
+
+/// Source that runs an effect chain over a mono or interleaved stereo source
+pub struct EffectChainSource<S>
+where
+    S: Source<Item = f32>,
+{
+    source: S,
+    chain: EffectChain,
+    updates: Option<ChainUpdates>,         // Edits of the chain's template made while it plays
+    sidechain_send: Option<SidechainSend>, // Publishes the level after the chain
+    modulation: EffectModulation,          // Mod matrix routings from the newest note into the chain
+    update_countdown: usize,               // Frames until updates and modulated settings are picked up
+    pending_right: Option<f32>, // Right sample of the frame being emitted
+}
+
+impl<S> EffectChainSource<S>
+where
+    S: Source<Item = f32>,
+{
+    pub fn new(source: S, chain: EffectChain) -> Self {
+        Self {
+            source,
+            chain,
+            updates: None,
+            sidechain_send: None,
+            modulation: EffectModulation::default(),
+            update_countdown: 0,
+            pending_right: None,
         }
     }
-}
\ No newline at end of file
+
+    /// Replace the chain whenever its template's slots change
+    pub fn with_updates(mut self, updates: ChainUpdates) -> Self {
+        self.updates = Some(updates);
+        self
+    }
+
+    /// Publish the chain's output level for compressors keyed to it
+    pub fn with_sidechain_send(mut self, sidechain_send: SidechainSend) -> Self {
+        self.sidechain_send = Some(sidechain_send);
+        self
+    }
+
+
+    /// Pick up template edits and apply the mod matrix to the chain every EFFECT_UPDATE_INTERVAL frames
+    fn apply_updates(&mut self) {
+        if self.update_countdown == 0 {
+            if let Some(updates) = &self.updates {
+                updates.receive(&mut self.chain, &mut self.modulation);
+            }
+            // Settings rest at their knob positions once the note moving them has ended
+            let note_playing = self.modulation.is_shared();
+            for (slot, parameter, amount) in self.modulation.amounts() {
+                self.chain.modulate_parameter(slot, parameter, if note_playing { amount } else { 0.0 });
+            }
+            self.update_countdown = EFFECT_UPDATE_INTERVAL;
+        }
+        self.update_countdown -= 1;
+    }
+}
+
+impl<S> Iterator for EffectChainSource<S>
+where
+    S: Source<Item = f32>,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(right) = self.pending_right.take() {
+            return Some(right);
+        }
+
+        let left = self.source.next()?;
+        self.apply_updates();
+        if self.source.channels() != 2 {
+            let output = self.chain.process_sample(left);
+            return Some(match &mut self.sidechain_send {
+                Some(send) => send.process_sample(output),
+                None => output,
+            });
+        }
+
+        // Stereo frames are processed as a whole so the effects see both channels together
+        let right = self.source.next().unwrap_or(left);
+        let (mut left, mut right) = self.chain.process_stereo(left, right);
+        if let Some(send) = &mut self.sidechain_send {
+            (left, right) = send.process_stereo(left, right);
+        }
+        self.pending_right = Some(right);
+        Some(left)
+    }
+}
+
+impl<S> Source for EffectChainSource<S>
+where
+    S: Source<Item = f32>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.source.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.source.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.source.total_duration()
+    }
+}
+
+/// Read a delay line between samples with linear interpolation
+///
+/// # Parameters
+/// - `buffer`: Circular delay buffer
+/// - `write_index`: Position the next input sample will be written to
+/// - `delay_samples`: Fractional delay in samples, shorter than the buffer
+pub(crate) fn read_interpolated(buffer: &[f32], write_index: usize, delay_samples: f32) -> f32 {
+    let delay_int = delay_samples as usize;
+    let delay_frac = delay_samples - delay_int as f32;
+
+    // Calculate read positions (circular buffer)
+    let read_index1 = if write_index >= delay_int {
+        write_index - delay_int
+    } else {
+        buffer.len() - (delay_int - write_index)
+    };
+
+    let read_index2 = if read_index1 == 0 {
+        buffer.len() - 1
+    } else {
+        read_index1 - 1
+    };
+
+    // Linear interpolation between adjacent samples
+    let sample1 = buffer[read_index1 % buffer.len()];
+    let sample2 = buffer[read_index2 % buffer.len()];
+
+    sample1 + delay_frac * (sample2 - sample1)
+}
+
+/// Effect parameter that glides to a new value with a one-pole filter instead of jumping,
+/// so turning a knob while audio plays does not produce zipper noise
+#[derive(Debug, Clone)]
+pub(crate) struct SmoothedValue {
+    current: f32,
+    target: f32,
+    coefficient: f32,
+}
+
+impl SmoothedValue {
+    pub(crate) fn new(value: f32, sample_rate: u32) -> Self {
+        Self::with_time(value, PARAMETER_SMOOTHING_MS, sample_rate)
+    }
+
+    /// Create a value that takes `time_ms` to cover about 63% of a change
+    pub(crate) fn with_time(value: f32, time_ms: f32, sample_rate: u32) -> Self {
+        Self {
+            current: value,
+            target: value,
+            coefficient: (-1000.0 / (time_ms * sample_rate as f32)).exp(),
+        }
+    }
+
+    /// Glide towards `target`
+    pub(crate) fn set(&mut self, target: f32) {
+        self.target = target;
+    }
+
+    /// Skip the rest of the glide and sit at the target
+    pub(crate) fn jump(&mut self) {
+        self.current = self.target;
+    }
+
+    /// Value the parameter is gliding to
+    pub(crate) fn target(&self) -> f32 {
+        self.target
+    }
+
+    /// Whether the value has not reached its target yet
+    pub(crate) fn is_gliding(&self) -> bool {
+        self.current != self.target
+    }
+
+    /// Advance by one sample and return the current value
+    pub(crate) fn next(&mut self) -> f32 {
+        if self.current != self.target {
+            self.current = self.target + self.coefficient * (self.current - self.target);
+            if (self.current - self.target).abs() < 1e-5 * self.target.abs().max(1.0) {
+                self.current = self.target;
+            }
+        }
+        self.current
+    }
+}
+
+/// Modulation oscillator for LFO-driven effects, free-running in Hz or locked to the tempo
+///
+/// Keeps the settings shown in the effect editor and runs them on a modulation [Lfo].
+#[derive(Debug, Clone)]
+pub(crate) struct EffectLfo {
+    lfo: Lfo,
+    shape: LfoShape,
+    rate_hz: f32,
+    sync: Option<SyncDivision>,
+    tempo_bpm: f32,
+}
+
+impl EffectLfo {
+    pub(crate) fn new(shape: LfoShape, rate_hz: f32, sample_rate: u32) -> Self {
+        let rate_hz = rate_hz.clamp(MIN_LFO_RATE_HZ, MAX_LFO_RATE_HZ);
+        Self {
+            lfo: Lfo::with_frequency(shape, rate_hz, sample_rate as f32),
+            shape,
+            rate_hz,
+            sync: None,
+            tempo_bpm: 120.0,
+        }
+    }
+
+    /// Lock one LFO cycle to a note length; the rate follows `set_tempo`
+    pub(crate) fn set_sync(&mut self, division: Option<SyncDivision>) {
+        self.sync = division;
+        self.set_tempo(self.tempo_bpm);
+    }
+
+    /// Shape, sync and rate settings for the effect editor
+    pub(crate) fn parameters(&self) -> [EffectParameter; 3] {
+        let shape_index = LfoShape::ALL.iter().position(|&shape| shape == self.shape).unwrap_or(0);
+        let rate = match self.sync {
+            Some(division) => {
+                let index = SyncDivision::ALL.iter().position(|&d| d == division).unwrap_or(0);
+                EffectParameter::choice("RATE", index, SyncDivision::ALL.len(), division)
+            }
+            None => EffectParameter::new("RATE", self.rate_hz, MIN_LFO_RATE_HZ, MAX_LFO_RATE_HZ, ParameterUnit::Hertz)
+                .with_exponential(),
+        };
+
+        [
+            EffectParameter::choice("SHAPE", shape_index, LfoShape::ALL.len(), self.shape),
+            EffectParameter::switch("SYNC", self.sync.is_some()),
+            rate,
+        ]
+    }
+
+    /// Change one of the settings returned by `parameters`
+    pub(crate) fn set_parameter(&mut self, index: usize, value: f32) {
+        match index {
+            0 => {
+                self.shape = LfoShape::ALL[(value as usize).min(LfoShape::ALL.len() - 1)];
+                self.lfo.set_shape(self.shape);
+            }
+            // Syncing starts from a quarter note; going back to Hz keeps the last rate
+            1 => self.sync = (value >= 0.5).then(|| self.sync.unwrap_or(SyncDivision::Quarter)),
+            2 => match self.sync {
+                Some(_) => self.sync = Some(SyncDivision::ALL[(value as usize).min(SyncDivision::ALL.len() - 1)]),
+                None => self.rate_hz = value.clamp(MIN_LFO_RATE_HZ, MAX_LFO_RATE_HZ),
+            },
+            _ => {}
+        }
+        self.set_tempo(self.tempo_bpm);
+    }
+
+    pub(crate) fn set_tempo(&mut self, tempo_bpm: f32) {
+        self.tempo_bpm = tempo_bpm;
+        if let Some(division) = self.sync {
+            self.rate_hz = tempo_bpm.max(1.0) / 60.0 / division.beats();
+        }
+        self.lfo.set_frequency(self.rate_hz);
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.lfo.reset();
+    }
+
+    /// Current bipolar value (-1.0 - 1.0), moving on by one sample
+    pub(crate) fn next_value(&mut self) -> f32 {
+        self.lfo.next_value()
+    }
+}
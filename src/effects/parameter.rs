@@ -0,0 +1,112 @@
+use std::fmt;
+
+/// How a parameter value is shown on the effect editor
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParameterUnit {
+    Percent,        // 0.0 - 1.0, shown as 0 - 100%
+    Milliseconds,
+    Seconds,
+    Hertz,
+    Decibels,
+    Ratio,          // Compression ratio, shown as N:1
+    Bits,
+    Number,         // Plain number such as a filter Q
+    Factor,         // Multiplier such as a sample-rate reduction, shown as XN
+    Choice(String), // Index into a list of options, shown as the selected option's label
+}
+
+/// One editable setting of an effect, with its range and current value
+#[derive(Debug, Clone, PartialEq)]
+pub struct EffectParameter {
+    pub label: &'static str,
+    pub value: f32,
+    pub min: f32,
+    pub max: f32,
+    pub unit: ParameterUnit,
+    pub exponential: bool, // Knob travel is spread evenly over ratios instead of steps, for frequencies and times
+}
+
+impl EffectParameter {
+    /// Create a parameter with a linear knob
+    ///
+    /// # Parameters
+    /// - `label`: Short label shown above the readout
+    /// - `value`: Current value in `unit`
+    /// - `min`: Lowest value
+    /// - `max`: Highest value
+    /// - `unit`: How the value is shown
+    pub fn new(label: &'static str, value: f32, min: f32, max: f32, unit: ParameterUnit) -> Self {
+        Self {
+            label,
+            value: value.clamp(min, max),
+            min,
+            max,
+            unit,
+            exponential: false,
+        }
+    }
+
+    /// Create a parameter picking one of `count` options; the value is the option's index
+    pub fn choice(label: &'static str, index: usize, count: usize, selected: impl fmt::Display) -> Self {
+        Self::new(label, index as f32, 0.0, count.saturating_sub(1) as f32, ParameterUnit::Choice(selected.to_string()))
+    }
+
+    /// Create an on/off parameter; the value is 1.0 when on
+    pub fn switch(label: &'static str, on: bool) -> Self {
+        Self::choice(label, on as usize, 2, if on { "ON" } else { "OFF" })
+    }
+
+    /// Spread the knob travel evenly over ratios, so every turn doubles or halves the value
+    pub fn with_exponential(mut self) -> Self {
+        self.exponential = self.min > 0.0;
+        self
+    }
+
+    /// Knob position of the current value (0.0 - 1.0)
+    pub fn normalized(&self) -> f32 {
+        if self.max <= self.min {
+            return 0.0;
+        }
+        let position = if self.exponential {
+            (self.value / self.min).ln() / (self.max / self.min).ln()
+        } else {
+            (self.value - self.min) / (self.max - self.min)
+        };
+        position.clamp(0.0, 1.0)
+    }
+
+    /// Value at a knob position (0.0 - 1.0); choices snap to whole options
+    pub fn value_at(&self, normalized: f32) -> f32 {
+        let normalized = normalized.clamp(0.0, 1.0);
+        let value = if self.exponential {
+            self.min * (self.max / self.min).powf(normalized)
+        } else {
+            self.min + (self.max - self.min) * normalized
+        };
+
+        match self.unit {
+            ParameterUnit::Choice(_) => value.round(),
+            _ => value,
+        }
+    }
+
+    /// Short readout of the current value for the editor panel
+    pub fn readout(&self) -> String {
+        let value = self.value;
+        match &self.unit {
+            ParameterUnit::Percent => format!("{:.0}%", value * 100.0),
+            ParameterUnit::Milliseconds if value >= 100.0 => format!("{:.0}MS", value),
+            ParameterUnit::Milliseconds => format!("{:.1}MS", value),
+            ParameterUnit::Seconds => format!("{:.2}S", value),
+            ParameterUnit::Hertz if value >= 1000.0 => format!("{:.1}KHZ", value / 1000.0),
+            ParameterUnit::Hertz if value >= 100.0 => format!("{:.0}HZ", value),
+            ParameterUnit::Hertz => format!("{:.2}HZ", value),
+            ParameterUnit::Decibels => format!("{:+.1}DB", value),
+            ParameterUnit::Ratio => format!("{:.1}:1", value),
+            ParameterUnit::Bits => format!("{:.1}B", value),
+            ParameterUnit::Number => format!("{:.2}", value),
+            ParameterUnit::Factor => format!("X{:.1}", value),
+            ParameterUnit::Choice(label) => label.clone(),
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use super::{AudioEffect, EffectParameter, ParameterUnit, SmoothedValue};
+use crate::modulation::lfo::{MAX_LFO_RATE_HZ, MIN_LFO_RATE_HZ};
+use std::f32::consts::PI;
+
+/// All-pass stage counts the phaser can be built with
+pub const PHASER_STAGE_COUNTS: [usize; 4] = [4, 6, 8, 12];
+
+/// Largest supported stage count
+const MAX_PHASER_STAGES: usize = 12;
+
+/// Lowest and highest frequency the all-pass notches sweep between
+const MIN_SWEEP_HZ: f32 = 200.0;
+const MAX_SWEEP_HZ: f32 = 3200.0;
+
+/// Phaser effect using a chain of LFO-swept first-order all-pass filters
+#[derive(Debug, Clone)]
+pub struct PhaserEffect {
+    // State of every all-pass stage, only the first `stages` are used
+    stage_states: [f32; MAX_PHASER_STAGES],
+    stages: usize,
+    last_output: f32,     // Output of the last stage, fed back into the first
+
+    // LFO (Low Frequency Oscillator) for the sweep
+    lfo_phase: f32,
+    lfo_rate: f32,        // LFO frequency in Hz
+
+    // Parameters
+    depth: SmoothedValue,    // Sweep depth (0.0 - 1.0)
+    feedback: SmoothedValue, // Feedback amount (0.0 - 0.95)
+    mix: SmoothedValue,      // Dry/wet mix (0.0 - 1.0)
+
+    sample_rate: u32,
+}
+
+impl PhaserEffect {
+    /// Create a new phaser effect
+    ///
+    /// # Parameters
+    /// - `stages`: Number of all-pass stages, snapped to the nearest of 4, 6, 8 or 12
+    /// - `lfo_rate`: LFO rate in Hz (typically 0.1 - 2.0)
+    /// - `depth`: Sweep depth (0.0 - 1.0)
+    /// - `feedback`: Feedback amount (0.0 - 0.95)
+    /// - `mix`: Dry/wet mix (0.0 - 1.0), 0.5 gives the deepest notches
+    /// - `sample_rate`: Audio sample rate
+    pub fn new(stages: usize, lfo_rate: f32, depth: f32, feedback: f32, mix: f32, sample_rate: u32) -> Self {
+        Self {
+            stage_states: [0.0; MAX_PHASER_STAGES],
+            stages: Self::nearest_stage_count(stages),
+            last_output: 0.0,
+            lfo_phase: 0.0,
+            lfo_rate: lfo_rate.max(0.01), // Prevent division by zero
+            depth: SmoothedValue::new(depth.clamp(0.0, 1.0), sample_rate),
+            feedback: SmoothedValue::new(feedback.clamp(0.0, 0.95), sample_rate),
+            mix: SmoothedValue::new(mix.clamp(0.0, 1.0), sample_rate),
+            sample_rate,
+        }
+    }
+
+    /// Set the number of all-pass stages, snapped to the nearest supported count
+    pub fn set_stages(&mut self, stages: usize) {
+        self.stages = Self::nearest_stage_count(stages);
+    }
+
+    /// Set LFO rate in Hz
+    pub fn set_lfo_rate(&mut self, rate: f32) {
+        self.lfo_rate = rate.max(0.01);
+    }
+
+    /// Set sweep depth (0.0 - 1.0)
+    pub fn set_depth(&mut self, depth: f32) {
+        self.depth.set(depth.clamp(0.0, 1.0));
+    }
+
+    /// Set feedback amount (0.0 - 0.95)
+    pub fn set_feedback(&mut self, feedback: f32) {
+        self.feedback.set(feedback.clamp(0.0, 0.95));
+    }
+
+    /// Set dry/wet mix (0.0 - 1.0)
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix.set(mix.clamp(0.0, 1.0));
+    }
+
+    /// Closest supported stage count
+    fn nearest_stage_count(stages: usize) -> usize {
+        *PHASER_STAGE_COUNTS
+            .iter()
+            .min_by_key(|&&count| count.abs_diff(stages))
+            .unwrap_or(&4)
+    }
+}
+
+impl AudioEffect for PhaserEffect {
+    fn process_sample(&mut self, input: f32) -> f32 {
+        // Generate LFO (sine wave) and sweep the notches exponentially around the middle of the range
+        let lfo_value = (self.lfo_phase * 2.0 * PI).sin();
+        let sweep = 0.5 + 0.5 * lfo_value * self.depth.next();
+        let frequency = MIN_SWEEP_HZ * (MAX_SWEEP_HZ / MIN_SWEEP_HZ).powf(sweep);
+
+        // First-order all-pass coefficient for the swept frequency
+        let warped = (PI * frequency / self.sample_rate as f32).tan();
+        let coefficient = (1.0 - warped) / (1.0 + warped);
+
+        // Run the all-pass chain with feedback from the previous output
+        let mut signal = input + self.last_output * self.feedback.next();
+        for state in self.stage_states.iter_mut().take(self.stages) {
+            let output = coefficient * signal + *state;
+            *state = signal - coefficient * output;
+            signal = output;
+        }
+        self.last_output = signal;
+
+        // Update LFO phase
+        self.lfo_phase += self.lfo_rate / self.sample_rate as f32;
+        if self.lfo_phase >= 1.0 {
+            self.lfo_phase -= 1.0;
+        }
+
+        // Mix dry and wet signals, the notches come from the phase shifted copy cancelling the dry one
+        let mix = self.mix.next();
+        input * (1.0 - mix) + signal * mix
+    }
+
+    fn snap(&mut self) {
+        self.depth.jump();
+        self.feedback.jump();
+        self.mix.jump();
+    }
+
+    fn reset(&mut self) {
+        self.stage_states = [0.0; MAX_PHASER_STAGES];
+        self.last_output = 0.0;
+        self.lfo_phase = 0.0;
+    }
+
+    fn parameters(&self) -> Vec<EffectParameter> {
+        let stage_index = PHASER_STAGE_COUNTS.iter().position(|&count| count == self.stages).unwrap_or(0);
+        vec![
+            EffectParameter::choice("STAGES", stage_index, PHASER_STAGE_COUNTS.len(), self.stages),
+            EffectParameter::new("RATE", self.lfo_rate, MIN_LFO_RATE_HZ, MAX_LFO_RATE_HZ, ParameterUnit::Hertz).with_exponential(),
+            EffectParameter::new("DEPTH", self.depth.target(), 0.0, 1.0, ParameterUnit::Percent),
+            EffectParameter::new("FDBK", self.feedback.target(), 0.0, 0.95, ParameterUnit::Percent),
+            EffectParameter::new("MIX", self.mix.target(), 0.0, 1.0, ParameterUnit::Percent),
+        ]
+    }
+
+    fn set_parameter(&mut self, index: usize, value: f32) {
+        match index {
+            0 => self.set_stages(PHASER_STAGE_COUNTS[(value as usize).min(PHASER_STAGE_COUNTS.len() - 1)]),
+            1 => self.set_lfo_rate(value),
+            2 => self.set_depth(value),
+            3 => self.set_feedback(value),
+            4 => self.set_mix(value),
+            _ => {}
+        }
+    }
+
+    fn name(&self) -> &str {
+        "Phaser"
+    }
+}
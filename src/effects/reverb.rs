@@ -1,175 +1,398 @@
-use super::AudioEffect;
+use super::{read_interpolated, AudioEffect, EffectParameter, ParameterUnit, SmoothedValue, DELAY_TIME_SMOOTHING_MS};
+use std::f32::consts::PI;
 
-/// Simple reverb effect using multiple delay lines (Schroeder reverb)
+/// Comb filter lengths at the nominal room size, tuned to avoid shared multiples
+const COMB_DELAYS_MS: [f32; 8] = [29.7, 37.1, 41.1, 43.7, 47.0, 50.3, 53.5, 56.3];
+/// All-pass diffuser lengths
+const ALLPASS_DELAYS_MS: [f32; 4] = [5.0, 1.7, 12.9, 9.3];
+/// Extra length of every right-channel line, which decorrelates the two tails
+const STEREO_SPREAD_MS: f32 = 0.52;
+/// Room size 0.0 - 1.0 scales the comb lengths between these factors
+const MIN_SIZE_SCALE: f32 = 0.5;
+const MAX_SIZE_SCALE: f32 = 1.5;
+/// Peak deviation of the modulated comb lengths at full modulation depth
+const MAX_MODULATION_MS: f32 = 0.25;
+/// Longest pre-delay the effect can hold
+const MAX_PRE_DELAY_MS: f32 = 250.0;
+/// Corner of the low-damping filter in the comb feedback path
+const LOW_DAMPING_HZ: f32 = 250.0;
+/// Range of the decay time
+const MIN_DECAY_SECONDS: f32 = 0.1;
+const MAX_DECAY_SECONDS: f32 = 20.0;
+
+/// Feedback comb filter with damping in its loop and a slowly swept length
 #[derive(Debug, Clone)]
-pub struct ReverbEffect {
-    // Comb filters (feedback delay lines)
-    comb_delays: Vec<Vec<f32>>,
-    comb_indices: Vec<usize>,
-    comb_feedback: Vec<f32>,
-    
-    // All-pass filters
-    allpass_delays: Vec<Vec<f32>>,
-    allpass_indices: Vec<usize>,
+struct CombFilter {
+    buffer: Vec<f32>,
+    index: usize,
+    base_delay_ms: f32,
+    high_damping_state: f32,
+    low_damping_state: f32,
+    lfo_phase: f32,
+    lfo_rate: f32, // Hz
+}
+
+/// Schroeder all-pass diffuser
+#[derive(Debug, Clone)]
+struct AllpassFilter {
+    buffer: Vec<f32>,
+    index: usize,
+}
+
+/// Per-sample settings shared by both channels
+#[derive(Debug, Clone, Copy)]
+struct ChannelSettings {
+    ms_to_samples: f32,
+    size_scale: f32,
+    modulation_samples: f32,
+    lfo_increment_per_hz: f32,
+    damping: f32,
+    low_damping: f32,
+    low_damping_coefficient: f32,
     allpass_feedback: f32,
-    
+}
+
+/// One side of the stereo reverb
+#[derive(Debug, Clone)]
+struct ReverbChannel {
+    combs: Vec<CombFilter>,
+    allpasses: Vec<AllpassFilter>,
+}
+
+impl ReverbChannel {
+    fn new(spread_ms: f32, lfo_phase_offset: f32, sample_rate: u32) -> Self {
+        let ms_to_samples = |ms: f32| ((ms / 1000.0) * sample_rate as f32) as usize;
+
+        let combs = COMB_DELAYS_MS
+            .iter()
+            .enumerate()
+            .map(|(i, &delay_ms)| {
+                let base_delay_ms = delay_ms + spread_ms;
+                let max_ms = base_delay_ms * MAX_SIZE_SCALE + MAX_MODULATION_MS;
+                CombFilter {
+                    buffer: vec![0.0; ms_to_samples(max_ms) + 2],
+                    index: 0,
+                    base_delay_ms,
+                    high_damping_state: 0.0,
+                    low_damping_state: 0.0,
+                    // Spread the sweeps so the combs never move together
+                    lfo_phase: (i as f32 * 0.7 + lfo_phase_offset) % (2.0 * PI),
+                    lfo_rate: 0.3 + i as f32 * 0.11,
+                }
+            })
+            .collect();
+
+        let allpasses = ALLPASS_DELAYS_MS
+            .iter()
+            .map(|&delay_ms| AllpassFilter {
+                buffer: vec![0.0; ms_to_samples(delay_ms + spread_ms).max(1)],
+                index: 0,
+            })
+            .collect();
+
+        Self { combs, allpasses }
+    }
+
+    /// Run the combs in parallel and the all-passes in series
+    fn process(&mut self, input: f32, comb_feedback: &[f32], settings: &ChannelSettings) -> f32 {
+        let mut output = 0.0;
+        for (comb, &feedback) in self.combs.iter_mut().zip(comb_feedback.iter()) {
+            comb.lfo_phase += comb.lfo_rate * settings.lfo_increment_per_hz;
+            if comb.lfo_phase >= 2.0 * PI {
+                comb.lfo_phase -= 2.0 * PI;
+            }
+
+            let delay_samples = comb.base_delay_ms * settings.size_scale * settings.ms_to_samples
+                + comb.lfo_phase.sin() * settings.modulation_samples;
+            let delayed = read_interpolated(&comb.buffer, comb.index, delay_samples.max(1.0));
+
+            // High damping: one-pole low-pass in the loop
+            comb.high_damping_state = delayed * (1.0 - settings.damping) + comb.high_damping_state * settings.damping;
+            // Low damping: remove part of the lows on every pass
+            comb.low_damping_state += settings.low_damping_coefficient * (comb.high_damping_state - comb.low_damping_state);
+            let filtered = comb.high_damping_state - comb.low_damping_state * settings.low_damping;
+
+            comb.buffer[comb.index] = input + filtered * feedback;
+            comb.index = (comb.index + 1) % comb.buffer.len();
+
+            output += delayed;
+        }
+        output /= self.combs.len() as f32;
+
+        for allpass in &mut self.allpasses {
+            let delayed = allpass.buffer[allpass.index];
+            allpass.buffer[allpass.index] = output + delayed * settings.allpass_feedback;
+            allpass.index = (allpass.index + 1) % allpass.buffer.len();
+            output = delayed - output;
+        }
+
+        output
+    }
+
+    fn reset(&mut self) {
+        for comb in &mut self.combs {
+            comb.buffer.fill(0.0);
+            comb.index = 0;
+            comb.high_damping_state = 0.0;
+            comb.low_damping_state = 0.0;
+        }
+        for allpass in &mut self.allpasses {
+            allpass.buffer.fill(0.0);
+            allpass.index = 0;
+        }
+    }
+}
+
+/// Stereo reverb built from parallel modulated comb filters and series all-pass diffusers
+/// (Schroeder reverb)
+///
+/// The right channel runs slightly longer lines than the left so the two tails are
+/// decorrelated, and the comb lengths drift by a fraction of a millisecond to break up
+/// the metallic ringing of fixed-length combs.
+#[derive(Debug, Clone)]
+pub struct ReverbEffect {
+    left: ReverbChannel,
+    right: ReverbChannel,
+
+    // Pre-delay line
+    pre_delay_buffer: Vec<f32>,
+    pre_delay_index: usize,
+    pre_delay_samples: SmoothedValue,
+
     // Parameters
-    room_size: f32,   // 0.0 - 1.0
-    damping: f32,     // 0.0 - 1.0
-    mix: f32,         // 0.0 - 1.0
-    
-    // Low-pass filter for damping
-    damping_filter: f32,
+    room_size: SmoothedValue,     // 0.0 - 1.0
+    decay_seconds: SmoothedValue, // RT60
+    damping: SmoothedValue,       // 0.0 - 1.0, high frequencies
+    low_damping: SmoothedValue,   // 0.0 - 1.0, low frequencies
+    diffusion: SmoothedValue,     // 0.0 - 1.0
+    modulation: SmoothedValue,    // 0.0 - 1.0
+    mix: SmoothedValue,           // 0.0 - 1.0
+
+    // Derived per-sample values
+    comb_feedback: Vec<f32>,
+    low_damping_coefficient: f32,
+    sample_rate: u32,
 }
 
 impl ReverbEffect {
     /// Create a new reverb effect
-    /// 
+    ///
+    /// The decay time follows the room size until set with `with_decay`.
+    ///
     /// # Parameters
     /// - `room_size`: Size of the room (0.0 - 1.0)
     /// - `damping`: High frequency damping (0.0 - 1.0)
     /// - `mix`: Dry/wet mix (0.0 - 1.0)
     /// - `sample_rate`: Audio sample rate
     pub fn new(room_size: f32, damping: f32, mix: f32, sample_rate: u32) -> Self {
-        // Enhanced comb filter delay times - carefully tuned for musical intervals
-        let comb_delays_ms = [29.7, 37.1, 41.1, 43.7, 47.0, 50.3, 53.5, 56.3];
-        let base_feedback = 0.6 + room_size * 0.35; // Increased feedback for fuller sound
-        
-        let mut comb_delays = Vec::new();
-        let mut comb_feedback = Vec::new();
-        
-        for (i, &delay_ms) in comb_delays_ms.iter().enumerate() {
-            let delay_samples = ((delay_ms / 1000.0) * sample_rate as f32) as usize;
-            comb_delays.push(vec![0.0; delay_samples.max(1)]);
-            // Vary feedback slightly for each comb filter
-            let feedback_variation = 1.0 + (i as f32 * 0.02 - 0.07);
-            comb_feedback.push(base_feedback * feedback_variation);
-        }
-        
-        // Enhanced all-pass filter delays for better diffusion
-        let allpass_delays_ms = [5.0, 1.7, 12.9, 9.3, 15.1, 8.2];
-        let mut allpass_delays = Vec::new();
-        
-        for &delay_ms in &allpass_delays_ms {
-            let delay_samples = ((delay_ms / 1000.0) * sample_rate as f32) as usize;
-            allpass_delays.push(vec![0.0; delay_samples.max(1)]);
-        }
-        
-        Self {
-            comb_delays,
-            comb_indices: vec![0; comb_delays_ms.len()],
-            comb_feedback,
-            allpass_delays,
-            allpass_indices: vec![0; allpass_delays_ms.len()],
-            allpass_feedback: 0.618, // Golden ratio for more natural sound
-            room_size: room_size.clamp(0.0, 1.0),
-            damping: damping.clamp(0.0, 1.0),
-            mix: mix.clamp(0.0, 1.0),
-            damping_filter: 0.0,
-        }
+        let room_size = room_size.clamp(0.0, 1.0);
+        // Two extra samples for the interpolated read at the longest pre-delay
+        let max_pre_delay = (MAX_PRE_DELAY_MS / 1000.0 * sample_rate as f32) as usize + 2;
+        let smoothed = |value: f32| SmoothedValue::new(value, sample_rate);
+
+        let mut effect = Self {
+            left: ReverbChannel::new(0.0, 0.0, sample_rate),
+            right: ReverbChannel::new(STEREO_SPREAD_MS, PI / 2.0, sample_rate),
+            pre_delay_buffer: vec![0.0; max_pre_delay],
+            pre_delay_index: 0,
+            pre_delay_samples: SmoothedValue::with_time(0.0, DELAY_TIME_SMOOTHING_MS, sample_rate),
+            room_size: smoothed(room_size),
+            decay_seconds: smoothed(0.5 + room_size * 3.0),
+            damping: smoothed(damping.clamp(0.0, 1.0)),
+            low_damping: smoothed(0.0),
+            diffusion: smoothed(0.6),
+            modulation: smoothed(0.3),
+            mix: smoothed(mix.clamp(0.0, 1.0)),
+            comb_feedback: vec![0.0; COMB_DELAYS_MS.len()],
+            low_damping_coefficient: 1.0 - (-2.0 * PI * LOW_DAMPING_HZ / sample_rate as f32).exp(),
+            sample_rate,
+        };
+        effect.update_feedback(room_size, effect.decay_seconds.target());
+        effect
     }
-    
-    /// Process sample through comb filters with improved damping
-    fn process_comb_filters(&mut self, input: f32) -> f32 {
-        let mut output = 0.0;
-        
-        for i in 0..self.comb_delays.len() {
-            let delay_line = &mut self.comb_delays[i];
-            let index = &mut self.comb_indices[i];
-            let feedback = self.comb_feedback[i];
-            
-            // Read delayed sample
-            let delayed = delay_line[*index];
-            
-            // Apply consistent damping across all comb filters
-            self.damping_filter = delayed * (1.0 - self.damping) + self.damping_filter * self.damping;
-            
-            // Write new sample with feedback
-            delay_line[*index] = input + self.damping_filter * feedback;
-            
-            // Advance index (circular)
-            *index = (*index + 1) % delay_line.len();
-            
-            // Accumulate output
-            output += delayed;
-        }
-        
-        output / self.comb_delays.len() as f32
-    }
-    
-    /// Process sample through all-pass filters
-    fn process_allpass_filters(&mut self, mut input: f32) -> f32 {
-        for i in 0..self.allpass_delays.len() {
-            let delay_line = &mut self.allpass_delays[i];
-            let index = &mut self.allpass_indices[i];
-            
-            // Read delayed sample
-            let delayed = delay_line[*index];
-            
-            // All-pass filter calculation
-            let output = -input + delayed;
-            delay_line[*index] = input + delayed * self.allpass_feedback;
-            
-            // Advance index (circular)
-            *index = (*index + 1) % delay_line.len();
-            
-            input = output;
+
+    /// Delay the onset of the tail by `pre_delay_ms`
+    pub fn with_pre_delay(mut self, pre_delay_ms: f32) -> Self {
+        let samples = self.pre_delay_to_samples(pre_delay_ms);
+        self.pre_delay_samples = SmoothedValue::with_time(samples, DELAY_TIME_SMOOTHING_MS, self.sample_rate);
+        self
+    }
+
+    /// Set the time for the tail to fall by 60 dB
+    pub fn with_decay(mut self, decay_seconds: f32) -> Self {
+        let decay_seconds = decay_seconds.clamp(MIN_DECAY_SECONDS, MAX_DECAY_SECONDS);
+        self.decay_seconds = SmoothedValue::new(decay_seconds, self.sample_rate);
+        self.update_feedback(self.room_size.target(), decay_seconds);
+        self
+    }
+
+    /// Set how strongly the all-pass stages smear the echoes (0.0 - 1.0)
+    pub fn with_diffusion(mut self, diffusion: f32) -> Self {
+        self.diffusion = SmoothedValue::new(diffusion.clamp(0.0, 1.0), self.sample_rate);
+        self
+    }
+
+    /// Set how quickly low frequencies die away compared to the mids (0.0 - 1.0)
+    pub fn with_low_damping(mut self, low_damping: f32) -> Self {
+        self.low_damping = SmoothedValue::new(low_damping.clamp(0.0, 1.0), self.sample_rate);
+        self
+    }
+
+    /// Set how far the comb lengths drift (0.0 - 1.0)
+    pub fn with_modulation(mut self, modulation: f32) -> Self {
+        self.modulation = SmoothedValue::new(modulation.clamp(0.0, 1.0), self.sample_rate);
+        self
+    }
+
+    /// Length scale applied to every comb for a room size
+    fn size_scale(room_size: f32) -> f32 {
+        MIN_SIZE_SCALE + (MAX_SIZE_SCALE - MIN_SIZE_SCALE) * room_size
+    }
+
+    /// Pre-delay in samples for a time in milliseconds, within the pre-delay line
+    fn pre_delay_to_samples(&self, pre_delay_ms: f32) -> f32 {
+        let samples = pre_delay_ms.clamp(0.0, MAX_PRE_DELAY_MS) / 1000.0 * self.sample_rate as f32;
+        samples.min((self.pre_delay_buffer.len() - 2) as f32)
+    }
+
+    /// Derive each comb's feedback gain from the decay time: a loop of length `d` must lose
+    /// 60 dB every `decay_seconds`, so g = 10^(-3 d / RT60)
+    fn update_feedback(&mut self, room_size: f32, decay_seconds: f32) {
+        let scale = Self::size_scale(room_size);
+        for (feedback, &delay_ms) in self.comb_feedback.iter_mut().zip(COMB_DELAYS_MS.iter()) {
+            let loop_seconds = delay_ms * scale / 1000.0;
+            *feedback = 10.0f32.powf(-3.0 * loop_seconds / decay_seconds);
         }
-        
-        input
     }
-    
+
     /// Set room size (0.0 - 1.0)
     pub fn set_room_size(&mut self, room_size: f32) {
-        self.room_size = room_size.clamp(0.0, 1.0);
-        let base_feedback = 0.5 + self.room_size * 0.28;
-        
-        for feedback in &mut self.comb_feedback {
-            *feedback = base_feedback;
-        }
+        self.room_size.set(room_size.clamp(0.0, 1.0));
     }
-    
+
     /// Set damping (0.0 - 1.0)
     pub fn set_damping(&mut self, damping: f32) {
-        self.damping = damping.clamp(0.0, 1.0);
+        self.damping.set(damping.clamp(0.0, 1.0));
+    }
+
+    /// Set the pre-delay in milliseconds
+    pub fn set_pre_delay(&mut self, pre_delay_ms: f32) {
+        let samples = self.pre_delay_to_samples(pre_delay_ms);
+        self.pre_delay_samples.set(samples);
     }
-    
+
+    /// Set the time in seconds for the tail to fall by 60 dB
+    pub fn set_decay(&mut self, decay_seconds: f32) {
+        self.decay_seconds.set(decay_seconds.clamp(MIN_DECAY_SECONDS, MAX_DECAY_SECONDS));
+    }
+
     /// Set dry/wet mix (0.0 - 1.0)
     pub fn set_mix(&mut self, mix: f32) {
-        self.mix = mix.clamp(0.0, 1.0);
+        self.mix.set(mix.clamp(0.0, 1.0));
     }
 }
 
 impl AudioEffect for ReverbEffect {
     fn process_sample(&mut self, input: f32) -> f32 {
-        // Process through comb filters
-        let comb_output = self.process_comb_filters(input);
-        
-        // Process through all-pass filters
-        let reverb_output = self.process_allpass_filters(comb_output);
-        
-        // Mix dry and wet signals
-        input * (1.0 - self.mix) + reverb_output * self.mix
-    }
-    
-    fn reset(&mut self) {
-        // Clear all delay lines
-        for delay_line in &mut self.comb_delays {
-            delay_line.fill(0.0);
+        let (left, right) = self.process_stereo(input, input);
+        (left + right) * 0.5
+    }
+
+    fn process_stereo(&mut self, left: f32, right: f32) -> (f32, f32) {
+        // Pre-delay the mono sum feeding both tails
+        let length = self.pre_delay_buffer.len();
+        self.pre_delay_buffer[self.pre_delay_index] = (left + right) * 0.5;
+        let input = read_interpolated(&self.pre_delay_buffer, self.pre_delay_index, self.pre_delay_samples.next());
+        self.pre_delay_index = (self.pre_delay_index + 1) % length;
+
+        // Resizing the room or changing the decay moves the comb feedback gains along with it
+        let resizing = self.room_size.is_gliding() || self.decay_seconds.is_gliding();
+        let room_size = self.room_size.next();
+        let decay_seconds = self.decay_seconds.next();
+        if resizing {
+            self.update_feedback(room_size, decay_seconds);
+        }
+
+        let ms_to_samples = self.sample_rate as f32 / 1000.0;
+        let settings = ChannelSettings {
+            ms_to_samples,
+            size_scale: Self::size_scale(room_size),
+            modulation_samples: self.modulation.next() * MAX_MODULATION_MS * ms_to_samples,
+            lfo_increment_per_hz: 2.0 * PI / self.sample_rate as f32,
+            damping: self.damping.next(),
+            low_damping: self.low_damping.next(),
+            low_damping_coefficient: self.low_damping_coefficient,
+            allpass_feedback: 0.3 + self.diffusion.next() * 0.45,
+        };
+        let wet_left = self.left.process(input, &self.comb_feedback, &settings);
+        let wet_right = self.right.process(input, &self.comb_feedback, &settings);
+
+        let mix = self.mix.next();
+        (
+            left * (1.0 - mix) + wet_left * mix,
+            right * (1.0 - mix) + wet_right * mix,
+        )
+    }
+
+    fn snap(&mut self) {
+        for value in [
+            &mut self.pre_delay_samples,
+            &mut self.room_size,
+            &mut self.decay_seconds,
+            &mut self.damping,
+            &mut self.low_damping,
+            &mut self.diffusion,
+            &mut self.modulation,
+            &mut self.mix,
+        ] {
+            value.jump();
         }
-        for delay_line in &mut self.allpass_delays {
-            delay_line.fill(0.0);
+        self.update_feedback(self.room_size.target(), self.decay_seconds.target());
+    }
+
+    fn reset(&mut self) {
+        self.left.reset();
+        self.right.reset();
+        self.pre_delay_buffer.fill(0.0);
+        self.pre_delay_index = 0;
+    }
+
+    fn parameters(&self) -> Vec<EffectParameter> {
+        let pre_delay_ms = self.pre_delay_samples.target() / self.sample_rate as f32 * 1000.0;
+        vec![
+            EffectParameter::new("SIZE", self.room_size.target(), 0.0, 1.0, ParameterUnit::Percent),
+            EffectParameter::new(
+                "DECAY",
+                self.decay_seconds.target(),
+                MIN_DECAY_SECONDS,
+                MAX_DECAY_SECONDS,
+                ParameterUnit::Seconds,
+            )
+            .with_exponential(),
+            EffectParameter::new("DAMP", self.damping.target(), 0.0, 1.0, ParameterUnit::Percent),
+            EffectParameter::new("LOW", self.low_damping.target(), 0.0, 1.0, ParameterUnit::Percent),
+            EffectParameter::new("DIFF", self.diffusion.target(), 0.0, 1.0, ParameterUnit::Percent),
+            EffectParameter::new("MOD", self.modulation.target(), 0.0, 1.0, ParameterUnit::Percent),
+            EffectParameter::new("PRE", pre_delay_ms, 0.0, MAX_PRE_DELAY_MS, ParameterUnit::Milliseconds),
+            EffectParameter::new("MIX", self.mix.target(), 0.0, 1.0, ParameterUnit::Percent),
+        ]
+    }
+
+    fn set_parameter(&mut self, index: usize, value: f32) {
+        match index {
+            0 => self.set_room_size(value),
+            1 => self.set_decay(value),
+            2 => self.set_damping(value),
+            3 => self.low_damping.set(value.clamp(0.0, 1.0)),
+            4 => self.diffusion.set(value.clamp(0.0, 1.0)),
+            5 => self.modulation.set(value.clamp(0.0, 1.0)),
+            6 => self.set_pre_delay(value),
+            7 => self.set_mix(value),
+            _ => {}
         }
-        
-        // Reset indices
-        self.comb_indices.fill(0);
-        self.allpass_indices.fill(0);
-        
-        // Reset damping filter
-        self.damping_filter = 0.0;
-    }
-    
+    }
+
     fn name(&self) -> &str {
         "Reverb"
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,77 @@
+use super::{AudioEffect, EffectLfo, EffectParameter, ParameterUnit, SmoothedValue};
+use crate::modulation::lfo::{LfoShape, SyncDivision};
+
+/// Tremolo effect: the level rises and falls with an LFO
+#[derive(Debug, Clone)]
+pub struct TremoloEffect {
+    lfo: EffectLfo,
+    depth: SmoothedValue, // 0.0 - 1.0
+}
+
+impl TremoloEffect {
+    /// Create a new tremolo effect
+    ///
+    /// # Parameters
+    /// - `shape`: LFO waveform
+    /// - `rate_hz`: LFO rate in Hz when not synced
+    /// - `depth`: How far the level dips (0.0 - 1.0)
+    /// - `sample_rate`: Audio sample rate
+    pub fn new(shape: LfoShape, rate_hz: f32, depth: f32, sample_rate: u32) -> Self {
+        Self {
+            lfo: EffectLfo::new(shape, rate_hz, sample_rate),
+            depth: SmoothedValue::new(depth.clamp(0.0, 1.0), sample_rate),
+        }
+    }
+
+    /// Lock one tremolo cycle to a note length at the project tempo
+    pub fn with_sync(mut self, division: SyncDivision) -> Self {
+        self.lfo.set_sync(Some(division));
+        self
+    }
+
+    /// Gain for the current LFO position, dipping from 1.0 down to 1.0 - depth
+    fn next_gain(&mut self) -> f32 {
+        1.0 - self.depth.next() * (0.5 - 0.5 * self.lfo.next_value())
+    }
+}
+
+impl AudioEffect for TremoloEffect {
+    fn process_sample(&mut self, input: f32) -> f32 {
+        input * self.next_gain()
+    }
+
+    fn process_stereo(&mut self, left: f32, right: f32) -> (f32, f32) {
+        let gain = self.next_gain();
+        (left * gain, right * gain)
+    }
+
+    fn set_tempo(&mut self, tempo_bpm: f32) {
+        self.lfo.set_tempo(tempo_bpm);
+    }
+
+    fn parameters(&self) -> Vec<EffectParameter> {
+        let mut parameters = self.lfo.parameters().to_vec();
+        parameters.push(EffectParameter::new("DEPTH", self.depth.target(), 0.0, 1.0, ParameterUnit::Percent));
+        parameters
+    }
+
+    fn set_parameter(&mut self, index: usize, value: f32) {
+        match index {
+            0..=2 => self.lfo.set_parameter(index, value),
+            3 => self.depth.set(value.clamp(0.0, 1.0)),
+            _ => {}
+        }
+    }
+
+    fn snap(&mut self) {
+        self.depth.jump();
+    }
+
+    fn reset(&mut self) {
+        self.lfo.reset();
+    }
+
+    fn name(&self) -> &str {
+        "Tremolo"
+    }
+}
@@ -0,0 +1,124 @@
+use std::f32::consts::PI;
+
+/// Response of a biquad equalizer band
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BiquadShape {
+    LowShelf,
+    Peaking,
+    HighShelf,
+}
+
+/// Second-order IIR filter with RBJ cookbook coefficients (transposed direct form II)
+///
+/// Meant for fixed or slowly changing settings such as EQ bands; use the
+/// state-variable filter for anything swept every sample.
+#[derive(Debug, Clone)]
+pub struct Biquad {
+    // Normalized coefficients (a0 = 1)
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+
+    // Filter states
+    z1: f32,
+    z2: f32,
+
+    sample_rate: u32,
+}
+
+impl Biquad {
+    /// Create a new biquad filter
+    ///
+    /// # Parameters
+    /// - `shape`: Shelf or peaking response
+    /// - `frequency`: Corner (shelves) or centre (peaking) frequency in Hz
+    /// - `gain_db`: Boost or cut in dB
+    /// - `q`: Bandwidth of a peak, or steepness of a shelf (0.707 for a plain shelf)
+    /// - `sample_rate`: Audio sample rate
+    pub fn new(shape: BiquadShape, frequency: f32, gain_db: f32, q: f32, sample_rate: u32) -> Self {
+        let nyquist = sample_rate as f32 / 2.0;
+        let a = 10.0f32.powf(gain_db / 40.0);
+        let w0 = 2.0 * PI * frequency.clamp(10.0, nyquist * 0.95) / sample_rate as f32;
+        let (sin_w0, cos_w0) = w0.sin_cos();
+        let alpha = sin_w0 / (2.0 * q.max(0.1));
+        let shelf_alpha = 2.0 * a.sqrt() * alpha;
+
+        let (b0, b1, b2, a0, a1, a2) = match shape {
+            BiquadShape::Peaking => (
+                1.0 + alpha * a,
+                -2.0 * cos_w0,
+                1.0 - alpha * a,
+                1.0 + alpha / a,
+                -2.0 * cos_w0,
+                1.0 - alpha / a,
+            ),
+            BiquadShape::LowShelf => (
+                a * ((a + 1.0) - (a - 1.0) * cos_w0 + shelf_alpha),
+                2.0 * a * ((a - 1.0) - (a + 1.0) * cos_w0),
+                a * ((a + 1.0) - (a - 1.0) * cos_w0 - shelf_alpha),
+                (a + 1.0) + (a - 1.0) * cos_w0 + shelf_alpha,
+                -2.0 * ((a - 1.0) + (a + 1.0) * cos_w0),
+                (a + 1.0) + (a - 1.0) * cos_w0 - shelf_alpha,
+            ),
+            BiquadShape::HighShelf => (
+                a * ((a + 1.0) + (a - 1.0) * cos_w0 + shelf_alpha),
+                -2.0 * a * ((a - 1.0) + (a + 1.0) * cos_w0),
+                a * ((a + 1.0) + (a - 1.0) * cos_w0 - shelf_alpha),
+                (a + 1.0) - (a - 1.0) * cos_w0 + shelf_alpha,
+                2.0 * ((a - 1.0) - (a + 1.0) * cos_w0),
+                (a + 1.0) - (a - 1.0) * cos_w0 - shelf_alpha,
+            ),
+        };
+
+        Self {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: a1 / a0,
+            a2: a2 / a0,
+            z1: 0.0,
+            z2: 0.0,
+            sample_rate,
+        }
+    }
+
+    /// Recalculate the coefficients for new settings, keeping the filter states
+    pub fn set_parameters(&mut self, shape: BiquadShape, frequency: f32, gain_db: f32, q: f32) {
+        let (z1, z2) = (self.z1, self.z2);
+        *self = Self::new(shape, frequency, gain_db, q, self.sample_rate);
+        self.z1 = z1;
+        self.z2 = z2;
+    }
+
+    /// Process a single sample
+    pub fn process_sample(&mut self, input: f32) -> f32 {
+        let output = self.b0 * input + self.z1;
+        self.z1 = self.b1 * input - self.a1 * output + self.z2;
+        self.z2 = self.b2 * input - self.a2 * output;
+        output
+    }
+
+    /// Clear the filter states
+    pub fn reset(&mut self) {
+        self.z1 = 0.0;
+        self.z2 = 0.0;
+    }
+
+    /// Gain of the filter in dB at a frequency, for drawing response curves
+    pub fn magnitude_db(&self, frequency: f32) -> f32 {
+        let w = 2.0 * PI * frequency / self.sample_rate as f32;
+        let (sin_w, cos_w) = w.sin_cos();
+        let (sin_2w, cos_2w) = (2.0 * w).sin_cos();
+
+        // Evaluate numerator and denominator on the unit circle, z^-1 = e^-jw
+        let num_re = self.b0 + self.b1 * cos_w + self.b2 * cos_2w;
+        let num_im = -(self.b1 * sin_w + self.b2 * sin_2w);
+        let den_re = 1.0 + self.a1 * cos_w + self.a2 * cos_2w;
+        let den_im = -(self.a1 * sin_w + self.a2 * sin_2w);
+
+        let magnitude_squared = (num_re * num_re + num_im * num_im) / (den_re * den_re + den_im * den_im);
+        10.0 * magnitude_squared.max(1e-12).log10()
+    }
+}
@@ -0,0 +1,158 @@
+use std::fmt;
+use crate::waveforms::adsr_envelope::EnvelopeGenerator;
+
+pub mod state_variable;
+pub mod multimode;
+pub mod biquad;
+
+pub use state_variable::StateVariableFilter;
+pub use multimode::MultimodeFilter;
+pub use biquad::{Biquad, BiquadShape};
+
+/// Lowest cutoff frequency reachable from the cutoff knob
+pub const MIN_CUTOFF_HZ: f32 = 20.0;
+
+/// Highest cutoff frequency reachable from the cutoff knob
+pub const MAX_CUTOFF_HZ: f32 = 20000.0;
+
+/// How many octaves a full-scale filter envelope sweeps the cutoff (up or down)
+pub const FILTER_ENVELOPE_OCTAVES: f32 = 6.0;
+
+/// Note frequency at which key tracking leaves the cutoff unchanged (C4)
+pub const KEY_TRACKING_REFERENCE_HZ: f32 = 261.63;
+
+/// Maps a normalized knob position (0.0 - 1.0) to a cutoff frequency in Hz.
+/// The mapping is exponential so that every knob step covers the same musical interval.
+pub fn cutoff_from_normalized(normalized: f32) -> f32 {
+    MIN_CUTOFF_HZ * (MAX_CUTOFF_HZ / MIN_CUTOFF_HZ).powf(normalized.clamp(0.0, 1.0))
+}
+
+/// Scales a cutoff frequency so that it follows the played note.
+/// A `key_tracking` of 1.0 moves the cutoff one octave per octave played, 0.0 disables tracking.
+pub fn key_tracked_cutoff(cutoff: f32, note_frequency: f32, key_tracking: f32) -> f32 {
+    cutoff * (note_frequency / KEY_TRACKING_REFERENCE_HZ).powf(key_tracking)
+}
+
+/// Response of the filter
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FilterMode {
+    LowPass,
+    HighPass,
+    BandPass,
+    Notch,
+}
+
+impl FilterMode {
+    /// Returns the next mode in the cycle LP -> HP -> BP -> Notch -> LP
+    pub fn next(self) -> Self {
+        match self {
+            FilterMode::LowPass => FilterMode::HighPass,
+            FilterMode::HighPass => FilterMode::BandPass,
+            FilterMode::BandPass => FilterMode::Notch,
+            FilterMode::Notch => FilterMode::LowPass,
+        }
+    }
+}
+
+/// Implements the [Display] trait for [FilterMode] using the short labels shown on the filter panel
+impl fmt::Display for FilterMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FilterMode::LowPass => write!(f, "LP"),
+            FilterMode::HighPass => write!(f, "HP"),
+            FilterMode::BandPass => write!(f, "BP"),
+            FilterMode::Notch => write!(f, "NT"),
+        }
+    }
+}
+
+/// Steepness of the filter, in dB per octave
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FilterSlope {
+    Db12,
+    Db24,
+}
+
+impl FilterSlope {
+    /// Returns the other slope
+    pub fn toggled(self) -> Self {
+        match self {
+            FilterSlope::Db12 => FilterSlope::Db24,
+            FilterSlope::Db24 => FilterSlope::Db12,
+        }
+    }
+}
+
+/// Implements the [Display] trait for [FilterSlope]
+impl fmt::Display for FilterSlope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FilterSlope::Db12 => write!(f, "12"),
+            FilterSlope::Db24 => write!(f, "24"),
+        }
+    }
+}
+
+/// Resonant filter whose cutoff is swept by its own envelope and whose cutoff and
+/// resonance can be offset by external modulation (LFOs, mod matrix), one sample at a time
+#[derive(Debug, Clone)]
+pub struct EnvelopedFilter {
+    filter: MultimodeFilter,
+    base_cutoff: f32,          // Cutoff in Hz before modulation
+    base_resonance: f32,       // Resonance (0.0 - 1.0) before modulation
+    envelope: EnvelopeGenerator,
+    envelope_amount: f32,      // Bipolar envelope depth (-1.0 - 1.0)
+    envelope_level: f32,       // Most recent envelope output, exposed as a modulation source
+}
+
+impl EnvelopedFilter {
+    /// Create a new enveloped filter
+    ///
+    /// # Parameters
+    /// - `filter`: Filter to run the audio through
+    /// - `base_cutoff`: Cutoff frequency in Hz when the envelope and modulation are at zero
+    /// - `base_resonance`: Resonance the filter was created with, used when modulation is at zero
+    /// - `envelope`: Filter envelope
+    /// - `envelope_amount`: Bipolar envelope depth; 1.0 opens the filter by [FILTER_ENVELOPE_OCTAVES], -1.0 closes it by as much
+    pub fn new(filter: MultimodeFilter, base_cutoff: f32, base_resonance: f32, envelope: EnvelopeGenerator, envelope_amount: f32) -> Self {
+        Self {
+            filter,
+            base_cutoff,
+            base_resonance,
+            envelope,
+            envelope_amount: envelope_amount.clamp(-1.0, 1.0),
+            envelope_level: 0.0,
+        }
+    }
+
+    /// Most recent output of the filter envelope (0.0 - 1.0)
+    pub fn envelope_level(&self) -> f32 {
+        self.envelope_level
+    }
+
+    /// Start the release of the filter envelope together with the amplitude envelope
+    pub fn release(&mut self) {
+        self.envelope.release();
+    }
+
+    /// Filter a single sample
+    ///
+    /// # Parameters
+    /// - `input`: Sample to filter
+    /// - `cutoff_octaves`: Additional cutoff offset in octaves from other modulation sources
+    /// - `resonance_offset`: Amount added to the base resonance by other modulation sources
+    pub fn process_sample(&mut self, input: f32, cutoff_octaves: f32, resonance_offset: f32) -> f32 {
+        // Sweep the cutoff exponentially so the envelope moves in musical intervals
+        self.envelope_level = self.envelope.next_amplitude();
+        let octaves = self.envelope_amount * self.envelope_level * FILTER_ENVELOPE_OCTAVES + cutoff_octaves;
+        let cutoff = if octaves != 0.0 {
+            self.base_cutoff * 2.0f32.powf(octaves)
+        } else {
+            self.base_cutoff
+        };
+        self.filter.set_cutoff(cutoff);
+        self.filter.set_resonance(self.base_resonance + resonance_offset);
+
+        self.filter.process_sample(input)
+    }
+}
@@ -0,0 +1,52 @@
+use super::{FilterMode, FilterSlope, StateVariableFilter};
+
+/// Multimode filter with selectable slope
+///
+/// The 12 dB/octave slope is a single state-variable stage. The 24 dB/octave slope
+/// cascades two stages; only the second one resonates so the peak does not double up.
+#[derive(Debug, Clone)]
+pub struct MultimodeFilter {
+    first_stage: StateVariableFilter,
+    second_stage: StateVariableFilter,
+    slope: FilterSlope,
+}
+
+impl MultimodeFilter {
+    /// Create a new multimode filter
+    ///
+    /// # Parameters
+    /// - `mode`: Filter response (LP, HP, BP, notch)
+    /// - `slope`: 12 or 24 dB per octave
+    /// - `cutoff`: Cutoff frequency in Hz
+    /// - `resonance`: Resonance amount (0.0 - 1.0)
+    /// - `sample_rate`: Audio sample rate
+    pub fn new(mode: FilterMode, slope: FilterSlope, cutoff: f32, resonance: f32, sample_rate: u32) -> Self {
+        Self {
+            first_stage: StateVariableFilter::new(mode, cutoff, 0.0, sample_rate),
+            second_stage: StateVariableFilter::new(mode, cutoff, resonance, sample_rate),
+            slope,
+        }
+    }
+
+    /// Set cutoff frequency in Hz on both stages
+    pub fn set_cutoff(&mut self, cutoff: f32) {
+        self.first_stage.set_cutoff(cutoff);
+        self.second_stage.set_cutoff(cutoff);
+    }
+
+    /// Set resonance amount (0.0 - 1.0) on the resonating stage
+    pub fn set_resonance(&mut self, resonance: f32) {
+        self.second_stage.set_resonance(resonance);
+    }
+
+    /// Process a single sample through one or both stages depending on the slope
+    pub fn process_sample(&mut self, input: f32) -> f32 {
+        match self.slope {
+            FilterSlope::Db12 => self.second_stage.process_sample(input),
+            FilterSlope::Db24 => {
+                let first = self.first_stage.process_sample(input);
+                self.second_stage.process_sample(first)
+            }
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use std::f32::consts::PI;
+use super::FilterMode;
+
+/// Resonant state-variable filter (trapezoidal integration, zero-delay feedback)
+///
+/// Unlike a naive biquad, this topology stays stable while cutoff and resonance
+/// are changed every sample, which lets envelopes and knobs sweep it freely.
+/// A single pass yields low-pass, high-pass, band-pass and notch responses at 12 dB/octave.
+#[derive(Debug, Clone)]
+pub struct StateVariableFilter {
+    // Integrator states
+    ic1eq: f32,
+    ic2eq: f32,
+
+    // Coefficients derived from cutoff and resonance
+    g: f32,
+    k: f32,
+
+    // Parameters
+    mode: FilterMode,
+    cutoff: f32,      // Cutoff frequency in Hz
+    resonance: f32,   // Resonance amount (0.0 - 1.0)
+
+    sample_rate: u32,
+}
+
+impl StateVariableFilter {
+    /// Create a new state-variable filter
+    ///
+    /// # Parameters
+    /// - `mode`: Which response (low-pass, high-pass, band-pass, notch) to output
+    /// - `cutoff`: Cutoff frequency in Hz
+    /// - `resonance`: Resonance amount (0.0 = flat, 1.0 = close to self-oscillation)
+    /// - `sample_rate`: Audio sample rate
+    pub fn new(mode: FilterMode, cutoff: f32, resonance: f32, sample_rate: u32) -> Self {
+        let mut filter = Self {
+            ic1eq: 0.0,
+            ic2eq: 0.0,
+            g: 0.0,
+            k: 0.0,
+            mode,
+            cutoff,
+            resonance: resonance.clamp(0.0, 1.0),
+            sample_rate,
+        };
+        filter.update_coefficients();
+        filter
+    }
+
+    /// Set cutoff frequency in Hz
+    pub fn set_cutoff(&mut self, cutoff: f32) {
+        if cutoff != self.cutoff {
+            self.cutoff = cutoff;
+            self.update_coefficients();
+        }
+    }
+
+    /// Set resonance amount (0.0 - 1.0)
+    pub fn set_resonance(&mut self, resonance: f32) {
+        let resonance = resonance.clamp(0.0, 1.0);
+        if resonance != self.resonance {
+            self.resonance = resonance;
+            self.update_coefficients();
+        }
+    }
+
+    /// Recalculate the filter coefficients from cutoff and resonance
+    fn update_coefficients(&mut self) {
+        // Keep the cutoff safely below Nyquist so tan() does not blow up
+        let nyquist = self.sample_rate as f32 * 0.49;
+        let cutoff = self.cutoff.clamp(10.0, nyquist);
+
+        // Q sweeps exponentially from Butterworth (0.707) up to a screaming 20
+        let q = 0.707 * (20.0f32 / 0.707).powf(self.resonance);
+
+        self.g = (PI * cutoff / self.sample_rate as f32).tan();
+        self.k = 1.0 / q;
+    }
+
+    /// Process a single sample and return the output of the selected mode
+    pub fn process_sample(&mut self, input: f32) -> f32 {
+        let a1 = 1.0 / (1.0 + self.g * (self.g + self.k));
+        let a2 = self.g * a1;
+        let a3 = self.g * a2;
+
+        let v3 = input - self.ic2eq;
+        let v1 = a1 * self.ic1eq + a2 * v3;
+        let v2 = self.ic2eq + a2 * self.ic1eq + a3 * v3;
+
+        self.ic1eq = 2.0 * v1 - self.ic1eq;
+        self.ic2eq = 2.0 * v2 - self.ic2eq;
+
+        let low = v2;
+        // Scaled by k so the band-pass peaks at unity rather than at Q
+        let band = self.k * v1;
+        let high = input - band - v2;
+
+        match self.mode {
+            FilterMode::LowPass => low,
+            FilterMode::HighPass => high,
+            FilterMode::BandPass => band,
+            FilterMode::Notch => low + high,
+        }
+    }
+}
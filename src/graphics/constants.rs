@@ -2,6 +2,58 @@
 pub const WINDOW_WIDTH: usize = 575;
 pub const WINDOW_HEIGHT: usize = 496;
 
+// Constants for the filter panel (bulb, cutoff and resonance knobs), placed above the rack
+pub const FILTER_PANEL_X: usize = 280;
+pub const FILTER_PANEL_Y: usize = 118;
+
+// Constants for the LFO panel (one row per track LFO plus the tempo), placed above the filter panel
+pub const LFO_PANEL_X: usize = 272;
+pub const LFO_PANEL_Y: usize = 14;
+pub const LFO_ROW_HEIGHT: usize = 24;
+
+// Constants for the amplitude envelope graph and its segment curve bars, placed between the LFO and filter panels
+pub const ENVELOPE_GRAPH_X: usize = 330;
+pub const ENVELOPE_GRAPH_Y: usize = 74;
+pub const ENVELOPE_GRAPH_WIDTH: usize = 150;
+pub const ENVELOPE_GRAPH_HEIGHT: usize = 36;
+
+// Constants for the velocity panel (keyboard velocity and per-track sensitivity), placed below the track list
+pub const VELOCITY_PANEL_X: usize = 10;
+pub const VELOCITY_PANEL_Y: usize = 116;
+
+// Constants for the effect chains (master row above the current track row), placed on the rack face
+pub const EFFECTS_PANEL_X: usize = 70;
+pub const EFFECTS_PANEL_Y: usize = 174;
+pub const EFFECT_BUTTON_WIDTH: usize = 22;
+pub const EFFECT_BUTTON_HEIGHT: usize = 18;
+pub const EFFECT_BUTTON_SPACING: usize = 3;
+pub const EFFECT_EDIT_X: usize = 326; // Move, bypass and remove buttons for the selected slot, on the master row
+pub const NEW_EFFECT_X: usize = 326;  // Effect type the + buttons add, on the track row
+pub const SIDECHAIN_BUTTON_X: usize = 500; // Compressor key of the current track, at the end of the track row
+pub const SIDECHAIN_BUTTON_WIDTH: usize = 40;
+pub const LIMITER_BUTTON_X: usize = 500; // Master limiter and its clip light, at the end of the master row
+pub const LIMITER_BUTTON_WIDTH: usize = 40;
+pub const CLIP_LIGHT_HOLD_SECONDS: f32 = 1.0; // How long the clip light stays lit after the last overload
+
+// Constants for the editor of the selected effect slot (a grid of knobs), shown in place of the waveform display
+pub const EFFECT_EDITOR_X: usize = 164;
+pub const EFFECT_EDITOR_Y: usize = 221;
+pub const EFFECT_EDITOR_COLUMNS: usize = 4;
+pub const EFFECT_KNOB_CELL_WIDTH: usize = 41;
+pub const EFFECT_KNOB_CELL_HEIGHT: usize = 17;
+pub const EFFECT_KNOB_DRAG_PIXELS: f32 = 100.0; // Mouse travel that turns a knob through its whole range
+
+// Constants for the EQ response curve of the current track, placed between the waveform display and the faders
+pub const EQ_GRAPH_X: usize = 334;
+pub const EQ_GRAPH_Y: usize = 221;
+pub const EQ_GRAPH_WIDTH: usize = 92;
+pub const EQ_GRAPH_HEIGHT: usize = 50;
+
+// Constants for the mod matrix strip (one column per slot), placed below the keys
+pub const MOD_MATRIX_X: usize = 8;
+pub const MOD_MATRIX_Y: usize = 444;
+pub const MOD_SLOT_WIDTH: usize = 141;
+
 // Constants for waveforms
 pub const WAVEFORM_SINE: usize = 0;
 pub const WAVEFORM_SQUARE: usize = 1;
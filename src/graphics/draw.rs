@@ -1,8 +1,11 @@
 use std::collections::HashMap;
 use minifb::Window;
-use crate::graphics::constants::{KEY_IDLE, KEY_PRESSED, TANGENT_IDLE, TANGENT_PRESSED, WINDOW_HEIGHT, WINDOW_WIDTH};
+use crate::graphics::constants::{ENVELOPE_GRAPH_HEIGHT, ENVELOPE_GRAPH_WIDTH, ENVELOPE_GRAPH_X, ENVELOPE_GRAPH_Y, EQ_GRAPH_HEIGHT, EQ_GRAPH_WIDTH, EQ_GRAPH_X, EQ_GRAPH_Y, FILTER_PANEL_X, FILTER_PANEL_Y, KEY_IDLE, KEY_PRESSED, TANGENT_IDLE, TANGENT_PRESSED, WINDOW_HEIGHT, WINDOW_WIDTH};
 use crate::graphics::sprites::{draw_sprite, Sprite, Sprites};
-use crate::state::State;
+use crate::effects::eq::{EQ_MAX_GAIN_DB, EQ_MAX_HZ, EQ_MIN_HZ};
+use crate::effects::{EffectKind, ParametricEqEffect};
+use crate::state::{EffectTarget, State};
+use crate::waveforms::adsr_envelope::{EnvelopeGenerator, DISPLAY_RATE};
 
 /// Draws the text sprite.
 ///
@@ -71,53 +74,51 @@ pub fn draw_buffer(window: &mut Window, window_buffer: &mut Vec<u32>) {
     window.update_with_buffer(&window_buffer, WINDOW_WIDTH, WINDOW_HEIGHT).unwrap();
 }
 
-/// Draws idle knobs.
+/// Draws the LPF bulb, lit when the current track's filter is active.
 ///
 /// # Parameters
 /// - `state`: Reference to the current `State` containing the state of the synthesizer.
 /// - `sprites`: A reference to the `Sprites` struct containing all the sprite images.
 /// - `window_buffer`: A mutable reference to the buffer representing the window's pixels.
-pub fn draw_bulb_sprite(state: &State, sprites: &Sprites, window_buffer: &mut Vec<u32>) {
-    draw_sprite(6 * sprites.knob[0].width as usize,
-                5 * sprites.knob[0].height as usize + 10,
-                &sprites.bulb[state.lpf_active], window_buffer, WINDOW_WIDTH);
+pub fn draw_bulb_sprite(state: &State, sprites: &Sprites, window_buffer: &mut [u32]) {
+    let bulb_index = state.current_track().lpf_active as usize;
+    draw_sprite(FILTER_PANEL_X,
+                FILTER_PANEL_Y + 18,
+                &sprites.bulb[bulb_index], window_buffer, WINDOW_WIDTH);
 }
 
-/// Draws idle knobs.
+/// Draws the filter cutoff knob for the current track.
 ///
 /// # Parameters
 /// - `state`: Reference to the current `State` containing the state of the synthesizer.
 /// - `sprites`: A reference to the `Sprites` struct containing all the sprite images.
 /// - `window_buffer`: A mutable reference to the buffer representing the window's pixels.
-pub fn draw_filter_cutoff_knob_sprite(state: &State, sprites: &Sprites, window_buffer: &mut Vec<u32>) {
-    let filter_cutoff = state.filter_factor;
-
-    // Assigns the appropriate sprite index based on cutoff float value threshold
-    let knob_sprite_index = match filter_cutoff {
-        v if (0.0..=0.14).contains(&v) => 0,
-        v if (0.14..=0.28).contains(&v) => 1,
-        v if (0.28..=0.42).contains(&v) => 2,
-        v if (0.42..=0.57).contains(&v) => 3,
-        v if (0.57..=0.71).contains(&v) => 4,
-        v if (0.71..=0.85).contains(&v) => 5,
-        v if (0.85..=0.99).contains(&v) => 6,
-        _ => 7 // Last knob for ~0.99
-    };
+pub fn draw_filter_cutoff_knob_sprite(state: &State, sprites: &Sprites, window_buffer: &mut [u32]) {
+    let knob_sprite_index = knob_sprite_index(state.current_track().filter_cutoff, sprites);
 
-    draw_sprite(6 * sprites.knob[0].width as usize,
-                5 * sprites.knob[0].height as usize - 10,
+    draw_sprite(FILTER_PANEL_X + 14,
+                FILTER_PANEL_Y,
                 &sprites.knob[knob_sprite_index], window_buffer, WINDOW_WIDTH);
 }
 
-/// Draws idle knob.
+/// Draws the filter resonance knob for the current track, to the right of the cutoff knob.
 ///
 /// # Parameters
+/// - `state`: Reference to the current `State` containing the state of the synthesizer.
 /// - `sprites`: A reference to the `Sprites` struct containing all the sprite images.
 /// - `window_buffer`: A mutable reference to the buffer representing the window's pixels.
-pub fn draw_idle_knob_sprite(sprites: &Sprites, window_buffer: &mut Vec<u32>) {
-    draw_sprite(7 * sprites.knob[0].width as usize,
-                5 * sprites.knob[0].height as usize - 10,
-                &sprites.knob[0], window_buffer, WINDOW_WIDTH);
+pub fn draw_filter_resonance_knob_sprite(state: &State, sprites: &Sprites, window_buffer: &mut [u32]) {
+    let knob_sprite_index = knob_sprite_index(state.current_track().filter_resonance, sprites);
+
+    draw_sprite(FILTER_PANEL_X + 14 + sprites.knob[0].width as usize,
+                FILTER_PANEL_Y,
+                &sprites.knob[knob_sprite_index], window_buffer, WINDOW_WIDTH);
+}
+
+/// Maps a normalized knob value (0.0 - 1.0) onto one of the knob sprite positions
+fn knob_sprite_index(value: f32, sprites: &Sprites) -> usize {
+    let last_index = sprites.knob.len() - 1;
+    ((value.clamp(0.0, 1.0) * last_index as f32).round() as usize).min(last_index)
 }
 
 /// Draws the note sprite for the given note sprite index.
@@ -184,10 +185,10 @@ pub fn draw_idle_key_sprites(sprites: &Sprites, window_buffer: &mut Vec<u32>) {
 /// - `sprites`: A reference to the `Sprites` struct containing all the sprite images.
 /// - `window_buffer`: A mutable reference to the buffer representing the window's pixels.
 pub fn draw_adsr_faders(state: &State, sprites: &Sprites, window_buffer: &mut Vec<u32>) {
-    // Compact fader dimensions to fit all 4 ADSR faders
-    let fader_width = 25;
+    // Compact fader dimensions to fit the delay and hold faders plus all 4 ADSR faders
+    let fader_width = 18;
     let fader_height = 50;
-    let fader_spacing = 30; // Minimal spacing between faders
+    let fader_spacing = 23; // Minimal spacing between faders
 
     // Position faders directly to the right of waveform visualizer
     // Display is positioned at: x = 1 * 164 = 164, y = 4 * 51 + 17 = 221
@@ -195,11 +196,12 @@ pub fn draw_adsr_faders(state: &State, sprites: &Sprites, window_buffer: &mut Ve
     let display_width = 164; // Display width (from DISPLAY_WIDTH constant)
     let display_y = 4 * 51 + 17; // Display y position
 
-    let base_x = display_x + display_width + 104; // Start right after display (164 + 164 + 5 = 333px)
+    let base_x = display_x + display_width + 104 + 2 * fader_spacing; // Start after the delay and hold faders
     let base_y = display_y; // Same y as display
 
-    // ADSR values
-    let adsr_values = [state.attack, state.decay, state.sustain, state.release];
+    // ADSR values of the current track
+    let track = state.current_track();
+    let adsr_values = [track.attack, track.decay, track.sustain, track.release];
     let labels = ["A", "D", "S", "R"];
 
     // Draw each ADSR fader
@@ -219,8 +221,219 @@ pub fn draw_adsr_faders(state: &State, sprites: &Sprites, window_buffer: &mut Ve
     }
 }
 
+/// Draws the delay (DL) and hold (H) faders of the current track's envelope
+/// to the left of the ADSR faders, completing the DAHDSR set.
+///
+/// # Parameters
+/// - `state`: Reference to the current `State` containing the track's envelope settings.
+/// - `window_buffer`: A mutable reference to the buffer representing the window's pixels.
+pub fn draw_delay_hold_faders(state: &State, window_buffer: &mut [u32]) {
+    let fader_width = 18;
+    let fader_height = 50;
+    let fader_spacing = 23;
+
+    // Same row as the ADSR faders (matching draw_adsr_faders)
+    let base_x = 164 + 164 + 104;
+    let base_y = 4 * 51 + 17;
+
+    let track = state.current_track();
+    let values = [track.delay, track.hold];
+    let labels = ["DL", "H"];
+
+    for (i, (&value, &label)) in values.iter().zip(labels.iter()).enumerate() {
+        let x = base_x + i * fader_spacing;
+
+        draw_fader_background(x, base_y, fader_width, fader_height, window_buffer);
+
+        let fill_height = (value as f32 / 99.0 * (fader_height - 4) as f32) as usize;
+        draw_fader_fill(x + 2, base_y + (fader_height - 2 - fill_height), fader_width - 4, fill_height, window_buffer);
+
+        // Centre the label, which may be two glyphs wide
+        let label_width = label.len() * 6 - 1;
+        draw_fader_label(x + (fader_width - label_width) / 2, base_y + fader_height + 3, label, window_buffer);
+    }
+}
+
+/// Draws the filter envelope faders (A, D, S, R), the bipolar envelope amount (E)
+/// and the key tracking amount (K) of the current track to the right of the filter buttons.
+///
+/// # Parameters
+/// - `state`: Reference to the current `State` containing the track's filter settings.
+/// - `window_buffer`: A mutable reference to the buffer representing the window's pixels.
+pub fn draw_filter_envelope_faders(state: &State, window_buffer: &mut [u32]) {
+    let fader_width = 14;
+    let fader_height = 40;
+    let fader_spacing = 18;
+
+    // Starts right after the mode/slope buttons (matching draw_filter_mode_buttons)
+    let base_x = FILTER_PANEL_X + 14 + 64 + 64 + 30;
+    let base_y = FILTER_PANEL_Y + 2;
+
+    let track = state.current_track();
+    let envelope_values = [track.filter_env_attack, track.filter_env_decay, track.filter_env_sustain, track.filter_env_release];
+    let labels = ["A", "D", "S", "R"];
+    let inner_height = fader_height - 4;
+
+    for (i, (&value, &label)) in envelope_values.iter().zip(labels.iter()).enumerate() {
+        let x = base_x + i * fader_spacing;
+
+        draw_fader_background(x, base_y, fader_width, fader_height, window_buffer);
+
+        let fill_height = (value as f32 / 99.0 * inner_height as f32) as usize;
+        draw_fader_fill(x + 2, base_y + (fader_height - 2 - fill_height), fader_width - 4, fill_height, window_buffer);
+
+        draw_fader_label(x + fader_width / 2 - 2, base_y + fader_height + 3, label, window_buffer);
+    }
+
+    // Envelope amount is bipolar, so it fills up or down from the middle of the fader
+    let amount_x = base_x + 4 * fader_spacing;
+    draw_fader_background(amount_x, base_y, fader_width, fader_height, window_buffer);
+    let center_y = base_y + 2 + inner_height / 2;
+    let amount_height = (track.filter_env_amount.abs() * (inner_height / 2) as f32) as usize;
+    if track.filter_env_amount >= 0.0 {
+        draw_fader_fill(amount_x + 2, center_y - amount_height, fader_width - 4, amount_height, window_buffer);
+    } else {
+        draw_fader_fill(amount_x + 2, center_y, fader_width - 4, amount_height, window_buffer);
+    }
+    draw_fader_label(amount_x + fader_width / 2 - 2, base_y + fader_height + 3, "E", window_buffer);
+
+    let key_x = base_x + 5 * fader_spacing;
+    draw_fader_background(key_x, base_y, fader_width, fader_height, window_buffer);
+    let key_height = (track.filter_key_tracking * inner_height as f32) as usize;
+    draw_fader_fill(key_x + 2, base_y + (fader_height - 2 - key_height), fader_width - 4, key_height, window_buffer);
+    draw_fader_label(key_x + fader_width / 2 - 2, base_y + fader_height + 3, "K", window_buffer);
+}
+
+/// Draws the amplitude envelope of the current track as a graph, followed by the
+/// attack, decay and release curvature bars (bipolar, filled from the centre).
+///
+/// The graph steps the same `EnvelopeGenerator` the voices use, so it shows exactly what is heard.
+///
+/// # Parameters
+/// - `state`: Reference to the current `State` containing the track's envelope settings.
+/// - `window_buffer`: A mutable reference to the buffer representing the window's pixels.
+pub fn draw_envelope_graph(state: &State, window_buffer: &mut [u32]) {
+    let track = state.current_track();
+    let plot_color = 0xFF00AA00; // Green, like the fader fill
+
+    // Run the envelope at the display rate, which is plenty of resolution for the graph
+    let mut envelope = EnvelopeGenerator::new(&track.amp_envelope(), DISPLAY_RATE);
+    let mut levels = Vec::new();
+    while !envelope.is_finished() && levels.len() < 10 * DISPLAY_RATE as usize {
+        levels.push(envelope.next_amplitude());
+    }
+
+    draw_fader_background(ENVELOPE_GRAPH_X, ENVELOPE_GRAPH_Y, ENVELOPE_GRAPH_WIDTH, ENVELOPE_GRAPH_HEIGHT, window_buffer);
+
+    let plot_width = ENVELOPE_GRAPH_WIDTH - 4;
+    let plot_height = ENVELOPE_GRAPH_HEIGHT - 4;
+    let bottom_y = ENVELOPE_GRAPH_Y + 2 + plot_height - 1;
+    let mut previous_y = bottom_y;
+
+    for px in 0..plot_width {
+        let level = levels.get(px * levels.len() / plot_width).copied().unwrap_or(0.0);
+        let y = bottom_y - (level.clamp(0.0, 1.0) * (plot_height - 1) as f32) as usize;
+
+        // Connect to the previous column so steep segments stay visible
+        let (top, bottom) = if y < previous_y { (y, previous_y) } else { (previous_y, y) };
+        for plot_y in top..=bottom {
+            let index = plot_y * WINDOW_WIDTH + ENVELOPE_GRAPH_X + 2 + px;
+            if index < window_buffer.len() {
+                window_buffer[index] = plot_color;
+            }
+        }
+        previous_y = y;
+    }
+
+    // Curvature bars for attack, decay and release to the right of the graph
+    let bar_x = ENVELOPE_GRAPH_X + ENVELOPE_GRAPH_WIDTH + 16;
+    let bar_width = 40;
+    let bar_height = 10;
+    let curves = [track.envelope_curves.attack, track.envelope_curves.decay, track.envelope_curves.release];
+    let labels = ["A", "D", "R"];
+
+    for (i, (&curvature, &label)) in curves.iter().zip(labels.iter()).enumerate() {
+        let y = ENVELOPE_GRAPH_Y + i * (bar_height + 3);
+        draw_fader_label(bar_x - 9, y + 1, label, window_buffer);
+        draw_fader_background(bar_x, y, bar_width, bar_height, window_buffer);
+
+        let center_x = bar_x + bar_width / 2;
+        let fill_width = (curvature.abs() * (bar_width / 2 - 2) as f32) as usize;
+        if curvature >= 0.0 {
+            draw_fader_fill(center_x, y + 2, fill_width, bar_height - 4, window_buffer);
+        } else {
+            draw_fader_fill(center_x - fill_width, y + 2, fill_width, bar_height - 4, window_buffer);
+        }
+    }
+}
+
+/// Draws the frequency response of the selected EQ slot, or else the current track's first EQ,
+/// on a log frequency axis, dimmed while the EQ is bypassed
+pub fn draw_eq_response(state: &State, window_buffer: &mut [u32]) {
+    let selected_eq = state.selected_effect
+        .and_then(|(target, index)| state.effect_chain(target).slots().get(index))
+        .filter(|slot| slot.kind == EffectKind::Eq);
+    let eq_slot = selected_eq.or_else(|| {
+        let chain = state.effect_chain(EffectTarget::Track);
+        chain.position(EffectKind::Eq).map(|index| &chain.slots()[index])
+    });
+
+    draw_fader_background(EQ_GRAPH_X, EQ_GRAPH_Y, EQ_GRAPH_WIDTH, EQ_GRAPH_HEIGHT, window_buffer);
+
+    let plot_width = EQ_GRAPH_WIDTH - 4;
+    let plot_height = EQ_GRAPH_HEIGHT - 4;
+    let center_y = EQ_GRAPH_Y + 2 + plot_height / 2;
+    let frequency_at = |px: usize| EQ_MIN_HZ * (EQ_MAX_HZ / EQ_MIN_HZ).powf(px as f32 / (plot_width - 1) as f32);
+    let y_for_gain = |gain_db: f32| {
+        let offset = (gain_db / EQ_MAX_GAIN_DB).clamp(-1.0, 1.0) * (plot_height / 2 - 1) as f32;
+        (center_y as f32 - offset) as usize
+    };
+
+    // 0 dB reference line
+    for px in 0..plot_width {
+        window_buffer[center_y * WINDOW_WIDTH + EQ_GRAPH_X + 2 + px] = 0xFF404040;
+    }
+
+    // Nothing more to draw until an EQ is added
+    let Some((eq, bypassed)) = eq_slot.and_then(|slot| slot.effect::<ParametricEqEffect>().map(|eq| (eq, slot.bypassed))) else {
+        return;
+    };
+    let plot_color = if bypassed { 0xFF666666 } else { EffectKind::Eq.color() };
+
+    let mut previous_y = y_for_gain(eq.response_db(frequency_at(0)));
+    for px in 0..plot_width {
+        let y = y_for_gain(eq.response_db(frequency_at(px)));
+
+        // Connect to the previous column so steep slopes stay visible
+        let (top, bottom) = if y < previous_y { (y, previous_y) } else { (previous_y, y) };
+        for plot_y in top..=bottom {
+            let index = plot_y * WINDOW_WIDTH + EQ_GRAPH_X + 2 + px;
+            if index < window_buffer.len() {
+                window_buffer[index] = plot_color;
+            }
+        }
+        previous_y = y;
+    }
+
+    // Mark every band at its frequency and gain
+    for band in eq.bands() {
+        let position = (band.frequency / EQ_MIN_HZ).ln() / (EQ_MAX_HZ / EQ_MIN_HZ).ln();
+        let px = (position.clamp(0.0, 1.0) * (plot_width - 1) as f32) as usize;
+        let y = y_for_gain(band.gain_db);
+        for dy in 0..3 {
+            for dx in 0..3 {
+                let x = (EQ_GRAPH_X + 1 + px + dx).min(EQ_GRAPH_X + EQ_GRAPH_WIDTH - 3);
+                let index = (y + dy - 1) * WINDOW_WIDTH + x;
+                if index < window_buffer.len() {
+                    window_buffer[index] = 0xFFFFFFFF;
+                }
+            }
+        }
+    }
+}
+
 /// Draws a fader background rectangle
-fn draw_fader_background(x: usize, y: usize, width: usize, height: usize, buffer: &mut Vec<u32>) {
+fn draw_fader_background(x: usize, y: usize, width: usize, height: usize, buffer: &mut [u32]) {
     let border_color = 0xFF404040; // Dark gray
     let bg_color = 0xFF202020;     // Very dark gray
 
@@ -243,7 +456,7 @@ fn draw_fader_background(x: usize, y: usize, width: usize, height: usize, buffer
 }
 
 /// Draws the fader fill based on value
-fn draw_fader_fill(x: usize, y: usize, width: usize, height: usize, buffer: &mut Vec<u32>) {
+fn draw_fader_fill(x: usize, y: usize, width: usize, height: usize, buffer: &mut [u32]) {
     let fill_color = 0xFF00AA00; // Green
 
     for dy in 0..height {
@@ -390,13 +603,21 @@ fn draw_simple_text(x: usize, y: usize, text: &str, color: u32, buffer: &mut Vec
 }
 
 
-/// Draws a simple text label for the fader
-fn draw_fader_label(x: usize, y: usize, label: &str, buffer: &mut Vec<u32>) {
+/// Draws a simple text label for the fader, one 5x7 glyph every 6 pixels
+fn draw_fader_label(x: usize, y: usize, label: &str, buffer: &mut [u32]) {
+    for (i, glyph) in label.chars().enumerate() {
+        draw_fader_glyph(x + i * 6, y, glyph, buffer);
+    }
+}
+
+/// Draws a single character of a fader label
+fn draw_fader_glyph(x: usize, y: usize, glyph: char, buffer: &mut [u32]) {
     let text_color = 0xFFFFFFFF; // White
 
-    // Simple 5x7 pixel font for A, D, S, R
-    let patterns = match label {
-        "A" => vec![ // A
+    // Simple 5x7 pixel font for A, D, S, R, the delay/hold stages' L and H,
+    // and the filter envelope's E (amount) and K (key tracking)
+    let patterns = match glyph {
+        'A' => vec![ // A
                      0b01110,
                      0b10001,
                      0b10001,
@@ -405,7 +626,7 @@ fn draw_fader_label(x: usize, y: usize, label: &str, buffer: &mut Vec<u32>) {
                      0b10001,
                      0b10001,
         ],
-        "D" => vec![ // D
+        'D' => vec![ // D
                      0b11110,
                      0b10001,
                      0b10001,
@@ -414,7 +635,7 @@ fn draw_fader_label(x: usize, y: usize, label: &str, buffer: &mut Vec<u32>) {
                      0b10001,
                      0b11110,
         ],
-        "S" => vec![ // S
+        'S' => vec![ // S
                      0b01111,
                      0b10000,
                      0b10000,
@@ -423,7 +644,7 @@ fn draw_fader_label(x: usize, y: usize, label: &str, buffer: &mut Vec<u32>) {
                      0b00001,
                      0b11110,
         ],
-        "R" => vec![ // R
+        'R' => vec![ // R
                      0b11110,
                      0b10001,
                      0b10001,
@@ -432,6 +653,42 @@ fn draw_fader_label(x: usize, y: usize, label: &str, buffer: &mut Vec<u32>) {
                      0b10010,
                      0b10001,
         ],
+        'E' => vec![ // E
+                     0b11111,
+                     0b10000,
+                     0b10000,
+                     0b11110,
+                     0b10000,
+                     0b10000,
+                     0b11111,
+        ],
+        'K' => vec![ // K
+                     0b10001,
+                     0b10010,
+                     0b10100,
+                     0b11000,
+                     0b10100,
+                     0b10010,
+                     0b10001,
+        ],
+        'H' => vec![ // H
+                     0b10001,
+                     0b10001,
+                     0b10001,
+                     0b11111,
+                     0b10001,
+                     0b10001,
+                     0b10001,
+        ],
+        'L' => vec![ // L
+                     0b10000,
+                     0b10000,
+                     0b10000,
+                     0b10000,
+                     0b10000,
+                     0b10000,
+                     0b11111,
+        ],
         _ => return,
     };
 
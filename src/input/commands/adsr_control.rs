@@ -1,5 +1,5 @@
 use minifb::{Key, KeyRepeat, Window};
-use rodio::Sink;
+use crate::audio::MixBus;
 use crate::state::State;
 use super::super::InputCommand;
 
@@ -36,7 +36,7 @@ impl ADSRControlCommand {
 }
 
 impl InputCommand for ADSRControlCommand {
-    fn execute(&self, state: &mut State, window: &mut Window, sink: &mut Sink) {
+    fn execute(&self, state: &mut State, window: &mut Window, bus: &MixBus) {
         let key = match (self.parameter, self.increase) {
             (ADSRParameter::Attack, false) => Key::F3,
             (ADSRParameter::Attack, true) => Key::F4,
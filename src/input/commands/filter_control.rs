@@ -0,0 +1,70 @@
+use minifb::{Key, KeyRepeat, Window};
+use crate::audio::MixBus;
+use crate::state::State;
+use super::super::InputCommand;
+
+/// Command for controlling the current track's resonant multimode filter
+pub struct FilterControlCommand {
+    action: FilterAction,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum FilterAction {
+    Toggle,
+    CycleMode,
+    ToggleSlope,
+    CutoffDown,
+    CutoffUp,
+    ResonanceDown,
+    ResonanceUp,
+}
+
+impl FilterControlCommand {
+    pub fn new_toggle() -> Self {
+        Self { action: FilterAction::Toggle }
+    }
+
+    pub fn new_cycle_mode() -> Self {
+        Self { action: FilterAction::CycleMode }
+    }
+
+    pub fn new_toggle_slope() -> Self {
+        Self { action: FilterAction::ToggleSlope }
+    }
+
+    pub fn new_cutoff(increase: bool) -> Self {
+        let action = if increase { FilterAction::CutoffUp } else { FilterAction::CutoffDown };
+        Self { action }
+    }
+
+    pub fn new_resonance(increase: bool) -> Self {
+        let action = if increase { FilterAction::ResonanceUp } else { FilterAction::ResonanceDown };
+        Self { action }
+    }
+}
+
+impl InputCommand for FilterControlCommand {
+    fn execute(&self, state: &mut State, window: &mut Window, _bus: &MixBus) {
+        let key = match self.action {
+            FilterAction::Toggle => Key::L,
+            FilterAction::CycleMode => Key::K,
+            FilterAction::ToggleSlope => Key::J,
+            FilterAction::CutoffDown => Key::Z,
+            FilterAction::CutoffUp => Key::X,
+            FilterAction::ResonanceDown => Key::C,
+            FilterAction::ResonanceUp => Key::V,
+        };
+
+        if window.is_key_pressed(key, KeyRepeat::No) {
+            match self.action {
+                FilterAction::Toggle => state.toggle_current_track_lpf(),
+                FilterAction::CycleMode => state.cycle_current_track_filter_mode(),
+                FilterAction::ToggleSlope => state.toggle_current_track_filter_slope(),
+                FilterAction::CutoffDown => state.decrease_current_track_filter_cutoff(),
+                FilterAction::CutoffUp => state.increase_current_track_filter_cutoff(),
+                FilterAction::ResonanceDown => state.decrease_current_track_filter_resonance(),
+                FilterAction::ResonanceUp => state.increase_current_track_filter_resonance(),
+            }
+        }
+    }
+}
@@ -1,14 +1,10 @@
 use minifb::{Key, KeyRepeat, Window};
-use rodio::{Sink, Source};
+use rodio::Source;
+use crate::audio::MixBus;
 use crate::state::State;
 use crate::music_theory::note::Note;
 use crate::state::utils::{get_key_mappings, handle_musical_note};
-use crate::waveforms::adsr_envelope::ADSREnvelope;
-use crate::waveforms::sine_wave::SineWave;
-use crate::waveforms::square_wave::SquareWave;
-use crate::waveforms::triangle_wave::TriangleWave;
 use crate::waveforms::{Waveform, AMPLITUDE};
-use crate::waveforms::sawtooth_wave::SawtoothWave;
 use super::super::InputCommand;
 
 /// Command for handling musical note keyboard input
@@ -23,19 +19,20 @@ impl KeyboardInputCommand {
 }
 
 impl InputCommand for KeyboardInputCommand {
-    fn execute(&self, state: &mut State, window: &mut Window, sink: &mut Sink) {
+    fn execute(&self, state: &mut State, window: &mut Window, bus: &MixBus) {
         // Key press is already checked by the handler, so we can directly execute
         
         // Find the note associated with this key
         let key_mappings = get_key_mappings();
         if let Some((_, note, _, _)) = key_mappings.iter().find(|(k, _, _, _)| *k == self.key) {
-            handle_musical_note(state, sink, *note);
+            let velocity = state.keyboard_velocity;
+            handle_musical_note(state, bus, *note, velocity);
             state.pressed_key = Some((self.key, *note));
             
             // Handle recording if active - record to current track
             if state.recording_state == crate::state::RecordingState::Recording {
                 // Finish previous note if there was one
-                if let Some((start_time, prev_note, prev_octave)) = state.current_note_start.take() {
+                if let Some((start_time, prev_note, prev_octave, prev_velocity)) = state.current_note_start.take() {
                     let duration = start_time.elapsed().as_secs_f32();
                     let timestamp = state.recording_start_time
                         .map(|start| start.elapsed().as_secs_f32() - duration)
@@ -47,12 +44,13 @@ impl InputCommand for KeyboardInputCommand {
                         octave: prev_octave,
                         timestamp,
                         duration,
+                        velocity: prev_velocity,
                     });
                 }
 
                 // Start recording new note using current track's octave
                 let current_track_octave = state.tracks[state.current_track_id].octave;
-                state.current_note_start = Some((std::time::Instant::now(), *note, current_track_octave));
+                state.current_note_start = Some((std::time::Instant::now(), *note, current_track_octave, velocity));
             }
         }
     }
@@ -6,6 +6,9 @@ pub mod adsr_control;
 pub mod recording_control;
 pub mod effects_toggle;
 pub mod track_control;
+pub mod filter_control;
+pub mod tempo_adjust;
+pub mod velocity_level;
 
 pub use keyboard_input::KeyboardInputCommand;
 pub use mouse_input::MouseInputCommand;
@@ -14,4 +17,7 @@ pub use octave_adjust::OctaveAdjustCommand;
 pub use adsr_control::ADSRControlCommand;
 pub use recording_control::RecordingControlCommand;
 pub use effects_toggle::EffectsToggleCommand;
-pub use track_control::{TrackControlCommand, TrackAction};
\ No newline at end of file
+pub use track_control::{TrackControlCommand, TrackAction};
+pub use filter_control::FilterControlCommand;
+pub use tempo_adjust::TempoAdjustCommand;
+pub use velocity_level::{VelocityLevelCommand, HARD_VELOCITY, MEDIUM_VELOCITY, SOFT_VELOCITY};
\ No newline at end of file
@@ -1,16 +1,18 @@
 use minifb::{Key, MouseButton, MouseMode, Window};
-use rodio::Sink;
+use crate::audio::MixBus;
 use crate::music_theory::note::Note;
-use crate::state::State;
+use crate::music_theory::MAX_VELOCITY;
+use crate::state::{EffectTarget, State};
 use crate::state::utils::{get_key_mappings, handle_musical_note};
-use crate::effects::AudioEffect;
+use crate::effects::{ParameterUnit, MAX_EFFECT_SLOTS};
+use crate::graphics::constants::{EFFECTS_PANEL_X, EFFECTS_PANEL_Y, EFFECT_BUTTON_HEIGHT, EFFECT_BUTTON_SPACING, EFFECT_BUTTON_WIDTH, EFFECT_EDITOR_COLUMNS, EFFECT_EDITOR_X, EFFECT_EDITOR_Y, EFFECT_EDIT_X, EFFECT_KNOB_CELL_HEIGHT, EFFECT_KNOB_CELL_WIDTH, EFFECT_KNOB_DRAG_PIXELS, ENVELOPE_GRAPH_WIDTH, ENVELOPE_GRAPH_X, ENVELOPE_GRAPH_Y, FILTER_PANEL_X, FILTER_PANEL_Y, LFO_PANEL_X, LFO_PANEL_Y, LFO_ROW_HEIGHT, MOD_MATRIX_X, MOD_MATRIX_Y, MOD_SLOT_WIDTH, LIMITER_BUTTON_WIDTH, LIMITER_BUTTON_X, NEW_EFFECT_X, SIDECHAIN_BUTTON_WIDTH, SIDECHAIN_BUTTON_X, VELOCITY_PANEL_X, VELOCITY_PANEL_Y};
 use super::super::InputCommand;
 
 /// Command for handling all mouse interactions
 pub struct MouseInputCommand;
 
 impl InputCommand for MouseInputCommand {
-    fn execute(&self, state: &mut State, window: &mut Window, sink: &mut Sink) {
+    fn execute(&self, state: &mut State, window: &mut Window, bus: &MixBus) {
         // Update mouse position
         if let Some((x, y)) = window.get_mouse_pos(MouseMode::Clamp) {
             state.mouse.x = x;
@@ -46,45 +48,77 @@ impl InputCommand for MouseInputCommand {
         }
 
         // Handle ADSR fader interactions
-        handle_adsr_fader_mouse(state, sink);
+        handle_adsr_fader_mouse(state, bus);
+
+        // Handle envelope delay and hold fader interactions
+        handle_delay_hold_fader_mouse(state);
         
         // Handle tangent (sharp) key interactions FIRST (they have priority over regular keys)
-        if handle_tangent_mouse(state, sink) {
+        if handle_tangent_mouse(state, bus) {
             return; // Exit if a tangent was clicked
         }
         
         // Handle regular keyboard key interactions
-        handle_keyboard_mouse(state, sink);
+        handle_keyboard_mouse(state, bus);
         
         // Handle octave fader interactions
         handle_octave_fader_mouse(state);
         
-        // Handle waveform display interactions
-        handle_waveform_display_mouse(state);
+        // Handle effect editor knobs, shown over the waveform display while a slot is selected
+        if state.selected_effect.is_some() {
+            handle_effect_editor_mouse(state);
+        } else {
+            handle_waveform_display_mouse(state);
+        }
         
         // Handle control button interactions - DISABLED: now using per-track transport
-        // handle_control_buttons_mouse(state, sink);
+        // handle_control_buttons_mouse(state, bus);
         
         // Handle effects button interactions
-        handle_effects_buttons_mouse(state, sink);
+        handle_effects_buttons_mouse(state, bus);
+        
+        // Handle filter bulb, knob and mode button interactions
+        handle_filter_panel_mouse(state);
+
+        // Handle filter envelope and key tracking fader interactions
+        handle_filter_envelope_fader_mouse(state);
+
+        // Handle LFO button and rate/depth bar interactions
+        handle_lfo_panel_mouse(state);
+
+        // Handle glide bar interactions
+        handle_glide_mouse(state);
+
+        // Handle velocity sensitivity bar interactions
+        handle_velocity_panel_mouse(state);
+
+        // Handle mod matrix button and amount bar interactions
+        handle_mod_matrix_mouse(state);
+
+        // Handle envelope curvature bar interactions
+        handle_envelope_curve_mouse(state);
+
+        // Handle retrigger/legato and envelope loop button interactions
+        handle_trigger_mode_mouse(state);
+        handle_envelope_loop_mouse(state);
         
         // Handle track selection clicks
-        handle_track_selection_mouse(state, sink);
+        handle_track_selection_mouse(state, bus);
     }
 }
 
 /// Handle mouse interactions with ADSR faders
-pub fn handle_adsr_fader_mouse(state: &mut State, sink: &mut Sink) {
+pub fn handle_adsr_fader_mouse(state: &mut State, bus: &MixBus) {
     // ADSR fader positions (matching the draw_adsr_faders function)
     let display_x = 164;
     let display_width = 164;
     let display_y = 4 * 51 + 17;
-    let base_x = display_x + display_width + 104;
-    let base_y = display_y;
-
-    let fader_width = 25;
+    let fader_width = 18;
     let fader_height = 50;
-    let fader_spacing = 30;
+    let fader_spacing = 23;
+
+    let base_x = display_x + display_width + 104 + 2 * fader_spacing;
+    let base_y = display_y;
 
     let adsr_params = ["attack", "decay", "sustain", "release"];
 
@@ -104,22 +138,10 @@ pub fn handle_adsr_fader_mouse(state: &mut State, sink: &mut Sink) {
 
                 // Update the appropriate ADSR parameter on current track
                 match *param {
-                    "attack" => {
-                        state.tracks[state.current_track_id].attack = new_value;
-                        state.attack = new_value; // Sync legacy state
-                    },
-                    "decay" => {
-                        state.tracks[state.current_track_id].decay = new_value;
-                        state.decay = new_value; // Sync legacy state
-                    },
-                    "sustain" => {
-                        state.tracks[state.current_track_id].sustain = new_value;
-                        state.sustain = new_value; // Sync legacy state
-                    },
-                    "release" => {
-                        state.tracks[state.current_track_id].release = new_value;
-                        state.release = new_value; // Sync legacy state
-                    },
+                    "attack" => state.tracks[state.current_track_id].attack = new_value,
+                    "decay" => state.tracks[state.current_track_id].decay = new_value,
+                    "sustain" => state.tracks[state.current_track_id].sustain = new_value,
+                    "release" => state.tracks[state.current_track_id].release = new_value,
                     _ => {}
                 }
             }
@@ -129,7 +151,7 @@ pub fn handle_adsr_fader_mouse(state: &mut State, sink: &mut Sink) {
 
 /// Handle mouse interactions with tangent (sharp) keys
 /// Returns true if a tangent was clicked, false otherwise
-pub fn handle_tangent_mouse(state: &mut State, sink: &mut Sink) -> bool {
+pub fn handle_tangent_mouse(state: &mut State, bus: &MixBus) -> bool {
     let key_width = 64; // sprites.keys[KEY_IDLE].width as i32
     let key_height = 144; // sprites.keys[KEY_IDLE].height
     let tangent_width = 30; // sprites.tangents[TANGENT_IDLE].width as i32
@@ -160,14 +182,15 @@ pub fn handle_tangent_mouse(state: &mut State, sink: &mut Sink) -> bool {
             state.mouse.y <= (key_y + tangent_height as usize) as f32 {
 
             if state.mouse.left_clicked {
-                // Trigger the note
-                handle_musical_note(state, sink, note);
+                // Trigger the note, harder the closer to the front of the key it is struck
+                let velocity = velocity_from_key_y(state.mouse.y, key_y, tangent_height as usize);
+                handle_musical_note(state, bus, note, velocity);
                 state.pressed_key = Some((key, note));
 
                 // Record note if recording - record to current track
                 if state.recording_state == crate::state::RecordingState::Recording {
                     // Finish previous note if there was one
-                    if let Some((start_time, prev_note, prev_octave)) = state.current_note_start.take() {
+                    if let Some((start_time, prev_note, prev_octave, prev_velocity)) = state.current_note_start.take() {
                         let duration = start_time.elapsed().as_secs_f32();
                         let timestamp = state.recording_start_time
                             .map(|start| start.elapsed().as_secs_f32() - duration)
@@ -179,12 +202,13 @@ pub fn handle_tangent_mouse(state: &mut State, sink: &mut Sink) -> bool {
                             octave: prev_octave,
                             timestamp,
                             duration,
+                            velocity: prev_velocity,
                         });
                     }
 
                     // Start recording new note using current track's octave
                     let current_track_octave = state.tracks[state.current_track_id].octave;
-                    state.current_note_start = Some((std::time::Instant::now(), note, current_track_octave));
+                    state.current_note_start = Some((std::time::Instant::now(), note, current_track_octave, velocity));
                 }
                 return true; // Return true to indicate a tangent was clicked
             }
@@ -193,8 +217,15 @@ pub fn handle_tangent_mouse(state: &mut State, sink: &mut Sink) -> bool {
     false // Return false if no tangent was clicked
 }
 
+/// Velocity of a note clicked on the keyboard sprite: soft at the back (top) of a key,
+/// full velocity at the front (bottom)
+fn velocity_from_key_y(mouse_y: f32, key_y: usize, key_height: usize) -> u8 {
+    let depth = ((mouse_y - key_y as f32) / key_height as f32).clamp(0.0, 1.0);
+    (1.0 + depth * (MAX_VELOCITY - 1) as f32).round() as u8
+}
+
 /// Handle mouse interactions with keyboard keys
-pub fn handle_keyboard_mouse(state: &mut State, sink: &mut Sink) {
+pub fn handle_keyboard_mouse(state: &mut State, bus: &MixBus) {
     // Virtual keyboard positioning (matching draw_idle_key_sprites exactly)
     // Keys are drawn from i=1 to i=7, at positions i * key_width
     let key_width = 64; // sprites.keys[KEY_IDLE].width
@@ -210,15 +241,15 @@ pub fn handle_keyboard_mouse(state: &mut State, sink: &mut Sink) {
             state.mouse.y >= key_y as f32 && state.mouse.y <= (key_y + key_height) as f32 {
 
             if state.mouse.left_clicked {
-                // Trigger the note
-                handle_musical_note(state, sink, note);
+                // Trigger the note, harder the closer to the front of the key it is struck
+                let velocity = velocity_from_key_y(state.mouse.y, key_y, key_height);
+                handle_musical_note(state, bus, note, velocity);
                 state.pressed_key = Some((key, note));
 
-
                 // Record note if recording - record to current track
                 if state.recording_state == crate::state::RecordingState::Recording {
                     // Finish previous note if there was one
-                    if let Some((start_time, prev_note, prev_octave)) = state.current_note_start.take() {
+                    if let Some((start_time, prev_note, prev_octave, prev_velocity)) = state.current_note_start.take() {
                         let duration = start_time.elapsed().as_secs_f32();
                         let timestamp = state.recording_start_time
                             .map(|start| start.elapsed().as_secs_f32() - duration)
@@ -230,12 +261,13 @@ pub fn handle_keyboard_mouse(state: &mut State, sink: &mut Sink) {
                             octave: prev_octave,
                             timestamp,
                             duration,
+                            velocity: prev_velocity,
                         });
                     }
 
                     // Start recording new note using current track's octave
                     let current_track_octave = state.tracks[state.current_track_id].octave;
-                    state.current_note_start = Some((std::time::Instant::now(), note, current_track_octave));
+                    state.current_note_start = Some((std::time::Instant::now(), note, current_track_octave, velocity));
                 }
                 return; // Exit after handling one key to avoid multiple triggers
             }
@@ -297,7 +329,7 @@ pub fn handle_waveform_display_mouse(state: &mut State) {
 }
 
 /// Handle mouse interactions with control buttons
-pub fn handle_control_buttons_mouse(state: &mut State, sink: &mut Sink) {
+pub fn handle_control_buttons_mouse(state: &mut State, bus: &MixBus) {
     // Control button positions - aligned with note display terminal (top left area)
     let button_width = 60;
     let button_height = 30;
@@ -341,7 +373,8 @@ pub fn handle_control_buttons_mouse(state: &mut State, sink: &mut Sink) {
 
         if state.mouse.left_clicked {
             // Stop all audio immediately
-            sink.stop();
+            bus.stop_all();
+            state.active_voice = None;
 
             // Stop recording and playback
             state.stop_recording();
@@ -359,54 +392,63 @@ pub fn handle_control_buttons_mouse(state: &mut State, sink: &mut Sink) {
 }
 
 /// Handle mouse interactions with effects buttons
-pub fn handle_effects_buttons_mouse(state: &mut State, sink: &mut Sink) {
-    // Match the positioning from draw_effects_buttons
-    let display_end_x = 164 + 164; // 328
-    let adsr_start_x = 164 + 164 + 104; // 432
-    let available_width = adsr_start_x - display_end_x; // 104px
+pub fn handle_effects_buttons_mouse(state: &mut State, bus: &MixBus) {
+    if !state.mouse.left_clicked {
+        return;
+    }
     
-    let button_width = 30;
-    let button_height = 20;
-    let button_spacing = (available_width - (3 * button_width)) / 4;
-    let base_x = display_end_x + button_spacing;
-    let base_y = 4 * 51 + 17 + 15;
+    // Match the positioning from draw_effects_buttons: master row first, current track row below
+    let rows = [EFFECTS_PANEL_Y, EFFECTS_PANEL_Y + EFFECT_BUTTON_HEIGHT + 4];
     
-    // Check each effect button
-    for i in 0..3 {
-        let button_x = base_x + i * (button_width + button_spacing);
-        
-        // Check if mouse is over this button
-        if state.mouse.x >= button_x as f32 && state.mouse.x <= (button_x + button_width) as f32 &&
-           state.mouse.y >= base_y as f32 && state.mouse.y <= (base_y + button_height) as f32 {
+    // Sidechain key button at the end of the track row
+    if state.mouse.x >= SIDECHAIN_BUTTON_X as f32 && state.mouse.x <= (SIDECHAIN_BUTTON_X + SIDECHAIN_BUTTON_WIDTH) as f32 &&
+       state.mouse.y >= rows[1] as f32 && state.mouse.y <= (rows[1] + EFFECT_BUTTON_HEIGHT) as f32 {
+        state.cycle_current_track_sidechain_key();
+        return;
+    }
+    
+    // Master limiter button at the end of the master row opens the limiter in the editor
+    if state.mouse.x >= LIMITER_BUTTON_X as f32 && state.mouse.x <= (LIMITER_BUTTON_X + LIMITER_BUTTON_WIDTH) as f32 &&
+       state.mouse.y >= rows[0] as f32 && state.mouse.y <= (rows[0] + EFFECT_BUTTON_HEIGHT) as f32 {
+        state.select_effect_slot(EffectTarget::Limiter, 0);
+        return;
+    }
+    let step = EFFECT_BUTTON_WIDTH + EFFECT_BUTTON_SPACING;
+    let over_button = |state: &State, x: usize, y: usize| {
+        state.mouse.x >= x as f32 && state.mouse.x <= (x + EFFECT_BUTTON_WIDTH) as f32 &&
+        state.mouse.y >= y as f32 && state.mouse.y <= (y + EFFECT_BUTTON_HEIGHT) as f32
+    };
+
+    // Move, bypass and remove buttons for the selected slot on the master row
+    for (i, action) in ["<", ">", "BYP", "DEL"].iter().enumerate() {
+        if over_button(state, EFFECT_EDIT_X + i * step, rows[0]) {
+            match *action {
+                "<" => state.move_selected_effect_slot(-1),
+                ">" => state.move_selected_effect_slot(1),
+                "BYP" => state.toggle_selected_effect_bypass(),
+                _ => state.remove_selected_effect_slot(),
+            }
+            return;
+        }
+    }
+
+    // Effect type picker on the track row
+    if over_button(state, NEW_EFFECT_X, rows[1]) {
+        state.cycle_new_effect_kind();
+        return;
+    }
+
+    for (target, row_y) in [(EffectTarget::Master, rows[0]), (EffectTarget::Track, rows[1])] {
+        let slot_count = state.effect_chain(target).len();
+        for i in 0..=slot_count.min(MAX_EFFECT_SLOTS - 1) {
+            let button_x = EFFECTS_PANEL_X + 16 + i * step;
             
-            if state.mouse.left_clicked {
-                // Toggle the appropriate effect on current track
-                match i {
-                    0 => {
-                        // Delay button
-                        state.toggle_current_track_delay();
-                        let current_track_id = state.current_track_id;
-                        if !state.tracks[current_track_id].delay_enabled {
-                            state.tracks[current_track_id].delay_effect.reset();
-                        }
-                    },
-                    1 => {
-                        // Reverb button
-                        state.toggle_current_track_reverb();
-                        let current_track_id = state.current_track_id;
-                        if !state.tracks[current_track_id].reverb_enabled {
-                            state.tracks[current_track_id].reverb_effect.reset();
-                        }
-                    },
-                    2 => {
-                        // Flanger button
-                        state.toggle_current_track_flanger();
-                        let current_track_id = state.current_track_id;
-                        if !state.tracks[current_track_id].flanger_enabled {
-                            state.tracks[current_track_id].flanger_effect.reset();
-                        }
-                    },
-                    _ => {}
+            // Check if mouse is over this slot, or over the add button after the last one
+            if over_button(state, button_x, row_y) {
+                if i < slot_count {
+                    state.select_effect_slot(target, i);
+                } else {
+                    state.add_effect_slot(target, state.new_effect_kind);
                 }
                 return; // Exit after handling one button
             }
@@ -414,8 +456,360 @@ pub fn handle_effects_buttons_mouse(state: &mut State, sink: &mut Sink) {
     }
 }
 
+/// Handle mouse interactions with the effect editor: dragging a knob up or down turns it,
+/// clicking a choice steps to its next option
+pub fn handle_effect_editor_mouse(state: &mut State) {
+    if !state.mouse.left_pressed {
+        state.effect_knob_drag = None;
+        return;
+    }
+    let parameters = state.selected_effect_parameters();
+
+    if state.mouse.left_clicked {
+        state.effect_knob_drag = None;
+        for (i, parameter) in parameters.iter().enumerate() {
+            // Cell position (matching draw_effect_editor)
+            let x = EFFECT_EDITOR_X + (i % EFFECT_EDITOR_COLUMNS) * EFFECT_KNOB_CELL_WIDTH;
+            let y = EFFECT_EDITOR_Y + (i / EFFECT_EDITOR_COLUMNS) * EFFECT_KNOB_CELL_HEIGHT;
+            if state.mouse.x < x as f32 || state.mouse.x >= (x + EFFECT_KNOB_CELL_WIDTH) as f32 ||
+               state.mouse.y < y as f32 || state.mouse.y >= (y + EFFECT_KNOB_CELL_HEIGHT) as f32 {
+                continue;
+            }
+
+            if let ParameterUnit::Choice(_) = parameter.unit {
+                let next = if parameter.value >= parameter.max { parameter.min } else { parameter.value + 1.0 };
+                state.set_selected_effect_parameter(i, next);
+            } else {
+                state.effect_knob_drag = Some((i, parameter.normalized()));
+            }
+            return;
+        }
+
+        // Cell after the knobs of a convolution reverb picks its impulse response
+        let i = parameters.len();
+        let x = EFFECT_EDITOR_X + (i % EFFECT_EDITOR_COLUMNS) * EFFECT_KNOB_CELL_WIDTH;
+        let y = EFFECT_EDITOR_Y + (i / EFFECT_EDITOR_COLUMNS) * EFFECT_KNOB_CELL_HEIGHT;
+        if state.mouse.x >= x as f32 && state.mouse.x < (x + EFFECT_KNOB_CELL_WIDTH) as f32 &&
+           state.mouse.y >= y as f32 && state.mouse.y < (y + EFFECT_KNOB_CELL_HEIGHT) as f32 {
+            state.cycle_impulse_response();
+        }
+        return;
+    }
+
+    // Turn the grabbed knob by the vertical distance from where the drag started
+    if let (Some((index, start_position)), Some((_, start_y))) = (state.effect_knob_drag, state.mouse.drag_start) {
+        if let Some(parameter) = parameters.get(index) {
+            let position = start_position + (start_y - state.mouse.y) / EFFECT_KNOB_DRAG_PIXELS;
+            let value = parameter.value_at(position);
+            if value != parameter.value {
+                state.set_selected_effect_parameter(index, value);
+            }
+        }
+    }
+}
+
+/// Handle mouse interactions with the filter bulb, the cutoff/resonance knobs and the mode/slope buttons
+pub fn handle_filter_panel_mouse(state: &mut State) {
+    if !state.mouse.left_clicked {
+        return;
+    }
+
+    // Bulb position (matching draw_bulb_sprite)
+    let bulb_x = FILTER_PANEL_X;
+    let bulb_y = FILTER_PANEL_Y + 18;
+    let bulb_size = 12; // sprites.bulb[0] width and height
+
+    if state.mouse.x >= bulb_x as f32 && state.mouse.x <= (bulb_x + bulb_size) as f32 &&
+       state.mouse.y >= bulb_y as f32 && state.mouse.y <= (bulb_y + bulb_size) as f32 {
+        state.toggle_current_track_lpf();
+        return;
+    }
+
+    // Knob positions (matching draw_filter_cutoff_knob_sprite and draw_filter_resonance_knob_sprite)
+    let knob_width = 64; // sprites.knob[0].width
+    let knob_height = 48; // sprites.knob[0].height
+    let cutoff_x = FILTER_PANEL_X + 14;
+    let resonance_x = cutoff_x + knob_width;
+    let knob_y = FILTER_PANEL_Y;
+
+    // Mode and slope buttons (matching draw_filter_mode_buttons)
+    let button_x = resonance_x + knob_width;
+    let button_width = 24;
+    let button_height = 16;
+    for i in 0..2 {
+        let button_y = FILTER_PANEL_Y + 6 + i * (button_height + 4);
+        if state.mouse.x >= button_x as f32 && state.mouse.x <= (button_x + button_width) as f32 &&
+           state.mouse.y >= button_y as f32 && state.mouse.y <= (button_y + button_height) as f32 {
+            if i == 0 {
+                state.cycle_current_track_filter_mode();
+            } else {
+                state.toggle_current_track_filter_slope();
+            }
+            return;
+        }
+    }
+
+    if state.mouse.y < knob_y as f32 || state.mouse.y > (knob_y + knob_height) as f32 {
+        return;
+    }
+
+    // Clicking the upper half of a knob turns it up, the lower half turns it down
+    let increase = state.mouse.y < (knob_y + knob_height / 2) as f32;
+
+    if state.mouse.x >= cutoff_x as f32 && state.mouse.x < resonance_x as f32 {
+        if increase {
+            state.increase_current_track_filter_cutoff();
+        } else {
+            state.decrease_current_track_filter_cutoff();
+        }
+    } else if state.mouse.x >= resonance_x as f32 && state.mouse.x < button_x as f32 {
+        if increase {
+            state.increase_current_track_filter_resonance();
+        } else {
+            state.decrease_current_track_filter_resonance();
+        }
+    }
+}
+
+/// Handle mouse interactions with the envelope delay and hold faders
+pub fn handle_delay_hold_fader_mouse(state: &mut State) {
+    if !state.mouse.left_clicked && !state.mouse.dragging {
+        return;
+    }
+
+    // Fader positions (matching the draw_delay_hold_faders function)
+    let base_x = 164 + 164 + 104;
+    let base_y = 4 * 51 + 17;
+
+    let fader_width = 18;
+    let fader_height = 50;
+    let fader_spacing = 23;
+
+    for stage in 0..2 {
+        let fader_x = base_x + stage * fader_spacing;
+
+        if state.mouse.x >= fader_x as f32 && state.mouse.x <= (fader_x + fader_width) as f32 &&
+            state.mouse.y >= base_y as f32 && state.mouse.y <= (base_y + fader_height) as f32 {
+            let relative_y = state.mouse.y - base_y as f32;
+            let normalized_value = 1.0 - (relative_y / fader_height as f32).clamp(0.0, 1.0);
+            let new_value = (normalized_value * 99.0) as u8;
+
+            let track = &mut state.tracks[state.current_track_id];
+            if stage == 0 {
+                track.delay = new_value;
+            } else {
+                track.hold = new_value;
+            }
+        }
+    }
+}
+
+/// Handle mouse interactions with the filter envelope, envelope amount and key tracking faders
+pub fn handle_filter_envelope_fader_mouse(state: &mut State) {
+    // Fader positions (matching the draw_filter_envelope_faders function)
+    let base_x = FILTER_PANEL_X + 14 + 64 + 64 + 30;
+    let base_y = FILTER_PANEL_Y + 2;
+
+    let fader_width = 14;
+    let fader_height = 40;
+    let fader_spacing = 18;
+
+    let params = ["attack", "decay", "sustain", "release", "amount", "key_tracking"];
+
+    for (i, param) in params.iter().enumerate() {
+        let fader_x = base_x + i * fader_spacing;
+        let fader_y = base_y;
+
+        // Check if mouse is over this fader while clicking or dragging
+        if state.mouse.x >= fader_x as f32 && state.mouse.x <= (fader_x + fader_width) as f32 &&
+            state.mouse.y >= fader_y as f32 && state.mouse.y <= (fader_y + fader_height) as f32 &&
+            (state.mouse.left_clicked || state.mouse.dragging) {
+            // Calculate new value based on mouse Y position
+            let relative_y = state.mouse.y - fader_y as f32;
+            let normalized_value = 1.0 - (relative_y / fader_height as f32).clamp(0.0, 1.0);
+            let new_value = (normalized_value * 99.0) as u8;
+
+            let track = &mut state.tracks[state.current_track_id];
+            match *param {
+                "attack" => track.filter_env_attack = new_value,
+                "decay" => track.filter_env_decay = new_value,
+                "sustain" => track.filter_env_sustain = new_value,
+                "release" => track.filter_env_release = new_value,
+                // Bipolar: top opens the filter, bottom closes it, middle is off
+                "amount" => track.filter_env_amount = normalized_value * 2.0 - 1.0,
+                "key_tracking" => track.filter_key_tracking = normalized_value,
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Handle mouse interactions with the LFO buttons and the rate/depth bars
+pub fn handle_lfo_panel_mouse(state: &mut State) {
+    if !state.mouse.left_clicked && !state.mouse.dragging {
+        return;
+    }
+
+    // Button and bar positions (matching draw_lfo_panel)
+    let button_width = 24;
+    let button_height = 16;
+    let bar_x = LFO_PANEL_X + 142;
+    let bar_width = 80;
+    let bar_height = 8;
+
+    for lfo_index in 0..state.current_track().lfos.len() {
+        let y = LFO_PANEL_Y + lfo_index * LFO_ROW_HEIGHT;
+
+        // Buttons only react to clicks, not to drags passing over them
+        if state.mouse.left_clicked {
+            for button in 0..4 {
+                let x = LFO_PANEL_X + 20 + button * (button_width + 4);
+                if state.mouse.x >= x as f32 && state.mouse.x <= (x + button_width) as f32 &&
+                   state.mouse.y >= y as f32 && state.mouse.y <= (y + button_height) as f32 {
+                    match button {
+                        0 => state.toggle_current_track_lfo(lfo_index),
+                        1 => state.cycle_current_track_lfo_shape(lfo_index),
+                        2 => state.cycle_current_track_lfo_destination(lfo_index),
+                        _ => state.toggle_current_track_lfo_sync(lfo_index),
+                    }
+                    return;
+                }
+            }
+        }
+
+        if state.mouse.x < bar_x as f32 || state.mouse.x > (bar_x + bar_width) as f32 {
+            continue;
+        }
+
+        let value = (state.mouse.x - bar_x as f32) / bar_width as f32;
+        let rate_y = y + 1;
+        let depth_y = y + 11;
+
+        if state.mouse.y >= rate_y as f32 && state.mouse.y <= (rate_y + bar_height) as f32 {
+            state.set_current_track_lfo_rate(lfo_index, value);
+        } else if state.mouse.y >= depth_y as f32 && state.mouse.y <= (depth_y + bar_height) as f32 {
+            state.set_current_track_lfo_depth(lfo_index, value);
+        }
+    }
+}
+
+/// Handle mouse interactions with the glide bar on the tempo row
+pub fn handle_glide_mouse(state: &mut State) {
+    if !state.mouse.left_clicked && !state.mouse.dragging {
+        return;
+    }
+
+    // Bar position (matching draw_glide_bar)
+    let bar_x = LFO_PANEL_X + 142;
+    let bar_width = 80;
+    let bar_height = 8;
+    let y = LFO_PANEL_Y + state.current_track().lfos.len() * LFO_ROW_HEIGHT + 1;
+
+    if state.mouse.x >= bar_x as f32 && state.mouse.x <= (bar_x + bar_width) as f32 &&
+        state.mouse.y >= y as f32 && state.mouse.y <= (y + bar_height) as f32 {
+        state.set_current_track_glide((state.mouse.x - bar_x as f32) / bar_width as f32);
+    }
+}
+
+/// Handle mouse interactions with the velocity sensitivity bars
+pub fn handle_velocity_panel_mouse(state: &mut State) {
+    if !state.mouse.left_clicked && !state.mouse.dragging {
+        return;
+    }
+
+    // Bar positions (matching draw_velocity_panel)
+    let bar_x = VELOCITY_PANEL_X + 24;
+    let bar_width = 80;
+    let bar_height = 8;
+
+    if state.mouse.x < bar_x as f32 || state.mouse.x > (bar_x + bar_width) as f32 {
+        return;
+    }
+
+    let amount = (state.mouse.x - bar_x as f32) / bar_width as f32;
+    for row in 0..2 {
+        let y = VELOCITY_PANEL_Y + (row + 1) * 11;
+        if state.mouse.y >= y as f32 && state.mouse.y <= (y + bar_height) as f32 {
+            if row == 0 {
+                state.set_current_track_velocity_to_amp(amount);
+            } else {
+                state.set_current_track_velocity_to_filter(amount);
+            }
+        }
+    }
+}
+
+/// Handle mouse interactions with the attack, decay and release curvature bars
+pub fn handle_envelope_curve_mouse(state: &mut State) {
+    if !state.mouse.left_clicked && !state.mouse.dragging {
+        return;
+    }
+
+    // Bar positions (matching draw_envelope_graph)
+    let bar_x = ENVELOPE_GRAPH_X + ENVELOPE_GRAPH_WIDTH + 16;
+    let bar_width = 40;
+    let bar_height = 10;
+
+    if state.mouse.x < bar_x as f32 || state.mouse.x > (bar_x + bar_width) as f32 {
+        return;
+    }
+
+    for segment in 0..3 {
+        let y = ENVELOPE_GRAPH_Y + segment * (bar_height + 3);
+        if state.mouse.y >= y as f32 && state.mouse.y <= (y + bar_height) as f32 {
+            // Bipolar: left half is logarithmic, centre is linear, right half is exponential
+            let normalized = (state.mouse.x - bar_x as f32) / bar_width as f32;
+            state.set_current_track_envelope_curve(segment, normalized * 2.0 - 1.0);
+            return;
+        }
+    }
+}
+
+/// Handle mouse interactions with the mod matrix source/destination buttons and amount bars
+pub fn handle_mod_matrix_mouse(state: &mut State) {
+    if !state.mouse.left_clicked && !state.mouse.dragging {
+        return;
+    }
+
+    // Button and bar positions (matching draw_mod_matrix)
+    let button_width = 28;
+    let button_height = 16;
+    let bar_width = 58;
+    let bar_height = 8;
+
+    for slot_index in 0..state.current_track().mod_matrix.len() {
+        let x = MOD_MATRIX_X + slot_index * MOD_SLOT_WIDTH;
+
+        // Buttons only react to clicks, not to drags passing over them
+        if state.mouse.left_clicked {
+            for button in 0..2 {
+                let button_x = x + 10 + button * (button_width + 2);
+                if state.mouse.x >= button_x as f32 && state.mouse.x <= (button_x + button_width) as f32 &&
+                   state.mouse.y >= MOD_MATRIX_Y as f32 && state.mouse.y <= (MOD_MATRIX_Y + button_height) as f32 {
+                    if button == 0 {
+                        state.cycle_current_track_mod_source(slot_index);
+                    } else {
+                        state.cycle_current_track_mod_destination(slot_index);
+                    }
+                    return;
+                }
+            }
+        }
+
+        let bar_x = x + 10;
+        let bar_y = MOD_MATRIX_Y + button_height + 4;
+        if state.mouse.x >= bar_x as f32 && state.mouse.x <= (bar_x + bar_width) as f32 &&
+           state.mouse.y >= bar_y as f32 && state.mouse.y <= (bar_y + bar_height) as f32 {
+            // Bipolar: left half is negative, centre is zero, right half is positive
+            let normalized = (state.mouse.x - bar_x as f32) / bar_width as f32;
+            state.set_current_track_mod_amount(slot_index, normalized * 2.0 - 1.0);
+            return;
+        }
+    }
+}
+
 /// Handle mouse interactions with track display, transport controls, and mute/solo buttons
-pub fn handle_track_selection_mouse(state: &mut State, sink: &mut Sink) {
+pub fn handle_track_selection_mouse(state: &mut State, bus: &MixBus) {
     // Track display positions (matching draw_track_info)
     let base_x = 10;
     let base_y = 10;
@@ -490,7 +884,8 @@ pub fn handle_track_selection_mouse(state: &mut State, sink: &mut Sink) {
             
             if state.mouse.left_clicked {
                 // Stop everything
-                sink.stop(); // Stop all audio immediately
+                bus.stop_all(); // Stop all audio immediately
+                state.active_voice = None;
                 state.stop_recording();
                 state.stop_playback();
                 state.stop_all_track_playback(); // Stop individual track playback
@@ -520,17 +915,45 @@ pub fn handle_track_selection_mouse(state: &mut State, sink: &mut Sink) {
                 let track = &state.tracks[current_track_id];
                 state.octave = track.octave;
                 state.waveform = track.waveform.clone();
-                state.attack = track.attack;
-                state.decay = track.decay;
-                state.sustain = track.sustain;
-                state.release = track.release;
-                state.delay_enabled = track.delay_enabled;
-                state.reverb_enabled = track.reverb_enabled;
-                state.flanger_enabled = track.flanger_enabled;
                 
                 println!("Switched to track {}: {}", i + 1, track.name);
                 return; // Exit after handling one track
             }
         }
     }
-}
\ No newline at end of file
+}
+/// Handle mouse interactions with the retrigger/legato button
+pub fn handle_trigger_mode_mouse(state: &mut State) {
+    if !state.mouse.left_clicked {
+        return;
+    }
+
+    // Button position (matching draw_trigger_mode_button)
+    let button_width = 48;
+    let button_height = 16;
+    let x = ENVELOPE_GRAPH_X - button_width - 8;
+    let y = ENVELOPE_GRAPH_Y + 2;
+
+    if state.mouse.x >= x as f32 && state.mouse.x <= (x + button_width) as f32 &&
+        state.mouse.y >= y as f32 && state.mouse.y <= (y + button_height) as f32 {
+        state.toggle_current_track_trigger_mode();
+    }
+}
+
+/// Handle mouse interactions with the envelope loop button below the retrigger/legato button
+pub fn handle_envelope_loop_mouse(state: &mut State) {
+    if !state.mouse.left_clicked {
+        return;
+    }
+
+    // Button position (matching draw_envelope_loop_button)
+    let button_width = 48;
+    let button_height = 16;
+    let x = ENVELOPE_GRAPH_X - button_width - 8;
+    let y = ENVELOPE_GRAPH_Y + 2 + button_height + 2;
+
+    if state.mouse.x >= x as f32 && state.mouse.x <= (x + button_width) as f32 &&
+        state.mouse.y >= y as f32 && state.mouse.y <= (y + button_height) as f32 {
+        state.toggle_current_track_envelope_loop();
+    }
+}
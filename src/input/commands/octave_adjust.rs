@@ -1,5 +1,5 @@
 use minifb::{Key, KeyRepeat, Window};
-use rodio::Sink;
+use crate::audio::MixBus;
 use crate::state::State;
 use crate::music_theory::{OCTAVE_LOWER_BOUND, OCTAVE_UPPER_BOUND};
 use super::super::InputCommand;
@@ -16,7 +16,7 @@ impl OctaveAdjustCommand {
 }
 
 impl InputCommand for OctaveAdjustCommand {
-    fn execute(&self, state: &mut State, window: &mut Window, sink: &mut Sink) {
+    fn execute(&self, state: &mut State, window: &mut Window, bus: &MixBus) {
         let key = if self.increase { Key::F2 } else { Key::F1 };
         
         if window.is_key_pressed(key, KeyRepeat::No) {
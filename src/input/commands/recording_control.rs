@@ -1,6 +1,7 @@
 use minifb::{Key, Window};
-use rodio::Sink;
+use crate::audio::MixBus;
 use crate::state::State;
+use crate::waveforms::SAMPLE_RATE;
 use crate::state::utils::{handle_musical_note};
 use super::super::InputCommand;
 
@@ -8,37 +9,35 @@ use super::super::InputCommand;
 pub struct RecordingControlCommand;
 
 impl InputCommand for RecordingControlCommand {
-    fn execute(&self, state: &mut State, window: &mut Window, sink: &mut Sink) {
+    fn execute(&self, state: &mut State, window: &mut Window, bus: &MixBus) {
         // Handle playback logic
-        handle_playback(state, sink);
+        handle_playback(state, bus);
         
         // Handle key release timing and fade effects
         let mut key_pressed = false;
         
-        // Check if any musical key is currently pressed
+        // Check if any musical key is currently held, on the computer keyboard or with the mouse
         if let Some(_) = state.pressed_key {
+            key_pressed = state.mouse.left_pressed;
             for (key, _, _, _) in crate::state::utils::get_key_mappings() {
-                if window.is_key_pressed(key, minifb::KeyRepeat::No) {
+                if window.is_key_down(key) {
                     key_pressed = true;
                     break;
                 }
             }
         }
         
-        // If no musical key is pressed, handle key release based on ADSR settings
+        // If no musical key is held, release the live voice so its envelope fades from wherever it is
         if !key_pressed && state.pressed_key.is_some() && state.key_release_time.is_none() {
-            // For very quick release settings (0-10), stop immediately
-            if state.release <= 10 {
-                sink.stop(); // Immediate stop for instant release
+            if let Some((_, voice)) = &state.active_voice {
+                voice.release();
             }
-            // For other settings, let ADSR envelope handle the release naturally
-            // The ADSR envelope will auto-release after max_sustain_samples 
             state.key_release_time = Some(std::time::Instant::now());
         }
         
         // Clear visual display quickly after audio has stopped
         if let Some(release_time) = state.key_release_time {
-            let visual_clear_time = (state.release_normalized() * 2.0).max(0.1); // Minimum 100ms for visual feedback
+            let visual_clear_time = state.current_track().amp_envelope().release.max(0.1); // Minimum 100ms for visual feedback
             if release_time.elapsed().as_secs_f32() > visual_clear_time {
                 state.current_frequency = None;
                 state.key_release_time = None;
@@ -48,7 +47,7 @@ impl InputCommand for RecordingControlCommand {
 }
 
 /// Handle multi-track playback of recorded loops during playback mode
-pub fn handle_playback(state: &mut State, sink: &mut Sink) {
+pub fn handle_playback(state: &mut State, bus: &MixBus) {
     if state.recording_state != crate::state::RecordingState::Playing {
         return;
     }
@@ -114,8 +113,8 @@ pub fn handle_playback(state: &mut State, sink: &mut Sink) {
 
                 if should_trigger {
                     // Create mixer and play note on this specific track
-                    let mixer = crate::audio::MultiTrackMixer::new(44100);
-                    mixer.play_note_on_track(track, recorded_note.note, sink);
+                    let mixer = crate::audio::MultiTrackMixer::new(SAMPLE_RATE as u32, state.tempo_bpm);
+                    mixer.play_note_on_track(track, recorded_note.note, recorded_note.velocity, bus);
                     
                     // Set visual feedback for any playing track
                     state.pressed_key = Some((Key::Q, recorded_note.note));
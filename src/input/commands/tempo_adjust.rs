@@ -0,0 +1,29 @@
+use minifb::{Key, KeyRepeat, Window};
+use crate::audio::MixBus;
+use crate::state::State;
+use super::super::InputCommand;
+
+/// Command for adjusting the song tempo up or down
+pub struct TempoAdjustCommand {
+    increase: bool,
+}
+
+impl TempoAdjustCommand {
+    pub fn new(increase: bool) -> Self {
+        Self { increase }
+    }
+}
+
+impl InputCommand for TempoAdjustCommand {
+    fn execute(&self, state: &mut State, window: &mut Window, _bus: &MixBus) {
+        let key = if self.increase { Key::Period } else { Key::Comma };
+        
+        if window.is_key_pressed(key, KeyRepeat::No) {
+            if self.increase {
+                state.increase_tempo();
+            } else {
+                state.decrease_tempo();
+            }
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use minifb::{Key, Window};
-use rodio::Sink;
+use crate::audio::MixBus;
 use crate::state::State;
 use super::super::InputCommand;
 
@@ -26,7 +26,7 @@ impl TrackControlCommand {
 }
 
 impl InputCommand for TrackControlCommand {
-    fn execute(&self, state: &mut State, _window: &mut Window, _sink: &mut Sink) {
+    fn execute(&self, state: &mut State, _window: &mut Window, _bus: &MixBus) {
         match &self.action {
             TrackAction::SwitchToTrack(track_id) => {
                 state.switch_to_track(*track_id);
@@ -36,10 +36,6 @@ impl InputCommand for TrackControlCommand {
                 let track = &state.tracks[current_track_id];
                 // state.octave = track.octave;
                 state.waveform = track.waveform.clone();
-                state.attack = track.attack;
-                state.decay = track.decay;
-                state.sustain = track.sustain;
-                state.release = track.release;
                 
                 println!("Switched to track {}: {}", track_id, track.name);
             },
@@ -0,0 +1,31 @@
+use minifb::Window;
+use crate::audio::MixBus;
+use crate::state::State;
+use super::super::InputCommand;
+
+/// Velocity picked by the soft level key
+pub const SOFT_VELOCITY: u8 = 40;
+
+/// Velocity picked by the medium level key
+pub const MEDIUM_VELOCITY: u8 = 80;
+
+/// Velocity picked by the hard level key
+pub const HARD_VELOCITY: u8 = 127;
+
+/// Command for picking the fixed velocity of notes played on the computer keyboard
+pub struct VelocityLevelCommand {
+    velocity: u8,
+}
+
+impl VelocityLevelCommand {
+    pub fn new(velocity: u8) -> Self {
+        Self { velocity }
+    }
+}
+
+impl InputCommand for VelocityLevelCommand {
+    fn execute(&self, state: &mut State, _window: &mut Window, _bus: &MixBus) {
+        // Key press is already checked by the handler
+        state.set_keyboard_velocity(self.velocity);
+    }
+}
@@ -1,5 +1,5 @@
 use minifb::{Key, KeyRepeat, Window};
-use rodio::Sink;
+use crate::audio::MixBus;
 use crate::state::State;
 use super::super::InputCommand;
 
@@ -7,7 +7,7 @@ use super::super::InputCommand;
 pub struct WaveformToggleCommand;
 
 impl InputCommand for WaveformToggleCommand {
-    fn execute(&self, state: &mut State, window: &mut Window, sink: &mut Sink) {
+    fn execute(&self, state: &mut State, window: &mut Window, bus: &MixBus) {
         if window.is_key_pressed(Key::Tab, KeyRepeat::No) {
             state.toggle_current_track_waveform();
         }
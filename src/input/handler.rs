@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 use minifb::{Key, Window};
-use rodio::Sink;
+use crate::audio::MixBus;
 
 use crate::state::State;
 use super::{InputCommand, InputCommandRef};
@@ -62,6 +62,24 @@ impl InputHandler {
         self.register_keyboard_command(Key::F11, Arc::new(EffectsToggleCommand::new_reverb()));  // toggle reverb
         self.register_keyboard_command(Key::F12, Arc::new(EffectsToggleCommand::new_flanger())); // toggle flanger
         
+        // Filter controls
+        self.register_keyboard_command(Key::L, Arc::new(FilterControlCommand::new_toggle()));          // toggle filter
+        self.register_keyboard_command(Key::K, Arc::new(FilterControlCommand::new_cycle_mode()));      // cycle filter mode
+        self.register_keyboard_command(Key::J, Arc::new(FilterControlCommand::new_toggle_slope()));    // toggle 12/24 dB slope
+        self.register_keyboard_command(Key::Z, Arc::new(FilterControlCommand::new_cutoff(false)));     // decrease cutoff
+        self.register_keyboard_command(Key::X, Arc::new(FilterControlCommand::new_cutoff(true)));      // increase cutoff
+        self.register_keyboard_command(Key::C, Arc::new(FilterControlCommand::new_resonance(false)));  // decrease resonance
+        self.register_keyboard_command(Key::V, Arc::new(FilterControlCommand::new_resonance(true)));   // increase resonance
+
+        // Tempo controls
+        self.register_keyboard_command(Key::Comma, Arc::new(TempoAdjustCommand::new(false)));  // decrease tempo
+        self.register_keyboard_command(Key::Period, Arc::new(TempoAdjustCommand::new(true)));  // increase tempo
+
+        // Computer keyboard velocity levels
+        self.register_keyboard_command(Key::I, Arc::new(VelocityLevelCommand::new(SOFT_VELOCITY)));    // soft
+        self.register_keyboard_command(Key::O, Arc::new(VelocityLevelCommand::new(MEDIUM_VELOCITY)));  // medium
+        self.register_keyboard_command(Key::P, Arc::new(VelocityLevelCommand::new(HARD_VELOCITY)));    // hard
+        
         // Track control commands (no keyboard switching - mouse only)
         self.register_keyboard_command(Key::M, Arc::new(TrackControlCommand::new(TrackAction::ToggleMute)));
         self.register_keyboard_command(Key::S, Arc::new(TrackControlCommand::new(TrackAction::ToggleSolo)));
@@ -77,12 +95,12 @@ impl InputHandler {
     }
     
     /// Handle all keyboard input by delegating to appropriate commands
-    pub fn handle_keyboard_input(&self, state: &mut State, window: &mut Window, sink: &mut Sink) {
+    pub fn handle_keyboard_input(&self, state: &mut State, window: &mut Window, bus: &MixBus) {
         for (key, command) in &self.keyboard_commands {
             if window.is_key_pressed(*key, minifb::KeyRepeat::No) || 
                (matches!(key, Key::F3 | Key::F4 | Key::F5 | Key::F6 | Key::F7 | Key::F8 | Key::F9 | Key::Key0) && 
                 window.is_key_pressed(*key, minifb::KeyRepeat::Yes)) {
-                command.execute(state, window, sink);
+                command.execute(state, window, bus);
                 // For musical note keys, return early to prevent multiple keys being processed
                 if matches!(key, Key::Q | Key::Key2 | Key::W | Key::Key3 | Key::E | Key::R | Key::Key5 | Key::T | Key::Key6 | Key::Y | Key::Key7 | Key::U) {
                     return;
@@ -92,17 +110,17 @@ impl InputHandler {
     }
     
     /// Handle mouse input
-    pub fn handle_mouse_input(&self, state: &mut State, window: &mut Window, sink: &mut Sink) {
-        self.mouse_command.execute(state, window, sink);
+    pub fn handle_mouse_input(&self, state: &mut State, window: &mut Window, bus: &MixBus) {
+        self.mouse_command.execute(state, window, bus);
     }
     
     /// Handle all input types
-    pub fn handle_input(&self, state: &mut State, window: &mut Window, sink: &mut Sink) {
-        self.handle_keyboard_input(state, window, sink);
-        self.handle_mouse_input(state, window, sink);
+    pub fn handle_input(&self, state: &mut State, window: &mut Window, bus: &MixBus) {
+        self.handle_keyboard_input(state, window, bus);
+        self.handle_mouse_input(state, window, bus);
         
         // Always handle recording control (key release timing, playback, etc.)
         let recording_command = RecordingControlCommand;
-        recording_command.execute(state, window, sink);
+        recording_command.execute(state, window, bus);
     }
 }
\ No newline at end of file
@@ -1,6 +1,6 @@
 use std::sync::Arc;
 use minifb::Window;
-use rodio::Sink;
+use crate::audio::MixBus;
 use crate::state::State;
 
 pub mod commands;
@@ -9,7 +9,7 @@ pub mod handler;
 /// Trait that all input commands must implement
 pub trait InputCommand: Send + Sync {
     /// Execute the input command with the given state and dependencies
-    fn execute(&self, state: &mut State, window: &mut Window, sink: &mut Sink);
+    fn execute(&self, state: &mut State, window: &mut Window, bus: &MixBus);
 }
 
 /// Type alias for easier usage of command references
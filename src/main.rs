@@ -1,8 +1,10 @@
 use rodio::{OutputStream, Sink};
 
 use crate::{
+    audio::MixBus,
     state::{event_loop::start_event_loop, State},
-    graphics::sprites::Sprites
+    graphics::sprites::Sprites,
+    waveforms::SAMPLE_RATE,
 };
 
 mod waveforms;
@@ -11,13 +13,15 @@ mod music_theory;
 mod graphics;
 mod input;
 mod effects;
+mod filters;
+mod modulation;
 mod audio;
 
 fn main() {
 
     // Initialize the audio output stream and sink
     let (_stream, stream_handle) = OutputStream::try_default().unwrap();
-    let mut sink = Sink::try_new(&stream_handle).unwrap();
+    let sink = Sink::try_new(&stream_handle).unwrap();
 
     // Instantiate the Sprites struct, which in turn will load sprites from sprite maps into 3d Vectors
     let sprites = Sprites::new();
@@ -25,6 +29,10 @@ fn main() {
     // Instantiate the state struct with default values for octave and waveform
     let mut state = State::new();
 
+    // Every note plays on one mix bus, which the sink plays for as long as the program runs
+    let (bus, bus_output) = MixBus::new(SAMPLE_RATE as u32, &state);
+    sink.append(bus_output);
+
     // Execute the main event loop, which handles user input and associated sound generation
-    start_event_loop(&mut state, &mut sink, &sprites);
+    start_event_loop(&mut state, &bus, &sprites);
 }
\ No newline at end of file
@@ -0,0 +1,247 @@
+use std::f32::consts::PI;
+use std::fmt;
+use super::random::Xorshift;
+use super::ModDestination;
+
+/// Slowest free-running LFO rate in Hz
+pub const MIN_LFO_RATE_HZ: f32 = 0.05;
+
+/// Fastest free-running LFO rate in Hz
+pub const MAX_LFO_RATE_HZ: f32 = 20.0;
+
+/// Slowest project tempo, at which synced times are longest
+pub const TEMPO_LOWER_BOUND: f32 = 40.0;
+
+/// Shape of the LFO waveform
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LfoShape {
+    Sine,
+    Triangle,
+    Square,
+    Saw,
+    SampleAndHold,
+}
+
+impl LfoShape {
+    /// All shapes in the order `next` cycles through them
+    pub const ALL: [LfoShape; 5] = [
+        LfoShape::Sine,
+        LfoShape::Triangle,
+        LfoShape::Square,
+        LfoShape::Saw,
+        LfoShape::SampleAndHold,
+    ];
+
+    /// Returns the next shape in the cycle Sine -> Triangle -> Square -> Saw -> S&H -> Sine
+    pub fn next(self) -> Self {
+        match self {
+            LfoShape::Sine => LfoShape::Triangle,
+            LfoShape::Triangle => LfoShape::Square,
+            LfoShape::Square => LfoShape::Saw,
+            LfoShape::Saw => LfoShape::SampleAndHold,
+            LfoShape::SampleAndHold => LfoShape::Sine,
+        }
+    }
+
+    /// Bipolar value of the waveform at `phase` (0.0 - 1.0); sample & hold returns `held_value`
+    pub fn value_at(self, phase: f32, held_value: f32) -> f32 {
+        match self {
+            LfoShape::Sine => (2.0 * PI * phase).sin(),
+            LfoShape::Triangle => {
+                if phase < 0.5 {
+                    4.0 * phase - 1.0
+                } else {
+                    3.0 - 4.0 * phase
+                }
+            }
+            LfoShape::Square => if phase < 0.5 { 1.0 } else { -1.0 },
+            LfoShape::Saw => 2.0 * phase - 1.0,
+            LfoShape::SampleAndHold => held_value,
+        }
+    }
+}
+
+/// Implements the [Display] trait for [LfoShape] using the short labels shown on the LFO panel
+impl fmt::Display for LfoShape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LfoShape::Sine => write!(f, "SIN"),
+            LfoShape::Triangle => write!(f, "TRI"),
+            LfoShape::Square => write!(f, "SQR"),
+            LfoShape::Saw => write!(f, "SAW"),
+            LfoShape::SampleAndHold => write!(f, "S+H"),
+        }
+    }
+}
+
+/// Note length of one LFO cycle (or one echo of the delay) when synced to the tempo
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SyncDivision {
+    Whole,
+    Half,
+    Quarter,
+    DottedEighth,
+    QuarterTriplet,
+    Eighth,
+    EighthTriplet,
+    Sixteenth,
+}
+
+impl SyncDivision {
+    /// All divisions from slowest to fastest, in the order the rate fader selects them
+    pub const ALL: [SyncDivision; 8] = [
+        SyncDivision::Whole,
+        SyncDivision::Half,
+        SyncDivision::Quarter,
+        SyncDivision::DottedEighth,
+        SyncDivision::QuarterTriplet,
+        SyncDivision::Eighth,
+        SyncDivision::EighthTriplet,
+        SyncDivision::Sixteenth,
+    ];
+
+    /// Picks a division from a normalized fader position (0.0 - 1.0)
+    pub fn from_normalized(normalized: f32) -> Self {
+        let last = Self::ALL.len() - 1;
+        let index = (normalized.clamp(0.0, 1.0) * last as f32).round() as usize;
+        Self::ALL[index.min(last)]
+    }
+
+    /// Length of the division in quarter-note beats
+    pub fn beats(self) -> f32 {
+        match self {
+            SyncDivision::Whole => 4.0,
+            SyncDivision::Half => 2.0,
+            SyncDivision::Quarter => 1.0,
+            SyncDivision::DottedEighth => 0.75,
+            SyncDivision::QuarterTriplet => 2.0 / 3.0,
+            SyncDivision::Eighth => 0.5,
+            SyncDivision::EighthTriplet => 1.0 / 3.0,
+            SyncDivision::Sixteenth => 0.25,
+        }
+    }
+}
+
+/// Implements the [Display] trait for [SyncDivision] as note fractions
+impl fmt::Display for SyncDivision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyncDivision::Whole => write!(f, "1/1"),
+            SyncDivision::Half => write!(f, "1/2"),
+            SyncDivision::Quarter => write!(f, "1/4"),
+            SyncDivision::DottedEighth => write!(f, "1/8."),
+            SyncDivision::QuarterTriplet => write!(f, "1/4T"),
+            SyncDivision::Eighth => write!(f, "1/8"),
+            SyncDivision::EighthTriplet => write!(f, "1/8T"),
+            SyncDivision::Sixteenth => write!(f, "1/16"),
+        }
+    }
+}
+
+/// Per-track LFO settings as edited on the LFO panel
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LfoSettings {
+    pub enabled: bool,
+    pub shape: LfoShape,
+    pub destination: ModDestination,
+    pub rate: f32,         // Fader position 0.0 - 1.0 (Hz exponentially, or a sync division)
+    pub tempo_sync: bool,  // Whether the rate follows the tempo instead of Hz
+    pub depth: f32,        // 0.0 - 1.0
+}
+
+impl LfoSettings {
+    pub fn new(destination: ModDestination) -> Self {
+        Self {
+            enabled: false,
+            shape: LfoShape::Sine,
+            destination,
+            rate: 0.5,
+            tempo_sync: false,
+            depth: 0.5,
+        }
+    }
+
+    /// Sync division selected by the rate fader when tempo sync is on
+    pub fn sync_division(&self) -> SyncDivision {
+        SyncDivision::from_normalized(self.rate)
+    }
+
+    /// LFO frequency in Hz, either free-running or derived from the tempo
+    pub fn frequency(&self, tempo_bpm: f32) -> f32 {
+        if self.tempo_sync {
+            tempo_bpm / 60.0 / self.sync_division().beats()
+        } else {
+            MIN_LFO_RATE_HZ * (MAX_LFO_RATE_HZ / MIN_LFO_RATE_HZ).powf(self.rate.clamp(0.0, 1.0))
+        }
+    }
+}
+
+/// Running low frequency oscillator producing bipolar values (-1.0 - 1.0)
+#[derive(Debug, Clone)]
+pub struct Lfo {
+    shape: LfoShape,
+    phase_increment: f32,
+    phase: f32,
+    held_value: f32,  // Current sample & hold step
+    random: Xorshift, // Source of the sample & hold steps
+    sample_rate: f32,
+}
+
+impl Lfo {
+    /// Create a new LFO starting at phase zero
+    ///
+    /// # Parameters
+    /// - `settings`: Shape and rate of the LFO
+    /// - `tempo_bpm`: Tempo used when the rate is synced
+    /// - `sample_rate`: Rate at which `next_value` is called
+    pub fn new(settings: &LfoSettings, tempo_bpm: f32, sample_rate: f32) -> Self {
+        Self::with_frequency(settings.shape, settings.frequency(tempo_bpm), sample_rate)
+    }
+
+    /// Create a new LFO running at a fixed frequency, starting at phase zero
+    ///
+    /// # Parameters
+    /// - `shape`: LFO waveform
+    /// - `frequency`: Cycles per second
+    /// - `sample_rate`: Rate at which `next_value` is called
+    pub fn with_frequency(shape: LfoShape, frequency: f32, sample_rate: f32) -> Self {
+        let mut random = Xorshift::from_clock();
+        Self {
+            shape,
+            phase_increment: frequency / sample_rate,
+            phase: 0.0,
+            held_value: random.next_bipolar(),
+            random,
+            sample_rate,
+        }
+    }
+
+    /// Change the waveform without moving the phase
+    pub fn set_shape(&mut self, shape: LfoShape) {
+        self.shape = shape;
+    }
+
+    /// Change the rate in Hz without moving the phase
+    pub fn set_frequency(&mut self, frequency: f32) {
+        self.phase_increment = frequency / self.sample_rate;
+    }
+
+    /// Go back to the start of the cycle
+    pub fn reset(&mut self) {
+        self.phase = 0.0;
+    }
+
+    /// Generate the next LFO value and advance the phase
+    pub fn next_value(&mut self) -> f32 {
+        let value = self.shape.value_at(self.phase, self.held_value);
+
+        self.phase += self.phase_increment;
+        if self.phase >= 1.0 {
+            self.phase -= 1.0;
+            // Pick a new step at the start of every cycle
+            self.held_value = self.random.next_bipolar();
+        }
+
+        value
+    }
+}
@@ -0,0 +1,253 @@
+use std::fmt;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use super::random::Xorshift;
+use super::{LFOS_PER_TRACK, MOD_CUTOFF_RANGE_OCTAVES, MOD_PITCH_RANGE_SEMITONES, MOD_PULSE_WIDTH_RANGE};
+
+/// Signal feeding a mod matrix slot
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ModSource {
+    Off,
+    Lfo1,
+    Lfo2,
+    AmpEnvelope,
+    FilterEnvelope,
+    Velocity,
+    Note,    // Note pitch relative to C4
+    Random,  // New random value on every note
+}
+
+impl ModSource {
+    /// Returns the next source in the order shown on the mod matrix buttons
+    pub fn next(self) -> Self {
+        match self {
+            ModSource::Off => ModSource::Lfo1,
+            ModSource::Lfo1 => ModSource::Lfo2,
+            ModSource::Lfo2 => ModSource::AmpEnvelope,
+            ModSource::AmpEnvelope => ModSource::FilterEnvelope,
+            ModSource::FilterEnvelope => ModSource::Velocity,
+            ModSource::Velocity => ModSource::Note,
+            ModSource::Note => ModSource::Random,
+            ModSource::Random => ModSource::Off,
+        }
+    }
+}
+
+/// Implements the [Display] trait for [ModSource] using the short labels shown on the mod matrix
+impl fmt::Display for ModSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModSource::Off => write!(f, "OFF"),
+            ModSource::Lfo1 => write!(f, "LF1"),
+            ModSource::Lfo2 => write!(f, "LF2"),
+            ModSource::AmpEnvelope => write!(f, "AEN"),
+            ModSource::FilterEnvelope => write!(f, "FEN"),
+            ModSource::Velocity => write!(f, "VEL"),
+            ModSource::Note => write!(f, "KEY"),
+            ModSource::Random => write!(f, "RND"),
+        }
+    }
+}
+
+/// Voice parameter that LFOs and mod matrix slots can modulate
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ModDestination {
+    Pitch,
+    PulseWidth,
+    Cutoff,
+    Resonance,
+    Amplitude,
+    Pan,
+    Effect { slot: usize, parameter: usize }, // Setting of an effect in the track's chain, as indexed by the effect editor
+}
+
+impl ModDestination {
+    /// Returns the next destination in the cycle Pitch -> PW -> Cutoff -> Res -> Amp -> Pan -> Pitch;
+    /// effect destinations depend on the track's chain, so they lead back to Pitch
+    pub fn next(self) -> Self {
+        match self {
+            ModDestination::Pitch => ModDestination::PulseWidth,
+            ModDestination::PulseWidth => ModDestination::Cutoff,
+            ModDestination::Cutoff => ModDestination::Resonance,
+            ModDestination::Resonance => ModDestination::Amplitude,
+            ModDestination::Amplitude => ModDestination::Pan,
+            ModDestination::Pan => ModDestination::Pitch,
+            ModDestination::Effect { .. } => ModDestination::Pitch,
+        }
+    }
+}
+
+/// Implements the [Display] trait for [ModDestination] using the short labels shown on the panels
+impl fmt::Display for ModDestination {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModDestination::Pitch => write!(f, "PIT"),
+            ModDestination::PulseWidth => write!(f, "PW"),
+            ModDestination::Cutoff => write!(f, "CUT"),
+            ModDestination::Resonance => write!(f, "RES"),
+            ModDestination::Amplitude => write!(f, "AMP"),
+            ModDestination::Pan => write!(f, "PAN"),
+            ModDestination::Effect { slot, parameter } => write!(f, "F{}.{}", slot + 1, parameter + 1),
+        }
+    }
+}
+
+/// One routing of the mod matrix, stored with the track
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModSlot {
+    pub source: ModSource,
+    pub destination: ModDestination,
+    pub amount: f32, // Bipolar -1.0 - 1.0
+}
+
+impl Default for ModSlot {
+    fn default() -> Self {
+        Self {
+            source: ModSource::Off,
+            destination: ModDestination::Cutoff,
+            amount: 0.0,
+        }
+    }
+}
+
+/// Current value of every modulation source of one voice
+#[derive(Debug, Clone, Copy)]
+pub struct ModSourceValues {
+    pub lfos: [f32; LFOS_PER_TRACK], // -1.0 - 1.0
+    pub amp_envelope: f32,           // 0.0 - 1.0
+    pub filter_envelope: f32,        // 0.0 - 1.0
+    pub velocity: f32,               // 0.0 - 1.0
+    pub note: f32,                   // -1.0 - 1.0
+    pub random: f32,                 // -1.0 - 1.0
+}
+
+impl ModSourceValues {
+    /// Value of a single source
+    pub fn get(&self, source: ModSource) -> f32 {
+        match source {
+            ModSource::Off => 0.0,
+            ModSource::Lfo1 => self.lfos[0],
+            ModSource::Lfo2 => self.lfos[1],
+            ModSource::AmpEnvelope => self.amp_envelope,
+            ModSource::FilterEnvelope => self.filter_envelope,
+            ModSource::Velocity => self.velocity,
+            ModSource::Note => self.note,
+            ModSource::Random => self.random,
+        }
+    }
+}
+
+/// Modulation accumulated from all LFOs and mod matrix slots for one sample
+#[derive(Debug, Clone, Copy)]
+pub struct ModulationOffsets {
+    pub pitch_semitones: f32,
+    pub pulse_width: f32,     // Added to a 50% duty cycle
+    pub cutoff_octaves: f32,
+    pub resonance: f32,       // Added to the track resonance
+    pub amplitude: f32,       // Gain multiplier
+    pub pan: f32,             // Added to the track pan
+}
+
+impl Default for ModulationOffsets {
+    fn default() -> Self {
+        Self {
+            pitch_semitones: 0.0,
+            pulse_width: 0.0,
+            cutoff_octaves: 0.0,
+            resonance: 0.0,
+            amplitude: 1.0,
+            pan: 0.0,
+        }
+    }
+}
+
+impl ModulationOffsets {
+    /// Add a modulation value, where 1.0 means full scale for the destination
+    pub fn add(&mut self, destination: ModDestination, value: f32) {
+        match destination {
+            ModDestination::Pitch => self.pitch_semitones += value * MOD_PITCH_RANGE_SEMITONES,
+            ModDestination::PulseWidth => self.pulse_width += value * MOD_PULSE_WIDTH_RANGE,
+            ModDestination::Cutoff => self.cutoff_octaves += value * MOD_CUTOFF_RANGE_OCTAVES,
+            ModDestination::Resonance => self.resonance += value,
+            ModDestination::Amplitude => self.amplitude *= (1.0 + value).max(0.0),
+            ModDestination::Pan => self.pan += value,
+            // Applied by the effect chain processing the note, see EffectModulation
+            ModDestination::Effect { .. } => {}
+        }
+    }
+
+    /// Add the contribution of every active mod matrix slot
+    pub fn add_slots(&mut self, slots: &[ModSlot], values: &ModSourceValues) {
+        for slot in slots.iter().filter(|slot| slot.source != ModSource::Off) {
+            self.add(slot.destination, values.get(slot.source) * slot.amount);
+        }
+    }
+}
+
+/// One effect setting modulated by a voice, with the total of every routing to it
+#[derive(Debug)]
+struct EffectModTarget {
+    slot: usize,
+    parameter: usize,
+    amount: AtomicU32, // Bits of the summed modulation, as a fraction of the knob's travel
+}
+
+/// Mod matrix routings to effect settings, shared between a voice and its track's chain on the bus
+///
+/// The voice adds up the routings to each setting on every sample; the chain reads the totals
+/// and moves the settings away from their knob positions through `AudioEffect::set_parameter`.
+#[derive(Debug, Clone, Default)]
+pub struct EffectModulation {
+    targets: Arc<[EffectModTarget]>,
+}
+
+impl EffectModulation {
+    /// Collect the effect settings the active slots of a mod matrix are routed to
+    pub fn new(slots: &[ModSlot]) -> Self {
+        let mut targets: Vec<EffectModTarget> = Vec::new();
+        for mod_slot in slots.iter().filter(|slot| slot.source != ModSource::Off) {
+            if let ModDestination::Effect { slot, parameter } = mod_slot.destination {
+                if !targets.iter().any(|target| target.slot == slot && target.parameter == parameter) {
+                    targets.push(EffectModTarget { slot, parameter, amount: AtomicU32::new(0) });
+                }
+            }
+        }
+        Self { targets: targets.into() }
+    }
+
+    /// Whether no slot is routed to an effect
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+
+    /// Whether the voice publishing the totals still holds them, which it does while it plays
+    pub fn is_shared(&self) -> bool {
+        Arc::strong_count(&self.targets) > 1
+    }
+
+    /// Sum the routings to every effect setting for the current source values
+    pub fn publish(&self, slots: &[ModSlot], values: &ModSourceValues) {
+        for target in self.targets.iter() {
+            let destination = ModDestination::Effect { slot: target.slot, parameter: target.parameter };
+            let amount: f32 = slots
+                .iter()
+                .filter(|slot| slot.source != ModSource::Off && slot.destination == destination)
+                .map(|slot| values.get(slot.source) * slot.amount)
+                .sum();
+            target.amount.store(amount.to_bits(), Ordering::Relaxed);
+        }
+    }
+
+    /// Latest totals as (effect slot, parameter index, fraction of the knob's travel)
+    pub fn amounts(&self) -> impl Iterator<Item = (usize, usize, f32)> + '_ {
+        self.targets
+            .iter()
+            .map(|target| (target.slot, target.parameter, f32::from_bits(target.amount.load(Ordering::Relaxed))))
+    }
+}
+
+/// Random value (-1.0 - 1.0) drawn once per note for the random source
+pub fn note_on_random() -> f32 {
+    // Xorshift scrambles the clock so consecutive notes do not get similar values
+    Xorshift::from_clock().next_bipolar()
+}
@@ -0,0 +1,24 @@
+pub mod lfo;
+pub mod matrix;
+pub mod random;
+
+pub use lfo::{Lfo, LfoSettings};
+pub use matrix::{EffectModulation, ModDestination, ModSlot, ModSource, ModSourceValues, ModulationOffsets};
+
+/// Number of LFOs available on every track
+pub const LFOS_PER_TRACK: usize = 2;
+
+/// Number of routing slots in every track's mod matrix
+pub const MOD_MATRIX_SLOTS: usize = 4;
+
+/// Pitch deviation in semitones of a full-scale modulation (LFO at full depth, slot at full amount)
+pub const MOD_PITCH_RANGE_SEMITONES: f32 = 2.0;
+
+/// Cutoff deviation in octaves of a full-scale modulation
+pub const MOD_CUTOFF_RANGE_OCTAVES: f32 = 4.0;
+
+/// Deviation from a 50% duty cycle of a full-scale pulse width modulation
+pub const MOD_PULSE_WIDTH_RANGE: f32 = 0.45;
+
+/// Octaves from C4 at which the note source reaches -1.0 or 1.0
+pub const MOD_NOTE_RANGE_OCTAVES: f32 = 4.0;
@@ -0,0 +1,33 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Small xorshift generator shared by the sample & hold LFOs, the random mod source and the noise in the effects
+#[derive(Debug, Clone)]
+pub struct Xorshift {
+    state: u32,
+}
+
+impl Xorshift {
+    /// Create a generator with a fixed seed, so the sequence is the same every time
+    pub fn new(seed: u32) -> Self {
+        Self { state: seed | 1 } // Xorshift must never be seeded with zero
+    }
+
+    /// Create a generator seeded from the clock, so every instance runs a different sequence
+    pub fn from_clock() -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.subsec_nanos())
+            .unwrap_or(0);
+        Self::new(seed)
+    }
+
+    /// Next random number mapped to -1.0 - 1.0
+    pub fn next_bipolar(&mut self) -> f32 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.state = x;
+        x as f32 / u32::MAX as f32 * 2.0 - 1.0
+    }
+}
@@ -1,4 +1,10 @@
 pub mod note;
 
 pub const OCTAVE_UPPER_BOUND: i32 = 6;
-pub const OCTAVE_LOWER_BOUND: i32 = 0;
\ No newline at end of file
+pub const OCTAVE_LOWER_BOUND: i32 = 0;
+
+/// Highest note velocity, as in MIDI
+pub const MAX_VELOCITY: u8 = 127;
+
+/// Velocity of computer keyboard notes until another level is picked
+pub const DEFAULT_VELOCITY: u8 = 100;
\ No newline at end of file
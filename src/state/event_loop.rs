@@ -3,7 +3,7 @@ use std::thread;
 use std::time::{Duration, Instant};
 
 use minifb::{Key as key, Window, WindowOptions};
-use rodio::Sink;
+use crate::audio::MixBus;
 
 use crate::{
     graphics::constants::*,
@@ -18,7 +18,7 @@ use crate::state::updaters::{AudioStateUpdater, VisualStateUpdater, RecordingSta
 ///
 /// # Parameters
 /// - `state`: Mutable reference to `State`, which manages the current state of the synthesizer.
-/// - `sink`: Mutable reference to `Sink`, the audio sink responsible for playing sound.
+/// - `bus`: Reference to `MixBus`, the mix every note is played on.
 /// - `sprites`: Reference to `Sprites`, containing all graphical assets used for rendering visuals.
 ///
 /// # Event Loop Logic
@@ -27,7 +27,7 @@ use crate::state::updaters::{AudioStateUpdater, VisualStateUpdater, RecordingSta
 /// - Updates the visual representation of the synthesizer based on the current state.
 /// - Renders the updated visual buffer onto the window.
 /// - Maintains a frame rate of approximately 60 frames per second by calculating necessary sleep time.
-pub fn start_event_loop(state: &mut State, sink: &mut Sink, sprites: &Sprites) {
+pub fn start_event_loop(state: &mut State, bus: &MixBus, sprites: &Sprites) {
     // Create a window with error handling
     let mut window = Window::new(
         "Rust Synthesizer 1.0",
@@ -56,10 +56,10 @@ pub fn start_event_loop(state: &mut State, sink: &mut Sink, sprites: &Sprites) {
         let start = Instant::now(); // Record start time for frame timing
 
         // Handle all input using command pattern
-        input_handler.handle_input(state, &mut window, sink);
+        input_handler.handle_input(state, &mut window, bus);
 
         // Update state using updater pattern
-        audio_updater.update(state, sink);
+        audio_updater.update(state, bus);
         visual_updater.update(state);
         recording_updater.update(state);
         mouse_updater.update(state);
@@ -1,12 +1,20 @@
+use std::cmp::Ordering;
+use std::fs;
+use std::path::PathBuf;
 use std::time::{Duration, Instant};
 
 use minifb::Key;
 
 use crate::graphics::constants::{WAVEFORM_SAWTOOTH, WAVEFORM_SINE, WAVEFORM_SQUARE, WAVEFORM_TRIANGLE};
-use crate::music_theory::{OCTAVE_LOWER_BOUND, OCTAVE_UPPER_BOUND};
+use crate::music_theory::{DEFAULT_VELOCITY, MAX_VELOCITY, OCTAVE_LOWER_BOUND, OCTAVE_UPPER_BOUND};
 use crate::music_theory::note::Note;
-use crate::waveforms::Waveform;
-use crate::effects::{DelayEffect, ReverbEffect, FlangerEffect};
+use crate::waveforms::{Waveform, SAMPLE_RATE};
+use crate::waveforms::adsr_envelope::{fader_level, fader_seconds, EnvelopeCurves, EnvelopeSettings, TriggerMode};
+use crate::audio::voice::VoiceHandle;
+use crate::effects::{AudioEffect, ChorusEffect, DelayEffect, EffectChain, EffectKind, ReverbEffect, FlangerEffect, PhaserEffect, DistortionEffect, DistortionShape, BitcrusherEffect, CompressorEffect, Detection, SidechainLevel, ParametricEqEffect, ConvolutionReverbEffect, ImpulseResponse, TremoloEffect, AutoPanEffect, LimiterEffect, ClipIndicator, EffectParameter, MAX_EFFECT_SLOTS};
+use crate::filters::{FilterMode, FilterSlope};
+use crate::modulation::lfo::{LfoShape, SyncDivision, TEMPO_LOWER_BOUND};
+use crate::modulation::{LfoSettings, ModDestination, ModSlot, LFOS_PER_TRACK, MOD_MATRIX_SLOTS};
 
 // DAW Track System
 #[derive(Debug, Clone)]
@@ -19,18 +27,41 @@ pub struct Track {
     pub playing: bool,      // Whether this track's loop is currently playing
     pub waveform: Waveform,
     pub octave: i32,
-    // Track-specific effects
-    pub delay_enabled: bool,
-    pub reverb_enabled: bool,
-    pub flanger_enabled: bool,
-    pub delay_effect: DelayEffect,
-    pub reverb_effect: ReverbEffect,
-    pub flanger_effect: FlangerEffect,
-    // Track-specific ADSR
+    // Track-specific effects, in processing order
+    pub effects: EffectChain,
+    pub sidechain_send: SidechainLevel, // This track's output level, for compressors keyed to it
+    pub sidechain_key: Option<usize>,   // Track whose level drives this track's compressor
+    // Track-specific resonant multimode filter
+    pub lpf_active: bool,
+    pub filter_mode: FilterMode,
+    pub filter_slope: FilterSlope,
+    pub filter_cutoff: f32,     // Knob position 0.0 - 1.0 (mapped exponentially to Hz)
+    pub filter_resonance: f32,  // 0.0 - 1.0
+    // Track-specific filter envelope (0-99 like the amplitude ADSR) and key tracking
+    pub filter_env_attack: u8,
+    pub filter_env_decay: u8,
+    pub filter_env_sustain: u8,
+    pub filter_env_release: u8,
+    pub filter_env_amount: f32,    // Bipolar -1.0 (closes filter) to 1.0 (opens filter)
+    pub filter_key_tracking: f32,  // 0.0 (fixed cutoff) to 1.0 (cutoff follows pitch)
+    // Track-specific LFOs
+    pub lfos: [LfoSettings; LFOS_PER_TRACK],
+    // Track-specific mod matrix (saved with the track like every other sound setting)
+    pub mod_matrix: [ModSlot; MOD_MATRIX_SLOTS],
+    // Track-specific DAHDSR
+    pub delay: u8,
     pub attack: u8,
     pub decay: u8,
     pub sustain: u8,
     pub release: u8,
+    pub hold: u8,
+    pub envelope_loop: bool, // Repeat attack, hold and decay while the note is held
+    pub envelope_curves: EnvelopeCurves,
+    pub trigger_mode: TriggerMode,
+    pub glide: u8, // Portamento time (0-99), zero for instant pitch changes
+    // Track-specific velocity response
+    pub velocity_to_amp: f32,    // 0.0 (every note at full level) to 1.0 (level follows velocity)
+    pub velocity_to_filter: f32, // 0.0 (fixed cutoff) to 1.0 (soft notes close the cutoff by up to 4 octaves)
 }
 
 impl Track {
@@ -44,43 +75,160 @@ impl Track {
             playing: false,
             waveform: Waveform::SQUARE,
             octave: 4,
-            delay_enabled: false,
-            reverb_enabled: false,
-            flanger_enabled: false,
-            delay_effect: DelayEffect::new(300.0, 0.55, 0.5, 44100),
-            reverb_effect: ReverbEffect::new(0.7, 0.4, 0.6, 44100),
-            flanger_effect: FlangerEffect::new(0.5, 0.7, 0.1, 0.5, 44100),
-            attack: 0,
-            decay: 0,
-            sustain: 50,
-            release: 20,
+            effects: EffectChain::new(SAMPLE_RATE as u32),
+            sidechain_send: SidechainLevel::default(),
+            sidechain_key: None,
+            lpf_active: false,
+            filter_mode: FilterMode::LowPass,
+            filter_slope: FilterSlope::Db12,
+            filter_cutoff: 1.0,
+            filter_resonance: 0.0,
+            filter_env_attack: 0,
+            filter_env_decay: 30,
+            filter_env_sustain: 0,
+            filter_env_release: 20,
+            filter_env_amount: 0.0,
+            filter_key_tracking: 0.0,
+            lfos: [LfoSettings::new(ModDestination::Pitch), LfoSettings::new(ModDestination::Cutoff)],
+            mod_matrix: [ModSlot::default(); MOD_MATRIX_SLOTS],
+            // DAHDSR defaults for pluck-like instant sound
+            delay: 0,    // Start right away
+            attack: 0,   // Instant attack (no delayed PAD effect)
+            decay: 0,    // No decay (sounds does not fade to sustain level)
+            sustain: 50, // Half sustain level (sound stays at half volume while key held)
+            release: 20, // Quick release
+            hold: 0,
+            envelope_loop: false,
+            envelope_curves: EnvelopeCurves::linear(),
+            trigger_mode: TriggerMode::Retrigger,
+            glide: 0,
+            velocity_to_amp: 0.5,
+            velocity_to_filter: 0.0,
+        }
+    }
+
+    /// The track's amplitude envelope in seconds and level, as heard and as drawn
+    pub fn amp_envelope(&self) -> EnvelopeSettings {
+        EnvelopeSettings {
+            delay: fader_seconds(self.delay),
+            attack: fader_seconds(self.attack),
+            hold: fader_seconds(self.hold),
+            decay: fader_seconds(self.decay),
+            sustain: fader_level(self.sustain),
+            release: fader_seconds(self.release),
+            curves: self.envelope_curves,
+            looping: self.envelope_loop,
+        }
+    }
+
+    /// Time in seconds to slide from one note to the next
+    pub fn glide_time(&self) -> f32 {
+        self.glide as f32 / 99.0 * MAX_GLIDE_SECONDS
+    }
+
+    /// The track's filter envelope in seconds and level, a plain linear ADSR
+    pub fn filter_envelope(&self) -> EnvelopeSettings {
+        EnvelopeSettings {
+            delay: 0.0,
+            attack: fader_seconds(self.filter_env_attack),
+            hold: 0.0,
+            decay: fader_seconds(self.filter_env_decay),
+            sustain: fader_level(self.filter_env_sustain),
+            release: fader_seconds(self.filter_env_release),
+            curves: EnvelopeCurves::linear(),
+            looping: false,
+        }
+    }
+
+    /// A new effect of the given kind with the track's default settings
+    pub fn default_effect(kind: EffectKind, impulse_response: &ImpulseResponse, sample_rate: u32) -> Box<dyn AudioEffect> {
+        match kind {
+            EffectKind::Delay => Box::new(DelayEffect::new(300.0, 0.55, 0.5, sample_rate)),
+            EffectKind::Reverb => Box::new(
+                ReverbEffect::new(0.7, 0.4, 0.6, sample_rate)
+                    .with_pre_delay(15.0)
+                    .with_diffusion(0.7)
+                    .with_modulation(0.3),
+            ),
+            EffectKind::Flanger => Box::new(FlangerEffect::new(0.5, 0.7, 0.1, 0.5, sample_rate)),
+            EffectKind::Chorus => Box::new(ChorusEffect::new(0.8, 0.5, 0.5, sample_rate)),
+            EffectKind::Phaser => Box::new(PhaserEffect::new(6, 0.4, 0.8, 0.5, 0.5, sample_rate)),
+            EffectKind::Distortion => Box::new(DistortionEffect::new(DistortionShape::SoftClip, 0.5, 0.6, 1.0, sample_rate)),
+            EffectKind::Bitcrusher => Box::new(BitcrusherEffect::new(8.0, 4.0, false, 1.0, sample_rate)),
+            EffectKind::Compressor => Box::new(CompressorEffect::new(-24.0, 4.0, 5.0, 150.0, 3.0, sample_rate)),
+            EffectKind::Eq => Box::new(ParametricEqEffect::new([2.0, -1.0, 1.5, 2.0], sample_rate)),
+            EffectKind::ConvolutionReverb => Box::new(ConvolutionReverbEffect::new(impulse_response.clone(), 20.0, 1.2, 0.3, sample_rate)),
+            EffectKind::Tremolo => Box::new(TremoloEffect::new(LfoShape::Sine, 5.0, 0.5, sample_rate)),
+            EffectKind::AutoPan => Box::new(AutoPanEffect::new(LfoShape::Sine, 0.5, 0.8, sample_rate)),
+            EffectKind::Limiter => Box::new(LimiterEffect::new(-0.3, 100.0, ClipIndicator::default(), sample_rate)),
         }
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct MasterTrack {
-    pub volume: f32,        // Master volume 0.0 - 1.0
-    pub delay_enabled: bool,
-    pub reverb_enabled: bool,
-    pub flanger_enabled: bool,
-    pub delay_effect: DelayEffect,
-    pub reverb_effect: ReverbEffect,
-    pub flanger_effect: FlangerEffect,
+    pub volume: f32,        // Master volume 0.0 - 1.0, applied to the mixed bus before the limiter
+    pub effects: EffectChain,
+    pub limiter: EffectChain,           // Brickwall limiter on the mixed bus after the effects, the only slot of its own chain
+    pub clip_indicator: ClipIndicator,  // Raised by the limiter when the bus goes past full scale
 }
 
 impl MasterTrack {
-    pub fn new() -> Self {
+    pub fn new(sample_rate: u32) -> Self {
+        let clip_indicator = ClipIndicator::default();
+        let mut limiter = EffectChain::new(sample_rate);
+        limiter.add_effect(EffectKind::Limiter, Box::new(LimiterEffect::new(-0.3, 100.0, clip_indicator.clone(), sample_rate)));
+
         Self {
             volume: 0.9,
-            delay_enabled: false,
-            reverb_enabled: false,
-            flanger_enabled: false,
-            delay_effect: DelayEffect::new(400.0, 0.4, 0.3, 44100),
-            reverb_effect: ReverbEffect::new(0.8, 0.3, 0.4, 44100),
-            flanger_effect: FlangerEffect::new(0.3, 0.5, 0.05, 0.3, 44100),
+            effects: EffectChain::new(sample_rate),
+            limiter,
+            clip_indicator,
         }
     }
+
+    /// A new effect of the given kind with the master bus's default settings
+    pub fn default_effect(kind: EffectKind, impulse_response: &ImpulseResponse, sample_rate: u32) -> Box<dyn AudioEffect> {
+        match kind {
+            EffectKind::Delay => Box::new(
+                DelayEffect::new(400.0, 0.4, 0.3, sample_rate)
+                    .with_ping_pong(true)
+                    .with_sync(SyncDivision::Quarter, SyncDivision::DottedEighth),
+            ),
+            // Long hall: RT60 of 3 s with thinned-out lows so the mix stays clear
+            EffectKind::Reverb => Box::new(
+                ReverbEffect::new(0.8, 0.3, 0.4, sample_rate)
+                    .with_pre_delay(30.0)
+                    .with_decay(3.0)
+                    .with_diffusion(0.75)
+                    .with_low_damping(0.4)
+                    .with_modulation(0.5),
+            ),
+            EffectKind::Flanger => Box::new(FlangerEffect::new(0.3, 0.5, 0.05, 0.3, sample_rate)),
+            EffectKind::Chorus => Box::new(ChorusEffect::new(0.5, 0.4, 0.35, sample_rate)),
+            EffectKind::Phaser => Box::new(PhaserEffect::new(4, 0.2, 0.6, 0.3, 0.5, sample_rate)),
+            EffectKind::Distortion => Box::new(DistortionEffect::new(DistortionShape::Tube, 0.2, 0.8, 0.5, sample_rate)),
+            EffectKind::Bitcrusher => Box::new(BitcrusherEffect::new(12.0, 2.0, true, 0.5, sample_rate)),
+            EffectKind::Compressor => Box::new(
+                CompressorEffect::new(-12.0, 2.0, 20.0, 200.0, 2.0, sample_rate)
+                    .with_knee(10.0)
+                    .with_detection(Detection::Rms),
+            ),
+            EffectKind::Eq => Box::new(ParametricEqEffect::new([1.0, 0.0, 0.0, 1.5], sample_rate)),
+            EffectKind::ConvolutionReverb => Box::new(ConvolutionReverbEffect::new(impulse_response.clone(), 20.0, 1.2, 0.25, sample_rate)),
+            EffectKind::Tremolo => Box::new(TremoloEffect::new(LfoShape::Triangle, 4.0, 0.4, sample_rate).with_sync(SyncDivision::Eighth)),
+            EffectKind::AutoPan => Box::new(AutoPanEffect::new(LfoShape::Sine, 0.25, 0.6, sample_rate).with_sync(SyncDivision::Half)),
+            EffectKind::Limiter => Box::new(LimiterEffect::new(-0.3, 100.0, ClipIndicator::default(), sample_rate)),
+        }
+    }
+}
+
+/// Effect chain an edit on the effects strip applies to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EffectTarget {
+    Master,
+    Track,   // The current track
+    Limiter, // The master bus's limiter, which can be edited but not bypassed or removed
 }
 
 // Recording structures
@@ -90,6 +238,7 @@ pub struct RecordedNote {
     pub octave: i32,
     pub timestamp: f32, // Time in seconds from recording start
     pub duration: f32,  // How long the note was held
+    pub velocity: u8,   // 0-127
 }
 
 #[derive(Debug, Clone)]
@@ -137,6 +286,13 @@ pub mod utils;
 pub mod updaters;
 
 const FRAME_DURATION: Duration = Duration::from_millis(16); // Approximately 60Hz refresh rate
+const FILTER_KNOB_STEP: f32 = 1.0 / 7.0; // One of the 8 knob sprite positions
+const TEMPO_UPPER_BOUND: f32 = 240.0;
+const TEMPO_STEP: f32 = 5.0;
+const MAX_GLIDE_SECONDS: f32 = 1.0; // Glide time at the top of the glide bar
+const IMPULSE_RESPONSE_DIR: &str = "assets/impulse_responses"; // WAV room responses the IR selector cycles through
+const BUILT_IN_ROOM_SECONDS: f32 = 1.2; // Tail of the synthetic room used until another IR is picked
+const IR_NAME_LENGTH: usize = 6; // Characters of a file name that fit under the IR selector
 
 // DAW State Struct - Multi-track Digital Audio Workstation
 pub struct State {
@@ -144,23 +300,18 @@ pub struct State {
     pub tracks: Vec<Track>,          // 4 individual tracks
     pub master_track: MasterTrack,   // Master mix bus
     pub current_track_id: usize,     // Currently selected track (0-3)
+    pub tempo_bpm: f32,              // Song tempo used by tempo-synced modulation
     
     // Legacy single-track compatibility (will be removed later)
     pub(crate) octave: i32,
     pub(crate) waveform: Waveform,
     pub(crate) pressed_key: Option<(Key, Note)>,
     waveform_sprite_index: usize,
-    pub(crate) filter_factor: f32,
-    pub(crate) lpf_active: usize,
     pub(crate) current_frequency: Option<f32>, // Track current playing frequency
     pub(crate) animation_start_time: Instant, // When the animation started
     pub(crate) key_release_time: Option<Instant>, // When the key was released for fade-out
-    
-    // Legacy ADSR (will use track-specific ADSR later)
-    pub attack: u8,
-    pub decay: u8,
-    pub sustain: u8,
-    pub release: u8,
+    pub(crate) active_voice: Option<(usize, VoiceHandle)>, // Live-played voice and its track, released on key up
+    pub keyboard_velocity: u8, // Velocity of notes played on the computer keyboard (0-127)
     
     // Recording state
     pub recording_state: RecordingState,
@@ -168,7 +319,7 @@ pub struct State {
     pub visual_notes: Vec<VisualNote>,
     pub recording_start_time: Option<Instant>,
     pub playback_start_time: Option<Instant>,
-    pub current_note_start: Option<(Instant, Note, i32)>, // (start_time, note, octave)
+    pub current_note_start: Option<(Instant, Note, i32, u8)>, // (start_time, note, octave, velocity)
     
     // Mouse state
     pub mouse: MouseState,
@@ -176,46 +327,53 @@ pub struct State {
     // Stop button feedback
     pub stop_button_glow_time: Option<Instant>,
     
-    // Audio effects
-    pub delay_enabled: bool,
-    pub reverb_enabled: bool,
-    pub flanger_enabled: bool,
-    pub delay_effect: DelayEffect,
-    pub reverb_effect: ReverbEffect,
-    pub flanger_effect: FlangerEffect,
+    // Clip light feedback
+    pub clip_time: Option<Instant>, // When the master bus last went past full scale
+    
+    // Effect chain editing
+    pub selected_effect: Option<(EffectTarget, usize)>, // Slot selected on the effects strip
+    pub new_effect_kind: EffectKind,                     // Effect the + buttons add
+    pub effect_knob_drag: Option<(usize, f32)>,          // Editor knob being turned and its position when grabbed
+    impulse_response: ImpulseResponse,                   // Room response for new convolution reverbs
+    impulse_response_index: usize,                       // 0 for the built-in room, otherwise the file before it in IMPULSE_RESPONSE_DIR
+    pub impulse_response_name: String,                   // Shown on the IR selector
+    pub impulse_response_failed: bool,                   // The last IR picked could not be loaded
 }
 
 // Initialize DAW State
 impl State {
     pub(crate) fn new() -> Self {
         // Create 4 tracks with different default settings
-        let tracks = vec![
+        let mut tracks = vec![
             Track::new(0, "Lead".to_string()),
             Track::new(1, "Bass".to_string()),
             Track::new(2, "Drums".to_string()),
             Track::new(3, "Pads".to_string()),
         ];
-        
+
+        // Lead and Bass play monophonic lines, so overlapping notes slur into each other by default
+        for track in tracks.iter_mut().take(2) {
+            track.trigger_mode = TriggerMode::Legato;
+        }
+
+        // Bass compression ducks under the drums once a compressor is added
+        tracks[1].sidechain_key = Some(2);
+
         State {
             // DAW Core initialization
             tracks,
-            master_track: MasterTrack::new(),
+            master_track: MasterTrack::new(SAMPLE_RATE as u32),
             current_track_id: 0, // Start with track 0 (Lead)
+            tempo_bpm: 120.0,
             octave: 4, // Set default octave to 4
             waveform: Waveform::SQUARE, // Set default waveform to Square
             pressed_key: None, // Default is no key
             waveform_sprite_index: WAVEFORM_SQUARE, // Set default waveform sprite index to Square
-            filter_factor: 1.0, // Set default cutoff to 1.0
-            lpf_active: 0, // Default for LPF is deactivated
             current_frequency: None, // No frequency being played initially
             animation_start_time: Instant::now(), // Initialize animation time
             key_release_time: None, // No key released initially
-            // ADSR defaults for pluck-like instant sound
-            attack: 0,   // Instant attack (no delayed PAD effect)
-            decay: 0,    // No decay (sounds does not fade to sustain level)
-            sustain: 50, // Half sustain level (sound stays at half volume while key held)
-            release: 20, // Quick release
-            
+            active_voice: None, // Nothing is being played live initially
+            keyboard_velocity: DEFAULT_VELOCITY,
             // Recording state defaults
             recording_state: RecordingState::Stopped,
             recorded_notes: Vec::new(),
@@ -229,22 +387,19 @@ impl State {
             
             // Stop button feedback defaults
             stop_button_glow_time: None,
+            clip_time: None,
             
-            // Audio effects defaults
-            delay_enabled: false,
-            reverb_enabled: false,
-            flanger_enabled: false,
-            delay_effect: DelayEffect::new(300.0, 0.55, 0.5, 44100), // 300ms delay, 55% feedback, 50% mix
-            reverb_effect: ReverbEffect::new(0.7, 0.4, 0.6, 44100), // Large room, light damping, 60% mix  
-            flanger_effect: FlangerEffect::new(0.5, 0.7, 0.1, 0.5, 44100), // 0.5Hz LFO, 70% depth, 10% feedback, 50% mix
+            // Effect chain editing defaults
+            selected_effect: None,
+            new_effect_kind: EffectKind::Delay,
+            effect_knob_drag: None,
+            impulse_response: ImpulseResponse::synthetic(BUILT_IN_ROOM_SECONDS, SAMPLE_RATE as u32),
+            impulse_response_index: 0,
+            impulse_response_name: "ROOM".to_string(),
+            impulse_response_failed: false,
         }
     }
 
-    /// Multiplies the sample frequency with that of the filter cutoff coefficient
-    pub fn apply_lpf(&mut self, sample: f32) -> f32 {
-        sample * self.filter_factor
-    }
-
     /// Increases the octave by one step, ensuring it does not exceed the upper bound.
     pub fn increase_octave(&mut self) {
         if self.octave < OCTAVE_UPPER_BOUND {
@@ -259,26 +414,6 @@ impl State {
         }
     }
 
-    /// Toggle LPF on/off
-    pub fn toggle_lpf(&mut self) {
-        self.lpf_active ^= 1;
-        self.filter_factor = 1.0;
-    }
-
-    /// Increases the filter cutoff
-    pub fn increase_filter_cutoff(&mut self) {
-        if self.lpf_active == 1 && self.filter_factor <= 0.9 {
-            self.filter_factor += 0.142857;
-        }
-    }
-
-    /// Decreases the filter cutoff
-    pub fn decrease_filter_cutoff(&mut self) {
-        if self.lpf_active == 1 && self.filter_factor >= 0.15 {
-            self.filter_factor -= 0.142857;
-        }
-    }
-
     /// Returns the current octave value.
     pub fn get_current_octave(&self) -> i32 {
         self.octave
@@ -306,56 +441,6 @@ impl State {
         };
     }
 
-    // ADSR control methods (0-99 range)
-    pub fn increase_attack(&mut self) {
-        self.attack = (self.attack + 1).min(99);
-    }
-
-    pub fn decrease_attack(&mut self) {
-        self.attack = self.attack.saturating_sub(1);
-    }
-
-    pub fn increase_decay(&mut self) {
-        self.decay = (self.decay + 1).min(99);
-    }
-
-    pub fn decrease_decay(&mut self) {
-        self.decay = self.decay.saturating_sub(1);
-    }
-
-    pub fn increase_sustain(&mut self) {
-        self.sustain = (self.sustain + 1).min(99);
-    }
-
-    pub fn decrease_sustain(&mut self) {
-        self.sustain = self.sustain.saturating_sub(1);
-    }
-
-    pub fn increase_release(&mut self) {
-        self.release = (self.release + 1).min(99);
-    }
-
-    pub fn decrease_release(&mut self) {
-        self.release = self.release.saturating_sub(1);
-    }
-
-    // Helper methods to convert 0-99 values to 0.0-1.0 range for calculations
-    pub fn attack_normalized(&self) -> f32 {
-        self.attack as f32 / 99.0
-    }
-
-    pub fn decay_normalized(&self) -> f32 {
-        self.decay as f32 / 99.0
-    }
-
-    pub fn sustain_normalized(&self) -> f32 {
-        self.sustain as f32 / 99.0
-    }
-
-    pub fn release_normalized(&self) -> f32 {
-        self.release as f32 / 99.0
-    }
-
     // Recording control methods
     pub fn start_recording(&mut self) {
         self.recording_state = RecordingState::Recording;
@@ -366,7 +451,7 @@ impl State {
 
     pub fn stop_recording(&mut self) {
         // Finish any currently held note
-        if let Some((start_time, note, octave)) = self.current_note_start.take() {
+        if let Some((start_time, note, octave, velocity)) = self.current_note_start.take() {
             let duration = start_time.elapsed().as_secs_f32();
             let timestamp = self.recording_start_time
                 .map(|start| start.elapsed().as_secs_f32() - duration)
@@ -377,6 +462,7 @@ impl State {
                 octave,
                 timestamp,
                 duration,
+                velocity,
             });
         }
         
@@ -431,43 +517,10 @@ impl State {
         });
     }
 
-    /// Calculate ADSR envelope amplitude at a given time since note start
-    pub fn calculate_adsr_amplitude(&self, time_since_start: f32, is_key_pressed: bool, time_since_release: Option<f32>) -> f32 {
-        if let Some(release_time) = time_since_release {
-            // Release phase
-            let release_duration = self.release_normalized() * 2.0; // Scale to 2 seconds max
-            if release_duration == 0.0 {
-                return 0.0;
-            }
-            let release_progress = (release_time / release_duration).min(1.0);
-            return self.sustain_normalized() * (1.0 - release_progress);
-        }
-
-        if !is_key_pressed {
-            return 0.0;
-        }
-
-        let attack_duration = self.attack_normalized() * 2.0; // Scale to 2 seconds max
-        let decay_duration = self.decay_normalized() * 2.0;
-
-        if time_since_start <= attack_duration {
-            // Attack phase
-            if attack_duration == 0.0 {
-                return 1.0;
-            }
-            return time_since_start / attack_duration;
-        } else if time_since_start <= attack_duration + decay_duration {
-            // Decay phase
-            if decay_duration == 0.0 {
-                return self.sustain_normalized();
-            }
-            let decay_time = time_since_start - attack_duration;
-            let decay_progress = decay_time / decay_duration;
-            return 1.0 - (1.0 - self.sustain_normalized()) * decay_progress;
-        } else {
-            // Sustain phase
-            return self.sustain_normalized();
-        }
+    /// Amplitude envelope level of the current track at a given time since note start,
+    /// evaluated with the same envelope model the audio engine plays
+    pub fn calculate_adsr_amplitude(&self, time_since_start: f32, time_since_release: Option<f32>) -> f32 {
+        self.current_track().amp_envelope().level_at(time_since_start, time_since_release)
     }
     
     // === DAW TRACK MANAGEMENT METHODS ===
@@ -476,6 +529,10 @@ impl State {
     pub fn switch_to_track(&mut self, track_id: usize) {
         if track_id < self.tracks.len() {
             self.current_track_id = track_id;
+            // A selected track slot belonged to the previous track
+            if matches!(self.selected_effect, Some((EffectTarget::Track, _))) {
+                self.selected_effect = None;
+            }
         }
     }
     
@@ -547,64 +604,48 @@ impl State {
     pub fn increase_current_track_attack(&mut self) {
         let track = &mut self.tracks[self.current_track_id];
         track.attack = (track.attack + 1).min(99);
-        // Sync with legacy state
-        self.attack = track.attack;
     }
     
     /// Decrease attack on current track
     pub fn decrease_current_track_attack(&mut self) {
         let track = &mut self.tracks[self.current_track_id];
         track.attack = track.attack.saturating_sub(1);
-        // Sync with legacy state
-        self.attack = track.attack;
     }
     
     /// Increase decay on current track
     pub fn increase_current_track_decay(&mut self) {
         let track = &mut self.tracks[self.current_track_id];
         track.decay = (track.decay + 1).min(99);
-        // Sync with legacy state
-        self.decay = track.decay;
     }
     
     /// Decrease decay on current track
     pub fn decrease_current_track_decay(&mut self) {
         let track = &mut self.tracks[self.current_track_id];
         track.decay = track.decay.saturating_sub(1);
-        // Sync with legacy state
-        self.decay = track.decay;
     }
     
     /// Increase sustain on current track
     pub fn increase_current_track_sustain(&mut self) {
         let track = &mut self.tracks[self.current_track_id];
         track.sustain = (track.sustain + 1).min(99);
-        // Sync with legacy state
-        self.sustain = track.sustain;
     }
     
     /// Decrease sustain on current track
     pub fn decrease_current_track_sustain(&mut self) {
         let track = &mut self.tracks[self.current_track_id];
         track.sustain = track.sustain.saturating_sub(1);
-        // Sync with legacy state
-        self.sustain = track.sustain;
     }
     
     /// Increase release on current track
     pub fn increase_current_track_release(&mut self) {
         let track = &mut self.tracks[self.current_track_id];
         track.release = (track.release + 1).min(99);
-        // Sync with legacy state
-        self.release = track.release;
     }
     
     /// Decrease release on current track
     pub fn decrease_current_track_release(&mut self) {
         let track = &mut self.tracks[self.current_track_id];
         track.release = track.release.saturating_sub(1);
-        // Sync with legacy state
-        self.release = track.release;
     }
     
     // === TRACK-SPECIFIC OCTAVE CONTROLS ===
@@ -629,32 +670,402 @@ impl State {
         }
     }
     
-    // === TRACK-SPECIFIC EFFECTS CONTROLS ===
+    // === EFFECT CHAIN CONTROLS ===
+
+    /// The master chain or the current track's chain
+    pub fn effect_chain(&self, target: EffectTarget) -> &EffectChain {
+        match target {
+            EffectTarget::Master => &self.master_track.effects,
+            EffectTarget::Track => &self.tracks[self.current_track_id].effects,
+            EffectTarget::Limiter => &self.master_track.limiter,
+        }
+    }
+
+    fn effect_chain_mut(&mut self, target: EffectTarget) -> &mut EffectChain {
+        match target {
+            EffectTarget::Master => &mut self.master_track.effects,
+            EffectTarget::Track => &mut self.tracks[self.current_track_id].effects,
+            EffectTarget::Limiter => &mut self.master_track.limiter,
+        }
+    }
+
+    /// Append an effect with its default settings to a chain and select it, unless the chain is full
+    pub fn add_effect_slot(&mut self, target: EffectTarget, kind: EffectKind) {
+        if self.effect_chain(target).len() >= MAX_EFFECT_SLOTS {
+            return;
+        }
+
+        let effect = match target {
+            EffectTarget::Master => MasterTrack::default_effect(kind, &self.impulse_response, SAMPLE_RATE as u32),
+            EffectTarget::Track => Track::default_effect(kind, &self.impulse_response, SAMPLE_RATE as u32),
+            EffectTarget::Limiter => return,
+        };
+        let chain = self.effect_chain_mut(target);
+        chain.add_effect(kind, effect);
+        self.selected_effect = Some((target, chain.len() - 1));
+
+        // A new compressor follows the track's existing sidechain key
+        if target == EffectTarget::Track {
+            self.apply_sidechain_key(self.current_track_id);
+        }
+    }
+
+    /// Select a slot on the effects strip and open its editor, or close the editor if it is already selected
+    pub fn select_effect_slot(&mut self, target: EffectTarget, index: usize) {
+        if self.selected_effect == Some((target, index)) {
+            self.selected_effect = None;
+        } else if index < self.effect_chain(target).len() {
+            self.selected_effect = Some((target, index));
+        }
+    }
+
+    /// Editable settings of the selected slot's effect, empty when no slot is selected
+    pub fn selected_effect_parameters(&self) -> Vec<EffectParameter> {
+        self.selected_effect
+            .and_then(|(target, index)| self.effect_chain(target).slots().get(index))
+            .map(|slot| slot.parameters())
+            .unwrap_or_default()
+    }
+
+    /// Change one setting of the selected slot's effect, given in the parameter's unit
+    pub fn set_selected_effect_parameter(&mut self, parameter: usize, value: f32) {
+        if let Some((target, index)) = self.selected_effect {
+            self.effect_chain_mut(target).set_parameter(index, parameter, value);
+        }
+    }
+
+    /// Load the next impulse response, after the built-in room and then the WAV files in
+    /// IMPULSE_RESPONSE_DIR, into the selected convolution reverb and any added later
+    ///
+    /// A file that cannot be loaded is reported and skipped, the reverb keeps its response.
+    pub fn cycle_impulse_response(&mut self) {
+        let Some((target, index)) = self.selected_effect else {
+            return;
+        };
+        let Some(reverb) = self.effect_chain(target).slots().get(index).and_then(|slot| slot.effect::<ConvolutionReverbEffect>()) else {
+            return;
+        };
+        let settings: Vec<f32> = reverb.parameters().iter().map(|parameter| parameter.value).collect();
+
+        // Listed again on every click, so files dropped in while the synth runs show up
+        let mut files: Vec<PathBuf> = fs::read_dir(IMPULSE_RESPONSE_DIR)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter(|path| path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("wav")))
+                    .collect()
+            })
+            .unwrap_or_default();
+        files.sort();
+
+        self.impulse_response_index = (self.impulse_response_index + 1) % (files.len() + 1);
+        let (impulse_response, name) = match self.impulse_response_index {
+            0 => (ImpulseResponse::synthetic(BUILT_IN_ROOM_SECONDS, SAMPLE_RATE as u32), "ROOM".to_string()),
+            file => {
+                let path = &files[file - 1];
+                match ImpulseResponse::load_wav(path) {
+                    Ok(impulse_response) => {
+                        let name = path.file_stem().unwrap_or_default().to_string_lossy().to_uppercase();
+                        (impulse_response.resampled(SAMPLE_RATE as u32), name.chars().take(IR_NAME_LENGTH).collect())
+                    }
+                    Err(error) => {
+                        println!("Could not load impulse response {}: {}", path.display(), error);
+                        self.impulse_response_failed = true;
+                        return;
+                    }
+                }
+            }
+        };
+
+        let reverb = ConvolutionReverbEffect::new(impulse_response.clone(), settings[0], settings[1], settings[2], SAMPLE_RATE as u32);
+        self.effect_chain_mut(target).replace_effect(index, Box::new(reverb));
+        self.impulse_response = impulse_response;
+        self.impulse_response_name = name;
+        self.impulse_response_failed = false;
+    }
+
+    /// Selected slot if it can be moved, bypassed or removed, which the limiter cannot
+    fn selected_chain_slot(&self) -> Option<(EffectTarget, usize)> {
+        self.selected_effect.filter(|&(target, _)| target != EffectTarget::Limiter)
+    }
+
+    /// Remove the selected slot, selecting its neighbour
+    pub fn remove_selected_effect_slot(&mut self) {
+        if let Some((target, index)) = self.selected_chain_slot() {
+            let chain = self.effect_chain_mut(target);
+            chain.remove(index);
+            self.selected_effect = if chain.is_empty() {
+                None
+            } else {
+                Some((target, index.min(chain.len() - 1)))
+            };
+
+            // Routings to the removed effect go with it, later slots move up one place
+            if target == EffectTarget::Track {
+                self.remap_effect_routings(|slot| match slot.cmp(&index) {
+                    Ordering::Less => Some(slot),
+                    Ordering::Equal => None,
+                    Ordering::Greater => Some(slot - 1),
+                });
+            }
+        }
+    }
+
+    /// Move the selected slot one place earlier (negative offset) or later in its chain
+    pub fn move_selected_effect_slot(&mut self, offset: isize) {
+        if let Some((target, index)) = self.selected_chain_slot() {
+            let chain = self.effect_chain_mut(target);
+            let new_index = index as isize + offset;
+            if new_index >= 0 && (new_index as usize) < chain.len() {
+                let new_index = new_index as usize;
+                chain.move_slot(index, new_index);
+                self.selected_effect = Some((target, new_index));
+
+                // Routings follow the moved effect and the ones it passed
+                if target == EffectTarget::Track {
+                    self.remap_effect_routings(|slot| {
+                        Some(if slot == index {
+                            new_index
+                        } else if index < slot && slot <= new_index {
+                            slot - 1
+                        } else if new_index <= slot && slot < index {
+                            slot + 1
+                        } else {
+                            slot
+                        })
+                    });
+                }
+            }
+        }
+    }
+
+    /// Point the current track's mod matrix routings to effects at the slot positions
+    /// `remap` gives, clearing routings whose effect is gone
+    fn remap_effect_routings(&mut self, remap: impl Fn(usize) -> Option<usize>) {
+        for mod_slot in self.tracks[self.current_track_id].mod_matrix.iter_mut() {
+            if let ModDestination::Effect { slot, parameter } = mod_slot.destination {
+                *mod_slot = match remap(slot) {
+                    Some(slot) => ModSlot { destination: ModDestination::Effect { slot, parameter }, ..*mod_slot },
+                    None => ModSlot::default(),
+                };
+            }
+        }
+    }
+
+    /// Bypass the selected slot, or bring it back in
+    pub fn toggle_selected_effect_bypass(&mut self) {
+        if let Some((target, index)) = self.selected_chain_slot() {
+            self.effect_chain_mut(target).toggle_bypass(index);
+        }
+    }
+
+    /// Step the effect the + buttons add through every effect type
+    pub fn cycle_new_effect_kind(&mut self) {
+        let index = EffectKind::ALL.iter().position(|&kind| kind == self.new_effect_kind).unwrap_or(0);
+        self.new_effect_kind = EffectKind::ALL[(index + 1) % EffectKind::ALL.len()];
+    }
+
+    /// Bypass or restore the current track's first effect of a kind, adding one if the track has none
+    pub fn toggle_current_track_effect(&mut self, kind: EffectKind) {
+        match self.effect_chain(EffectTarget::Track).position(kind) {
+            Some(index) => self.effect_chain_mut(EffectTarget::Track).toggle_bypass(index),
+            None => self.add_effect_slot(EffectTarget::Track, kind),
+        }
+    }
+
+    /// Key the current track's compressor to the next other track, or back to its own input after the last one
+    pub fn cycle_current_track_sidechain_key(&mut self) {
+        let current_track_id = self.current_track_id;
+        let track_count = self.tracks.len();
+        let start = self.tracks[current_track_id].sidechain_key.map_or(0, |key| key + 1);
+        self.tracks[current_track_id].sidechain_key = (start..track_count).find(|&id| id != current_track_id);
+        self.apply_sidechain_key(current_track_id);
+    }
+
+    /// Point a track's compressors at the level of the track it is keyed to
+    fn apply_sidechain_key(&mut self, track_id: usize) {
+        let sidechain = self.tracks[track_id].sidechain_key.map(|key| self.tracks[key].sidechain_send.clone());
+        self.tracks[track_id].effects.set_sidechain(sidechain);
+    }
     
-    /// Toggle delay on current track
-    pub fn toggle_current_track_delay(&mut self) {
+    // === TRACK-SPECIFIC FILTER CONTROLS ===
+
+    /// Toggle the resonant filter on current track
+    pub fn toggle_current_track_lpf(&mut self) {
         let track = &mut self.tracks[self.current_track_id];
-        track.delay_enabled = !track.delay_enabled;
-        // Sync with legacy state
-        self.delay_enabled = track.delay_enabled;
+        track.lpf_active = !track.lpf_active;
     }
-    
-    /// Toggle reverb on current track
-    pub fn toggle_current_track_reverb(&mut self) {
+
+    /// Cycle the filter mode (LP -> HP -> BP -> Notch) on current track
+    pub fn cycle_current_track_filter_mode(&mut self) {
         let track = &mut self.tracks[self.current_track_id];
-        track.reverb_enabled = !track.reverb_enabled;
-        // Sync with legacy state
-        self.reverb_enabled = track.reverb_enabled;
+        track.filter_mode = track.filter_mode.next();
     }
-    
-    /// Toggle flanger on current track
-    pub fn toggle_current_track_flanger(&mut self) {
+
+    /// Toggle the filter slope between 12 and 24 dB/octave on current track
+    pub fn toggle_current_track_filter_slope(&mut self) {
         let track = &mut self.tracks[self.current_track_id];
-        track.flanger_enabled = !track.flanger_enabled;
-        // Sync with legacy state
-        self.flanger_enabled = track.flanger_enabled;
+        track.filter_slope = track.filter_slope.toggled();
     }
-    
+
+    /// Increase filter cutoff on current track by one knob step
+    pub fn increase_current_track_filter_cutoff(&mut self) {
+        let track = &mut self.tracks[self.current_track_id];
+        if track.lpf_active {
+            track.filter_cutoff = (track.filter_cutoff + FILTER_KNOB_STEP).min(1.0);
+        }
+    }
+
+    /// Decrease filter cutoff on current track by one knob step
+    pub fn decrease_current_track_filter_cutoff(&mut self) {
+        let track = &mut self.tracks[self.current_track_id];
+        if track.lpf_active {
+            track.filter_cutoff = (track.filter_cutoff - FILTER_KNOB_STEP).max(0.0);
+        }
+    }
+
+    /// Increase filter resonance on current track by one knob step
+    pub fn increase_current_track_filter_resonance(&mut self) {
+        let track = &mut self.tracks[self.current_track_id];
+        if track.lpf_active {
+            track.filter_resonance = (track.filter_resonance + FILTER_KNOB_STEP).min(1.0);
+        }
+    }
+
+    /// Decrease filter resonance on current track by one knob step
+    pub fn decrease_current_track_filter_resonance(&mut self) {
+        let track = &mut self.tracks[self.current_track_id];
+        if track.lpf_active {
+            track.filter_resonance = (track.filter_resonance - FILTER_KNOB_STEP).max(0.0);
+        }
+    }
+
+    /// Set the curvature (-1.0 logarithmic to 1.0 exponential) of an amplitude envelope segment
+    /// on current track, where segment 0 is attack, 1 is decay and 2 is release
+    pub fn set_current_track_envelope_curve(&mut self, segment: usize, curvature: f32) {
+        let curves = &mut self.tracks[self.current_track_id].envelope_curves;
+        let curvature = curvature.clamp(-1.0, 1.0);
+        match segment {
+            0 => curves.attack = curvature,
+            1 => curves.decay = curvature,
+            _ => curves.release = curvature,
+        }
+    }
+
+    /// Switch the amplitude envelope loop of current track on or off
+    pub fn toggle_current_track_envelope_loop(&mut self) {
+        let track = &mut self.tracks[self.current_track_id];
+        track.envelope_loop = !track.envelope_loop;
+    }
+
+    /// Set the glide time of current track from a normalized bar position (0.0 - 1.0)
+    pub fn set_current_track_glide(&mut self, normalized: f32) {
+        self.tracks[self.current_track_id].glide = (normalized.clamp(0.0, 1.0) * 99.0).round() as u8;
+    }
+
+    /// Set the velocity of notes played on the computer keyboard
+    pub fn set_keyboard_velocity(&mut self, velocity: u8) {
+        self.keyboard_velocity = velocity.min(MAX_VELOCITY);
+    }
+
+    /// Set how strongly velocity scales the level of current track (0.0 - 1.0)
+    pub fn set_current_track_velocity_to_amp(&mut self, amount: f32) {
+        self.tracks[self.current_track_id].velocity_to_amp = amount.clamp(0.0, 1.0);
+    }
+
+    /// Set how strongly velocity moves the filter cutoff of current track (0.0 - 1.0)
+    pub fn set_current_track_velocity_to_filter(&mut self, amount: f32) {
+        self.tracks[self.current_track_id].velocity_to_filter = amount.clamp(0.0, 1.0);
+    }
+
+    /// Switch current track between retriggering and legato envelopes
+    pub fn toggle_current_track_trigger_mode(&mut self) {
+        let track = &mut self.tracks[self.current_track_id];
+        track.trigger_mode = track.trigger_mode.toggled();
+    }
+
+    // === TRACK-SPECIFIC LFO CONTROLS ===
+
+    /// Toggle an LFO on current track
+    pub fn toggle_current_track_lfo(&mut self, lfo_index: usize) {
+        let lfo = &mut self.tracks[self.current_track_id].lfos[lfo_index];
+        lfo.enabled = !lfo.enabled;
+    }
+
+    /// Cycle the shape of an LFO on current track
+    pub fn cycle_current_track_lfo_shape(&mut self, lfo_index: usize) {
+        let lfo = &mut self.tracks[self.current_track_id].lfos[lfo_index];
+        lfo.shape = lfo.shape.next();
+    }
+
+    /// Cycle the destination of an LFO on current track
+    pub fn cycle_current_track_lfo_destination(&mut self, lfo_index: usize) {
+        let lfo = &mut self.tracks[self.current_track_id].lfos[lfo_index];
+        lfo.destination = lfo.destination.next();
+    }
+
+    /// Switch an LFO on current track between a rate in Hz and a tempo-synced rate
+    pub fn toggle_current_track_lfo_sync(&mut self, lfo_index: usize) {
+        let lfo = &mut self.tracks[self.current_track_id].lfos[lfo_index];
+        lfo.tempo_sync = !lfo.tempo_sync;
+    }
+
+    /// Set the rate fader (0.0 - 1.0) of an LFO on current track
+    pub fn set_current_track_lfo_rate(&mut self, lfo_index: usize, rate: f32) {
+        self.tracks[self.current_track_id].lfos[lfo_index].rate = rate.clamp(0.0, 1.0);
+    }
+
+    /// Set the depth (0.0 - 1.0) of an LFO on current track
+    pub fn set_current_track_lfo_depth(&mut self, lfo_index: usize, depth: f32) {
+        self.tracks[self.current_track_id].lfos[lfo_index].depth = depth.clamp(0.0, 1.0);
+    }
+
+    // === TRACK-SPECIFIC MOD MATRIX CONTROLS ===
+
+    /// Cycle the source of a mod matrix slot on current track
+    pub fn cycle_current_track_mod_source(&mut self, slot_index: usize) {
+        let slot = &mut self.tracks[self.current_track_id].mod_matrix[slot_index];
+        slot.source = slot.source.next();
+    }
+
+    /// Cycle the destination of a mod matrix slot on current track; after Pan come the settings
+    /// of every effect in the track's chain, in editor order
+    pub fn cycle_current_track_mod_destination(&mut self, slot_index: usize) {
+        let track = &mut self.tracks[self.current_track_id];
+        let effect_destinations: Vec<ModDestination> = track.effects.slots().iter().enumerate()
+            .flat_map(|(slot, effect_slot)| {
+                (0..effect_slot.parameters().len()).map(move |parameter| ModDestination::Effect { slot, parameter })
+            })
+            .collect();
+
+        let slot = &mut track.mod_matrix[slot_index];
+        slot.destination = match slot.destination {
+            ModDestination::Pan => effect_destinations.first().copied().unwrap_or(ModDestination::Pitch),
+            destination @ ModDestination::Effect { .. } => effect_destinations.iter()
+                .position(|&effect| effect == destination)
+                .and_then(|index| effect_destinations.get(index + 1).copied())
+                .unwrap_or(ModDestination::Pitch),
+            destination => destination.next(),
+        };
+    }
+
+    /// Set the bipolar amount (-1.0 - 1.0) of a mod matrix slot on current track
+    pub fn set_current_track_mod_amount(&mut self, slot_index: usize, amount: f32) {
+        self.tracks[self.current_track_id].mod_matrix[slot_index].amount = amount.clamp(-1.0, 1.0);
+    }
+
+    // === TEMPO CONTROLS ===
+
+    /// Increases the tempo by one step, ensuring it does not exceed the upper bound.
+    pub fn increase_tempo(&mut self) {
+        self.tempo_bpm = (self.tempo_bpm + TEMPO_STEP).min(TEMPO_UPPER_BOUND);
+    }
+
+    /// Decreases the tempo by one step, ensuring it does not go below the lower bound.
+    pub fn decrease_tempo(&mut self) {
+        self.tempo_bpm = (self.tempo_bpm - TEMPO_STEP).max(TEMPO_LOWER_BOUND);
+    }
+
     /// Toggle waveform on current track
     pub fn toggle_current_track_waveform(&mut self) {
         let track = &mut self.tracks[self.current_track_id];
@@ -1,5 +1,5 @@
 use crate::state::State;
-use rodio::Sink;
+use crate::audio::MixBus;
 
 /// Handles audio-related state updates
 pub struct AudioStateUpdater;
@@ -10,7 +10,10 @@ impl AudioStateUpdater {
     }
     
     /// Update audio-related state logic
-    pub fn update(&self, state: &mut State, sink: &mut Sink) {
+    pub fn update(&self, state: &mut State, bus: &MixBus) {
+        // Effect chains and the master volume edited this frame reach the mix playing on the bus
+        bus.sync(state);
+
         // Handle key release timing and audio fade effects
         self.handle_key_release_timing(state);
         
@@ -21,7 +21,7 @@ impl RecordingStateUpdater {
     fn handle_recording_cleanup(&self, state: &mut State) {
         // Finish any held notes when stopping recording
         if state.recording_state == RecordingState::Stopped && state.current_note_start.is_some() {
-            if let Some((start_time, note, octave)) = state.current_note_start.take() {
+            if let Some((start_time, note, octave, velocity)) = state.current_note_start.take() {
                 let duration = start_time.elapsed().as_secs_f32();
                 let timestamp = state.recording_start_time
                     .map(|start| start.elapsed().as_secs_f32() - duration)
@@ -32,6 +32,7 @@ impl RecordingStateUpdater {
                     octave,
                     timestamp,
                     duration,
+                    velocity,
                 });
             }
         }
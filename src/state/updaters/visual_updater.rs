@@ -1,3 +1,5 @@
+use std::time::Instant;
+
 use crate::state::State;
 
 /// Handles visual-related state updates
@@ -15,6 +17,16 @@ impl VisualStateUpdater {
         
         // Update stop button glow effect
         self.update_stop_button_glow(state);
+        
+        // Light the clip light if the master limiter caught an overload since the last frame
+        self.update_clip_light(state);
+    }
+    
+    /// Restart the clip light's hold time when the master bus went past full scale
+    fn update_clip_light(&self, state: &mut State) {
+        if state.master_track.clip_indicator.take() {
+            state.clip_time = Some(Instant::now());
+        }
     }
     
     /// Update stop button glow timing
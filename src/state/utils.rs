@@ -1,84 +1,18 @@
 use std::collections::HashMap;
 
 use minifb::Key;
-use rodio::{Sink, Source};
+use crate::audio::MixBus;
 use crate::audio::MultiTrackMixer;
-use crate::effects::{EffectWrapper, AudioEffect, DelayEffect, ReverbEffect, FlangerEffect};
-use std::time::Duration;
+use crate::effects::{EffectKind, EffectWrapper, MAX_EFFECT_SLOTS};
 
-use crate::graphics::draw::{draw_adsr_faders, draw_control_buttons, draw_display_sprite_single, draw_idle_key_sprites, draw_idle_tangent_sprites, draw_note_sprite, draw_octave_fader_sprite, draw_pressed_key_sprite, draw_rack_sprite, draw_tangent_sprites};
+use crate::graphics::draw::{draw_adsr_faders, draw_bulb_sprite, draw_control_buttons, draw_delay_hold_faders, draw_display_sprite_single, draw_envelope_graph, draw_eq_response, draw_filter_cutoff_knob_sprite, draw_filter_envelope_faders, draw_filter_resonance_knob_sprite, draw_idle_key_sprites, draw_idle_tangent_sprites, draw_note_sprite, draw_octave_fader_sprite, draw_pressed_key_sprite, draw_rack_sprite, draw_tangent_sprites};
 use crate::graphics::sprites::Sprites;
 use crate::music_theory::note::Note;
-use crate::state::State;
-use crate::waveforms::adsr_envelope::ADSREnvelope;
-use crate::waveforms::sawtooth_wave::SawtoothWave;
-use crate::waveforms::sine_wave::SineWave;
-use crate::waveforms::square_wave::SquareWave;
-use crate::waveforms::triangle_wave::TriangleWave;
-use crate::waveforms::{Waveform, AMPLITUDE};
-
-/// Effects processor that applies enabled effects to an audio source
-struct EffectsProcessor<S: Source<Item = f32>> {
-    source: S,
-    delay_effect: DelayEffect,
-    reverb_effect: ReverbEffect,
-    flanger_effect: FlangerEffect,
-    delay_enabled: bool,
-    reverb_enabled: bool,
-    flanger_enabled: bool,
-}
-
-impl<S: Source<Item = f32>> EffectsProcessor<S> {
-    fn new(source: S, state: &State) -> Self {
-        Self {
-            source,
-            delay_effect: DelayEffect::new(300.0, 0.55, 0.5, 44100), // Enhanced parameters
-            reverb_effect: ReverbEffect::new(0.7, 0.4, 0.6, 44100),  // Larger room, more wet
-            flanger_effect: FlangerEffect::new(0.5, 0.7, 0.1, 0.5, 44100),
-            delay_enabled: state.delay_enabled,
-            reverb_enabled: state.reverb_enabled,
-            flanger_enabled: state.flanger_enabled,
-        }
-    }
-}
-
-impl<S: Source<Item = f32>> Iterator for EffectsProcessor<S> {
-    type Item = f32;
+use crate::state::{EffectTarget, State};
+use crate::modulation::ModSource;
+use crate::waveforms::adsr_envelope::TriggerMode;
+use crate::waveforms::{Waveform, AMPLITUDE, SAMPLE_RATE};
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.source.next().map(|mut sample| {
-            // Apply effects in series: Delay -> Reverb -> Flanger
-            if self.delay_enabled {
-                sample = self.delay_effect.process_sample(sample);
-            }
-            if self.reverb_enabled {
-                sample = self.reverb_effect.process_sample(sample);
-            }
-            if self.flanger_enabled {
-                sample = self.flanger_effect.process_sample(sample);
-            }
-            sample
-        })
-    }
-}
-
-impl<S: Source<Item = f32>> Source for EffectsProcessor<S> {
-    fn current_frame_len(&self) -> Option<usize> {
-        self.source.current_frame_len()
-    }
-
-    fn channels(&self) -> u16 {
-        self.source.channels()
-    }
-
-    fn sample_rate(&self) -> u32 {
-        self.source.sample_rate()
-    }
-
-    fn total_duration(&self) -> Option<Duration> {
-        self.source.total_duration()
-    }
-}
 use crate::{
     graphics::constants::*,
     graphics::waveform_display::generate_waveform_display
@@ -88,10 +22,11 @@ use crate::{
 ///
 /// # Parameters
 /// - `octave`: A mutable reference to the current octave of the synthesizer.
-/// - `sink`: A mutable reference to the audio sink where the sound will be played.
+/// - `bus`: The mix bus where the sound will be played.
 /// - `current_waveform`: The waveform enum representing the type of waveform to use for synthesizing the sound.
 /// - `note`: The musical note (pitch) to be played.
-pub fn handle_musical_note(state: &mut State, sink: &mut Sink, note: Note) {
+/// - `velocity`: How hard the note was played (0-127).
+pub fn handle_musical_note(state: &mut State, bus: &MixBus, note: Note, velocity: u8) {
     // Get current track info without borrowing
     let current_track_id = state.current_track_id;
     let base_frequency = note.frequency(state.tracks[current_track_id].octave);
@@ -101,13 +36,32 @@ pub fn handle_musical_note(state: &mut State, sink: &mut Sink, note: Note) {
     state.animation_start_time = std::time::Instant::now();
     state.key_release_time = None; // Clear any previous release time
 
-    // Stop any currently playing audio to prevent queueing
-    sink.stop();
+    // Take over from the note still sounding on this track, if any
+    let mut start_level = 0.0;
+    let mut glide_from = None;
+    if let Some((track_id, voice)) = state.active_voice.take() {
+        if track_id == current_track_id && !voice.is_finished() {
+            // Legato keeps the held voice and its envelopes running, only the pitch moves (gliding if set)
+            if state.tracks[current_track_id].trigger_mode == TriggerMode::Legato && voice.is_held() {
+                voice.legato(base_frequency);
+                state.active_voice = Some((track_id, voice));
+                return;
+            }
+            // The new note starts from where this one is, so it can be cut without a click
+            start_level = voice.level();
+            glide_from = Some(voice.frequency());
+            voice.stop();
+        } else {
+            // A note left held on another track rings out alongside the new one
+            voice.release();
+        }
+    }
 
-    // Create mixer and play note on current track
-    let mixer = MultiTrackMixer::new(44100);
+    // Create mixer and play note on current track, retriggering from the level and pitch of the previous note
+    let mixer = MultiTrackMixer::new(SAMPLE_RATE as u32, state.tempo_bpm);
     let current_track = &state.tracks[current_track_id];
-    mixer.play_note_on_track(current_track, note, sink);
+    let voice = mixer.play_held_note_on_track(current_track, note, velocity, start_level, glide_from, bus);
+    state.active_voice = Some((current_track_id, voice));
     
     // Return early - mixer handles everything now
     return;
@@ -205,17 +159,47 @@ pub fn update_buffer_with_state(state: &State, sprites: &Sprites, window_buffer:
     // Draw all tangents as overlay on key sprites in their idle state first
     draw_idle_tangent_sprites(sprites, window_buffer, &tangent_map);
 
-    // Draw the bulb
-    // draw_bulb_sprite(state, sprites, window_buffer);
+    // Draw the bulb, lit when the current track's LPF is active
+    draw_bulb_sprite(state, sprites, window_buffer);
 
     // Draw the cutoff knob for LPF
-    // draw_filter_cutoff_knob_sprite(state, sprites, window_buffer);
+    draw_filter_cutoff_knob_sprite(state, sprites, window_buffer);
+
+    // Draw the resonance knob to the right of the cutoff knob for LPF
+    draw_filter_resonance_knob_sprite(state, sprites, window_buffer);
+
+    // Draw filter panel labels
+    draw_filter_labels(window_buffer);
+
+    // Draw filter mode and slope buttons for the current track
+    draw_filter_mode_buttons(state, window_buffer);
+
+    // Draw the filter envelope, envelope amount and key tracking faders
+    draw_filter_envelope_faders(state, window_buffer);
+
+    // Draw the LFO panel of the current track and the tempo
+    draw_lfo_panel(state, window_buffer);
+
+    // Draw the glide bar of the current track next to the tempo
+    draw_glide_bar(state, window_buffer);
+
+    // Draw the mod matrix slots of the current track
+    draw_mod_matrix(state, window_buffer);
+
+    // Draw the keyboard velocity and the current track's velocity sensitivity
+    draw_velocity_panel(state, window_buffer);
 
-    // Draw the idle knob to the left of the cutoff knob for LPF
-    // draw_idle_knob_sprite(sprites, window_buffer);
+    // Draw the amplitude envelope graph and its segment curve bars
+    draw_envelope_graph(state, window_buffer);
+    draw_eq_response(state, window_buffer);
 
-    // Draw ADSR faders
+    // Draw the retrigger/legato and envelope loop buttons left of the envelope graph
+    draw_trigger_mode_button(state, window_buffer);
+    draw_envelope_loop_button(state, window_buffer);
+
+    // Draw ADSR faders and the delay and hold faders next to them
     draw_adsr_faders(state, sprites, window_buffer);
+    draw_delay_hold_faders(state, window_buffer);
     
     // Draw control buttons - DISABLED: now using per-track transport
     // draw_control_buttons(state, window_buffer);
@@ -234,15 +218,9 @@ pub fn update_buffer_with_state(state: &State, sprites: &Sprites, window_buffer:
     
     // Always show the display frame, but only show waveform when playing or fading
     let (frequency, amplitude) = if state.current_frequency.is_some() || state.key_release_time.is_some() {
-        // Calculate amplitude based on whether key is pressed or released
-        let amplitude = if let Some(release_time) = state.key_release_time {
-            // Fade out over 2 seconds after key release
-            let fade_duration = release_time.elapsed().as_secs_f32();
-            let fade_factor = (1.0 - fade_duration / 2.0).max(0.0);
-            fade_factor
-        } else {
-            1.0 // Full brightness when key is pressed
-        };
+        // Follow the amplitude envelope of the current track, so the display fades as the note does
+        let time_since_release = state.key_release_time.map(|release_time| release_time.elapsed().as_secs_f32());
+        let amplitude = state.calculate_adsr_amplitude(animation_time, time_since_release);
         
         // Use last played frequency during fade
         let frequency = state.current_frequency.unwrap_or(440.0);
@@ -257,6 +235,9 @@ pub fn update_buffer_with_state(state: &State, sprites: &Sprites, window_buffer:
     let current_track_waveform = state.tracks[state.current_track_id].waveform.clone();
     let waveform_sprite = generate_waveform_display(frequency, current_track_waveform, animation_time, amplitude);
     draw_display_sprite_single(&waveform_sprite, window_buffer);
+
+    // Draw the editor of the selected effect slot over the waveform display
+    draw_effect_editor(state, window_buffer);
     
 
     // Check if a key is pressed
@@ -360,49 +341,200 @@ pub fn create_tangent_map() -> HashMap<i32, usize> {
     tangent_map
 }
 
-/// Draws effects buttons (Delay, Reverb, Flanger) between waveform display and ADSR faders
+/// Draws the master chain and, below it, the current track's chain as one button per slot in processing order,
+/// with the controls for the selected slot and the effect type to add
 pub fn draw_effects_buttons(state: &State, buffer: &mut Vec<u32>) {
-    // Position between waveform display and ADSR faders
-    let display_end_x = 164 + 164; // 328
-    let adsr_start_x = 164 + 164 + 104; // 432
-    let available_width = adsr_start_x - display_end_x; // 104px
-    
-    let button_width = 30;
-    let button_height = 20;
-    let button_spacing = (available_width - (3 * button_width)) / 4; // Equal spacing
-    let base_x = display_end_x + button_spacing;
-    let base_y = 4 * 51 + 17 + 15; // Same Y as display + offset
-    
-    // Show current track's effects
     let current_track = &state.tracks[state.current_track_id];
-    let effects = [
-        ("DLY", current_track.delay_enabled, 0xFF4444FF), // Blue for delay
-        ("REV", current_track.reverb_enabled, 0xFF44FF44), // Green for reverb  
-        ("FLG", current_track.flanger_enabled, 0xFFFF4444), // Red for flanger
+    let rows = [
+        ("MST", EffectTarget::Master, EFFECTS_PANEL_Y),
+        ("TRK", EffectTarget::Track, EFFECTS_PANEL_Y + EFFECT_BUTTON_HEIGHT + 4),
     ];
+    let step = EFFECT_BUTTON_WIDTH + EFFECT_BUTTON_SPACING;
     
-    for (i, (label, enabled, base_color)) in effects.iter().enumerate() {
-        let x = base_x + i * (button_width + button_spacing);
+    for (row_label, target, row_y) in rows.iter() {
+        draw_simple_text(EFFECTS_PANEL_X, row_y + EFFECT_BUTTON_HEIGHT / 2 - 2, row_label, 0xFFCCCCCC, buffer);
+        
+        let chain = state.effect_chain(*target);
+        for (i, slot) in chain.slots().iter().enumerate() {
+            let x = EFFECTS_PANEL_X + 16 + i * step;
+            
+            // Choose colors based on state
+            let (bg_color, border_color, text_color) = if slot.bypassed {
+                (0xFF333333, 0xFF666666, 0xFF999999) // Dark when bypassed
+            } else {
+                (slot.kind.color(), 0xFFFFFFFF, 0xFFFFFFFF) // Bright when active
+            };
+            draw_effects_button_shape(x, *row_y, EFFECT_BUTTON_WIDTH, EFFECT_BUTTON_HEIGHT, bg_color, border_color, buffer);
+            draw_effects_button_label(x, *row_y, EFFECT_BUTTON_WIDTH, &slot.kind.to_string(), text_color, buffer);
+            
+            if state.selected_effect == Some((*target, i)) {
+                draw_selection_underline(x, *row_y, EFFECT_BUTTON_WIDTH, buffer);
+            }
+        }
         
-        // Choose colors based on state
-        let (bg_color, border_color, text_color) = if *enabled {
-            (*base_color, 0xFFFFFFFF, 0xFFFFFFFF) // Bright when enabled
+        // Add button after the last slot while the chain has room
+        if chain.len() < MAX_EFFECT_SLOTS {
+            let x = EFFECTS_PANEL_X + 16 + chain.len() * step;
+            draw_effects_button_shape(x, *row_y, EFFECT_BUTTON_WIDTH, EFFECT_BUTTON_HEIGHT, 0xFF333333, 0xFF666666, buffer);
+            draw_effects_button_label(x, *row_y, EFFECT_BUTTON_WIDTH, "+", 0xFF999999, buffer);
+        }
+    }
+    
+    // Controls for the selected slot, dimmed while nothing they apply to is selected
+    let edit_colors = if matches!(state.selected_effect, Some((target, _)) if target != EffectTarget::Limiter) {
+        (0xFF444444, 0xFFCCCCCC, 0xFFFFFFFF)
+    } else {
+        (0xFF333333, 0xFF666666, 0xFF999999)
+    };
+    for (i, label) in ["<", ">", "BYP", "DEL"].iter().enumerate() {
+        let x = EFFECT_EDIT_X + i * step;
+        draw_effects_button_shape(x, rows[0].2, EFFECT_BUTTON_WIDTH, EFFECT_BUTTON_HEIGHT, edit_colors.0, edit_colors.1, buffer);
+        draw_effects_button_label(x, rows[0].2, EFFECT_BUTTON_WIDTH, label, edit_colors.2, buffer);
+    }
+    
+    // Effect type the + buttons add
+    let new_y = rows[1].2;
+    draw_simple_text(NEW_EFFECT_X - 16, new_y + EFFECT_BUTTON_HEIGHT / 2 - 2, "NEW", 0xFFCCCCCC, buffer);
+    draw_effects_button_shape(NEW_EFFECT_X, new_y, EFFECT_BUTTON_WIDTH, EFFECT_BUTTON_HEIGHT, state.new_effect_kind.color(), 0xFFFFFFFF, buffer);
+    draw_effects_button_label(NEW_EFFECT_X, new_y, EFFECT_BUTTON_WIDTH, &state.new_effect_kind.to_string(), 0xFFFFFFFF, buffer);
+    
+    // Track whose level drives the current track's compressor, amber while keyed
+    let key_y = rows[1].2;
+    let (bg_color, border_color, text_color) = if current_track.sidechain_key.is_some() {
+        (0xFFAA6600, 0xFFFFFFFF, 0xFFFFFFFF)
+    } else {
+        (0xFF333333, 0xFF666666, 0xFF999999)
+    };
+    draw_effects_button_shape(SIDECHAIN_BUTTON_X, key_y, SIDECHAIN_BUTTON_WIDTH, EFFECT_BUTTON_HEIGHT, bg_color, border_color, buffer);
+    let key_label = match current_track.sidechain_key {
+        Some(key) => format!("KEY {}", key + 1),
+        None => "KEY -".to_string(),
+    };
+    draw_simple_text(SIDECHAIN_BUTTON_X + 10, key_y + EFFECT_BUTTON_HEIGHT / 2 - 2, &key_label, text_color, buffer);
+    
+    // Master limiter, with a clip light that stays lit for a moment after the bus went past full scale
+    let limiter_y = rows[0].2;
+    draw_effects_button_shape(LIMITER_BUTTON_X, limiter_y, LIMITER_BUTTON_WIDTH, EFFECT_BUTTON_HEIGHT, 0xFF444444, 0xFFCCCCCC, buffer);
+    draw_simple_text(LIMITER_BUTTON_X + 8, limiter_y + EFFECT_BUTTON_HEIGHT / 2 - 2, "LIM", 0xFFFFFFFF, buffer);
+    if state.selected_effect == Some((EffectTarget::Limiter, 0)) {
+        draw_selection_underline(LIMITER_BUTTON_X, limiter_y, LIMITER_BUTTON_WIDTH, buffer);
+    }
+    let clipping = state.clip_time.is_some_and(|time| time.elapsed().as_secs_f32() < CLIP_LIGHT_HOLD_SECONDS);
+    let light_color = if clipping { 0xFFFF2222 } else { 0xFF441111 };
+    let light_x = LIMITER_BUTTON_X + LIMITER_BUTTON_WIDTH - 14;
+    for dy in 0..6 {
+        let start = (limiter_y + 6 + dy) * WINDOW_WIDTH + light_x;
+        if let Some(row) = buffer.get_mut(start..start + 6) {
+            row.fill(light_color);
+        }
+    }
+}
+
+/// Underline the button at (`x`, `y`) in amber to mark it as selected
+fn draw_selection_underline(x: usize, y: usize, width: usize, buffer: &mut [u32]) {
+    for dy in 1..3 {
+        for dx in 2..width - 2 {
+            let index = (y + EFFECT_BUTTON_HEIGHT + dy) * WINDOW_WIDTH + x + dx;
+            if index < buffer.len() {
+                buffer[index] = 0xFFFFAA00;
+            }
+        }
+    }
+}
+
+/// Draws the editor of the selected effect slot over the waveform display: one knob per parameter
+/// with its label and value readout, in rows of EFFECT_EDITOR_COLUMNS
+pub fn draw_effect_editor(state: &State, buffer: &mut [u32]) {
+    let Some(slot) = state.selected_effect.and_then(|(target, index)| state.effect_chain(target).slots().get(index)) else {
+        return;
+    };
+
+    let rows = slot.parameters().len().div_ceil(EFFECT_EDITOR_COLUMNS).max(3);
+    let height = rows * EFFECT_KNOB_CELL_HEIGHT;
+    let width = EFFECT_EDITOR_COLUMNS * EFFECT_KNOB_CELL_WIDTH;
+    for y in EFFECT_EDITOR_Y..EFFECT_EDITOR_Y + height {
+        let start = y * WINDOW_WIDTH + EFFECT_EDITOR_X;
+        if let Some(row) = buffer.get_mut(start..start + width) {
+            row.fill(0xFF111111);
+        }
+    }
+
+    // Bypassed effects keep their settings but are drawn greyed out
+    let (arc_color, text_color) = if slot.bypassed {
+        (0xFF777777, 0xFF999999)
+    } else {
+        (slot.kind.color(), 0xFFFFFFFF)
+    };
+
+    for (i, parameter) in slot.parameters().iter().enumerate() {
+        let x = EFFECT_EDITOR_X + (i % EFFECT_EDITOR_COLUMNS) * EFFECT_KNOB_CELL_WIDTH;
+        let y = EFFECT_EDITOR_Y + (i / EFFECT_EDITOR_COLUMNS) * EFFECT_KNOB_CELL_HEIGHT;
+        let readout_color = if state.effect_knob_drag.map(|(index, _)| index) == Some(i) { 0xFFFFAA00 } else { text_color };
+
+        draw_effect_knob(x + 6, y + 8, parameter.normalized(), arc_color, buffer);
+        draw_simple_text(x + 13, y + 2, parameter.label, 0xFF999999, buffer);
+        draw_simple_text(x + 13, y + 9, &parameter.readout(), readout_color, buffer);
+    }
+
+    // Convolution reverbs get a cell after their knobs that picks the impulse response
+    if slot.kind == EffectKind::ConvolutionReverb {
+        let i = slot.parameters().len();
+        let x = EFFECT_EDITOR_X + (i % EFFECT_EDITOR_COLUMNS) * EFFECT_KNOB_CELL_WIDTH;
+        let y = EFFECT_EDITOR_Y + (i / EFFECT_EDITOR_COLUMNS) * EFFECT_KNOB_CELL_HEIGHT;
+        let (readout, readout_color) = if state.impulse_response_failed {
+            ("ERROR", 0xFFFF4444)
         } else {
-            (0xFF333333, 0xFF666666, 0xFF999999) // Dark when disabled
+            (state.impulse_response_name.as_str(), text_color)
         };
-        
-        // Draw button background and border with rounded corners effect
-        draw_effects_button_shape(x, base_y, button_width, button_height, bg_color, border_color, buffer);
-        
-        // Draw label text centered
-        let text_x = x + button_width / 2 - (label.len() * 2); // Rough centering
-        let text_y = base_y + button_height / 2 - 3;
-        draw_effects_button_text(text_x, text_y, label, text_color, buffer);
+
+        draw_simple_text(x + 2, y + 2, "IR", 0xFF999999, buffer);
+        draw_simple_text(x + 2, y + 9, readout, readout_color, buffer);
     }
 }
 
+/// Draw a small knob centered on (`center_x`, `center_y`): a 270 degree arc lit up to the
+/// knob position (0.0 - 1.0) with a pointer, open at the bottom
+fn draw_effect_knob(center_x: usize, center_y: usize, position: f32, color: u32, buffer: &mut [u32]) {
+    let radius = 5.0f32;
+    let sweep = 135.0f32.to_radians();
+    let pointer_angle = -sweep + 2.0 * sweep * position.clamp(0.0, 1.0);
+
+    for dy in -5i32..=5 {
+        for dx in -5i32..=5 {
+            let distance = ((dx * dx + dy * dy) as f32).sqrt();
+            // Angle clockwise from the top of the knob
+            let angle = (dx as f32).atan2(-dy as f32);
+            if distance < radius - 1.5 || distance > radius + 0.5 || angle.abs() > sweep {
+                continue;
+            }
+            let pixel_color = if angle <= pointer_angle { color } else { 0xFF444444 };
+            let index = (center_y as i32 + dy) as usize * WINDOW_WIDTH + (center_x as i32 + dx) as usize;
+            if index < buffer.len() {
+                buffer[index] = pixel_color;
+            }
+        }
+    }
+
+    // Pointer from the centre towards the arc
+    for step in 0..4 {
+        let x = center_x as f32 + pointer_angle.sin() * step as f32 + 0.5;
+        let y = center_y as f32 - pointer_angle.cos() * step as f32 + 0.5;
+        let index = y as usize * WINDOW_WIDTH + x as usize;
+        if index < buffer.len() {
+            buffer[index] = 0xFFFFFFFF;
+        }
+    }
+}
+
+/// Draw a label roughly centered on an effects button
+fn draw_effects_button_label(x: usize, y: usize, width: usize, label: &str, color: u32, buffer: &mut Vec<u32>) {
+    let text_x = x + width / 2 - (label.len() * 2);
+    let text_y = y + EFFECT_BUTTON_HEIGHT / 2 - 2;
+    draw_effects_button_text(text_x, text_y, label, color, buffer);
+}
+
 /// Draw a button shape with rounded corners effect and glow
-fn draw_effects_button_shape(x: usize, y: usize, width: usize, height: usize, bg_color: u32, border_color: u32, buffer: &mut Vec<u32>) {
+fn draw_effects_button_shape(x: usize, y: usize, width: usize, height: usize, bg_color: u32, border_color: u32, buffer: &mut [u32]) {
     // Draw main button body
     for dy in 1..height-1 {
         for dx in 1..width-1 {
@@ -458,6 +590,21 @@ fn draw_effects_button_text(x: usize, y: usize, text: &str, color: u32, buffer:
         ('V', vec![0b101, 0b101, 0b101, 0b101, 0b010]),
         ('F', vec![0b111, 0b100, 0b111, 0b100, 0b100]),
         ('G', vec![0b111, 0b100, 0b101, 0b101, 0b111]),
+        ('C', vec![0b111, 0b100, 0b100, 0b100, 0b111]),
+        ('H', vec![0b101, 0b101, 0b111, 0b101, 0b101]),
+        ('O', vec![0b111, 0b101, 0b101, 0b101, 0b111]),
+        ('P', vec![0b111, 0b101, 0b111, 0b100, 0b100]),
+        ('A', vec![0b111, 0b101, 0b111, 0b101, 0b101]),
+        ('S', vec![0b111, 0b100, 0b111, 0b001, 0b111]),
+        ('T', vec![0b111, 0b010, 0b010, 0b010, 0b010]),
+        ('B', vec![0b110, 0b101, 0b110, 0b101, 0b110]),
+        ('I', vec![0b111, 0b010, 0b010, 0b010, 0b111]),
+        ('M', vec![0b101, 0b111, 0b101, 0b101, 0b101]),
+        ('Q', vec![0b111, 0b101, 0b101, 0b111, 0b001]),
+        ('N', vec![0b110, 0b101, 0b101, 0b101, 0b101]),
+        ('+', vec![0b000, 0b010, 0b111, 0b010, 0b000]),
+        ('<', vec![0b001, 0b010, 0b100, 0b010, 0b001]),
+        ('>', vec![0b100, 0b010, 0b001, 0b010, 0b100]),
     ]);
     
     for (i, ch) in text.chars().enumerate() {
@@ -496,6 +643,217 @@ fn blend_colors(color1: u32, color2: u32, factor: f32) -> u32 {
     0xFF000000 | (r << 16) | (g << 8) | b
 }
 
+/// Draws the labels of the filter panel (filter bulb, cutoff and resonance knobs)
+pub fn draw_filter_labels(buffer: &mut [u32]) {
+    let label_color = 0xFFFFFFFF;
+    let knob_width = 64; // sprites.knob[0].width
+    let label_y = FILTER_PANEL_Y + 48; // Directly below the knob sprites
+
+    draw_simple_text(FILTER_PANEL_X - 1, FILTER_PANEL_Y + 33, "FLT", label_color, buffer);
+    draw_simple_text(FILTER_PANEL_X + 14 + knob_width / 2 - 6, label_y, "CUT", label_color, buffer);
+    draw_simple_text(FILTER_PANEL_X + 14 + knob_width + knob_width / 2 - 6, label_y, "RES", label_color, buffer);
+}
+
+/// Draws the filter mode (LP, HP, BP, NT) and slope (12, 24) buttons to the right of the resonance knob
+pub fn draw_filter_mode_buttons(state: &State, buffer: &mut [u32]) {
+    let current_track = state.current_track();
+    let x = FILTER_PANEL_X + 14 + 2 * 64; // Right of the resonance knob
+    let button_width = 24;
+    let button_height = 16;
+
+    let (bg_color, border_color, text_color) = if current_track.lpf_active {
+        (0xFFAA6600, 0xFFFFFFFF, 0xFFFFFFFF) // Amber when the filter is active
+    } else {
+        (0xFF333333, 0xFF666666, 0xFF999999) // Dark when bypassed
+    };
+
+    let labels = [
+        current_track.filter_mode.to_string(),
+        current_track.filter_slope.to_string(),
+    ];
+
+    for (i, label) in labels.iter().enumerate() {
+        let y = FILTER_PANEL_Y + 6 + i * (button_height + 4);
+        draw_effects_button_shape(x, y, button_width, button_height, bg_color, border_color, buffer);
+        draw_simple_text(x + button_width / 2 - label.len() * 2, y + button_height / 2 - 2, label, text_color, buffer);
+    }
+}
+
+/// Draw the LFO rows of the current track (on/off, shape, destination and sync buttons,
+/// rate and depth bars with readouts) followed by the tempo
+pub fn draw_lfo_panel(state: &State, buffer: &mut [u32]) {
+    let button_width = 24;
+    let button_height = 16;
+    let bar_x = LFO_PANEL_X + 142;
+    let bar_width = 80;
+    let bar_height = 8;
+
+    for (i, lfo) in state.current_track().lfos.iter().enumerate() {
+        let y = LFO_PANEL_Y + i * LFO_ROW_HEIGHT;
+
+        let (bg_color, border_color, text_color) = if lfo.enabled {
+            (0xFFAA6600, 0xFFFFFFFF, 0xFFFFFFFF) // Amber when the LFO is active
+        } else {
+            (0xFF333333, 0xFF666666, 0xFF999999) // Dark when off
+        };
+
+        draw_simple_text(LFO_PANEL_X, y + 6, &format!("LFO{}", i + 1), 0xFFFFFFFF, buffer);
+
+        let labels = [
+            if lfo.enabled { "ON".to_string() } else { "OFF".to_string() },
+            lfo.shape.to_string(),
+            lfo.destination.to_string(),
+            if lfo.tempo_sync { "BPM".to_string() } else { "HZ".to_string() },
+        ];
+
+        for (j, label) in labels.iter().enumerate() {
+            let x = LFO_PANEL_X + 20 + j * (button_width + 4);
+            draw_effects_button_shape(x, y, button_width, button_height, bg_color, border_color, buffer);
+            draw_simple_text(x + button_width / 2 - label.len() * 2, y + button_height / 2 - 2, label, text_color, buffer);
+        }
+
+        // Rate bar with Hz or note division readout
+        let rate_readout = if lfo.tempo_sync {
+            lfo.sync_division().to_string()
+        } else {
+            format!("{:.1}HZ", lfo.frequency(state.tempo_bpm))
+        };
+        draw_simple_text(bar_x - 10, y + 2, "R", text_color, buffer);
+        draw_track_bar(bar_x, y + 1, bar_width, bar_height, 0xFF222222, buffer);
+        draw_volume_bar(bar_x, y + 1, (lfo.rate * bar_width as f32) as usize, bar_height, 0xFF0088FF, buffer);
+        draw_simple_text(bar_x + bar_width + 4, y + 2, &rate_readout, text_color, buffer);
+
+        // Depth bar with percentage readout
+        draw_simple_text(bar_x - 10, y + 12, "D", text_color, buffer);
+        draw_track_bar(bar_x, y + 11, bar_width, bar_height, 0xFF222222, buffer);
+        draw_volume_bar(bar_x, y + 11, (lfo.depth * bar_width as f32) as usize, bar_height, 0xFF0088FF, buffer);
+        draw_simple_text(bar_x + bar_width + 4, y + 12, &format!("{:.0}%", lfo.depth * 100.0), text_color, buffer);
+    }
+
+    let tempo_y = LFO_PANEL_Y + state.current_track().lfos.len() * LFO_ROW_HEIGHT + 2;
+    draw_simple_text(LFO_PANEL_X, tempo_y, &format!("BPM {:.0}", state.tempo_bpm), 0xFFFFFFFF, buffer);
+}
+
+/// Draw the glide time bar of the current track on the tempo row of the LFO panel,
+/// lined up with the LFO rate and depth bars
+pub fn draw_glide_bar(state: &State, buffer: &mut [u32]) {
+    let bar_x = LFO_PANEL_X + 142;
+    let bar_width = 80;
+    let bar_height = 8;
+    let y = LFO_PANEL_Y + state.current_track().lfos.len() * LFO_ROW_HEIGHT + 1;
+
+    let track = state.current_track();
+    let text_color = if track.glide > 0 { 0xFFFFFFFF } else { 0xFF999999 };
+
+    draw_simple_text(bar_x - 10, y + 1, "G", text_color, buffer);
+    draw_track_bar(bar_x, y, bar_width, bar_height, 0xFF222222, buffer);
+    draw_volume_bar(bar_x, y, track.glide as usize * bar_width / 99, bar_height, 0xFF0088FF, buffer);
+    draw_simple_text(bar_x + bar_width + 4, y + 1, &format!("{:.2}S", track.glide_time()), text_color, buffer);
+}
+
+/// Draw the velocity panel: the computer keyboard velocity above the current track's
+/// velocity-to-amplitude and velocity-to-filter sensitivity bars
+pub fn draw_velocity_panel(state: &State, buffer: &mut [u32]) {
+    let bar_x = VELOCITY_PANEL_X + 24;
+    let bar_width = 80;
+    let bar_height = 8;
+    let text_color = 0xFFFFFFFF;
+    let track = state.current_track();
+
+    draw_simple_text(VELOCITY_PANEL_X, VELOCITY_PANEL_Y + 1, &format!("VEL {}", state.keyboard_velocity), text_color, buffer);
+
+    let sensitivities = [("AMP", track.velocity_to_amp), ("FLT", track.velocity_to_filter)];
+    for (i, (label, amount)) in sensitivities.iter().enumerate() {
+        let y = VELOCITY_PANEL_Y + (i + 1) * 11;
+        draw_simple_text(VELOCITY_PANEL_X, y + 1, label, text_color, buffer);
+        draw_track_bar(bar_x, y, bar_width, bar_height, 0xFF222222, buffer);
+        draw_volume_bar(bar_x, y, (amount * bar_width as f32) as usize, bar_height, 0xFF0088FF, buffer);
+        draw_simple_text(bar_x + bar_width + 4, y + 1, &format!("{:.0}%", amount * 100.0), text_color, buffer);
+    }
+}
+
+/// Draw the envelope trigger mode button of the current track, lit while legato is on
+pub fn draw_trigger_mode_button(state: &State, buffer: &mut [u32]) {
+    let button_width = 48;
+    let button_height = 16;
+    let x = ENVELOPE_GRAPH_X - button_width - 8;
+    let y = ENVELOPE_GRAPH_Y + 2;
+    let trigger_mode = state.current_track().trigger_mode;
+
+    let (bg_color, border_color, text_color) = if trigger_mode == TriggerMode::Legato {
+        (0xFFAA6600, 0xFFFFFFFF, 0xFFFFFFFF) // Amber when legato
+    } else {
+        (0xFF333333, 0xFF666666, 0xFF999999) // Dark when retriggering
+    };
+
+    let label = trigger_mode.to_string();
+    draw_effects_button_shape(x, y, button_width, button_height, bg_color, border_color, buffer);
+    draw_simple_text(x + button_width / 2 - label.len() * 2, y + button_height / 2 - 2, &label, text_color, buffer);
+}
+
+/// Draw the envelope loop button of the current track below the trigger mode button, lit while looping
+pub fn draw_envelope_loop_button(state: &State, buffer: &mut [u32]) {
+    let button_width = 48;
+    let button_height = 16;
+    let x = ENVELOPE_GRAPH_X - button_width - 8;
+    let y = ENVELOPE_GRAPH_Y + 2 + button_height + 2;
+
+    let (bg_color, border_color, text_color) = if state.current_track().envelope_loop {
+        (0xFFAA6600, 0xFFFFFFFF, 0xFFFFFFFF) // Amber when looping
+    } else {
+        (0xFF333333, 0xFF666666, 0xFF999999) // Dark when off
+    };
+
+    draw_effects_button_shape(x, y, button_width, button_height, bg_color, border_color, buffer);
+    draw_simple_text(x + button_width / 2 - 8, y + button_height / 2 - 2, "LOOP", text_color, buffer);
+}
+
+/// Draw the mod matrix slots of the current track: source and destination buttons
+/// above a bipolar amount bar with readout
+pub fn draw_mod_matrix(state: &State, buffer: &mut [u32]) {
+    let button_width = 28;
+    let button_height = 16;
+    let bar_width = 58;
+    let bar_height = 8;
+
+    for (i, slot) in state.current_track().mod_matrix.iter().enumerate() {
+        let x = MOD_MATRIX_X + i * MOD_SLOT_WIDTH;
+        let y = MOD_MATRIX_Y;
+        let is_active = slot.source != ModSource::Off;
+
+        let (bg_color, border_color, text_color) = if is_active {
+            (0xFFAA6600, 0xFFFFFFFF, 0xFFFFFFFF) // Amber when the slot is routed
+        } else {
+            (0xFF333333, 0xFF666666, 0xFF999999) // Dark when off
+        };
+
+        draw_simple_text(x, y + 6, &format!("M{}", i + 1), 0xFFFFFFFF, buffer);
+
+        let labels = [slot.source.to_string(), slot.destination.to_string()];
+        for (j, label) in labels.iter().enumerate() {
+            let button_x = x + 10 + j * (button_width + 2);
+            draw_effects_button_shape(button_x, y, button_width, button_height, bg_color, border_color, buffer);
+            draw_simple_text(button_x + button_width / 2 - label.len() * 2, y + button_height / 2 - 2, label, text_color, buffer);
+        }
+
+        // Amount is bipolar, so the bar fills left or right from its centre
+        let bar_x = x + 10;
+        let bar_y = y + button_height + 4;
+        let center_x = bar_x + bar_width / 2;
+        let fill_width = (slot.amount.abs() * (bar_width / 2) as f32) as usize;
+        draw_track_bar(bar_x, bar_y, bar_width, bar_height, 0xFF222222, buffer);
+        if slot.amount >= 0.0 {
+            draw_volume_bar(center_x, bar_y, fill_width, bar_height, 0xFF0088FF, buffer);
+        } else {
+            draw_volume_bar(center_x - fill_width, bar_y, fill_width, bar_height, 0xFF0088FF, buffer);
+        }
+        draw_volume_bar(center_x, bar_y, 1, bar_height, 0xFF666666, buffer);
+
+        let readout = format!("{:+.0}", slot.amount * 100.0);
+        draw_simple_text(bar_x + bar_width + 4, bar_y + 1, &readout, text_color, buffer);
+    }
+}
+
 /// Draw track information display with per-track transport controls
 pub fn draw_track_info(state: &State, buffer: &mut Vec<u32>) {
     let base_x = 10;
@@ -560,7 +918,7 @@ pub fn draw_track_info(state: &State, buffer: &mut Vec<u32>) {
 }
 
 /// Draw a simple track background bar
-fn draw_track_bar(x: usize, y: usize, width: usize, height: usize, color: u32, buffer: &mut Vec<u32>) {
+fn draw_track_bar(x: usize, y: usize, width: usize, height: usize, color: u32, buffer: &mut [u32]) {
     for dy in 0..height {
         for dx in 0..width {
             let pixel_x = x + dx;
@@ -575,7 +933,7 @@ fn draw_track_bar(x: usize, y: usize, width: usize, height: usize, color: u32, b
 }
 
 /// Draw simple text using a basic bitmap font
-fn draw_simple_text(x: usize, y: usize, text: &str, color: u32, buffer: &mut Vec<u32>) {
+fn draw_simple_text(x: usize, y: usize, text: &str, color: u32, buffer: &mut [u32]) {
     // Simple 3x5 bitmap font (limited character set)
     let font_patterns = std::collections::HashMap::from([
         ('1', vec![0b010, 0b110, 0b010, 0b010, 0b111]),
@@ -597,6 +955,37 @@ fn draw_simple_text(x: usize, y: usize, text: &str, color: u32, buffer: &mut Vec
         (' ', vec![0b000, 0b000, 0b000, 0b000, 0b000]),
         ('M', vec![0b101, 0b111, 0b101, 0b101, 0b101]),
         ('S', vec![0b111, 0b100, 0b111, 0b001, 0b111]),
+        ('A', vec![0b111, 0b101, 0b111, 0b101, 0b101]),
+        ('C', vec![0b111, 0b100, 0b100, 0b100, 0b111]),
+        ('E', vec![0b111, 0b100, 0b111, 0b100, 0b111]),
+        ('F', vec![0b111, 0b100, 0b111, 0b100, 0b100]),
+        ('G', vec![0b111, 0b100, 0b101, 0b101, 0b111]),
+        ('H', vec![0b101, 0b101, 0b111, 0b101, 0b101]),
+        ('I', vec![0b111, 0b010, 0b010, 0b010, 0b111]),
+        ('J', vec![0b001, 0b001, 0b001, 0b101, 0b111]),
+        ('K', vec![0b101, 0b101, 0b110, 0b101, 0b101]),
+        ('N', vec![0b110, 0b101, 0b101, 0b101, 0b101]),
+        ('O', vec![0b111, 0b101, 0b101, 0b101, 0b111]),
+        ('Q', vec![0b111, 0b101, 0b101, 0b111, 0b001]),
+        ('R', vec![0b111, 0b101, 0b111, 0b110, 0b101]),
+        ('T', vec![0b111, 0b010, 0b010, 0b010, 0b010]),
+        ('U', vec![0b101, 0b101, 0b101, 0b101, 0b111]),
+        ('V', vec![0b101, 0b101, 0b101, 0b101, 0b010]),
+        ('W', vec![0b101, 0b101, 0b101, 0b111, 0b101]),
+        ('X', vec![0b101, 0b101, 0b010, 0b101, 0b101]),
+        ('Y', vec![0b101, 0b101, 0b010, 0b010, 0b010]),
+        ('Z', vec![0b111, 0b001, 0b010, 0b100, 0b111]),
+        ('0', vec![0b111, 0b101, 0b101, 0b101, 0b111]),
+        ('5', vec![0b111, 0b100, 0b111, 0b001, 0b111]),
+        ('6', vec![0b111, 0b100, 0b111, 0b101, 0b111]),
+        ('7', vec![0b111, 0b001, 0b001, 0b001, 0b001]),
+        ('8', vec![0b111, 0b101, 0b111, 0b101, 0b111]),
+        ('9', vec![0b111, 0b101, 0b111, 0b001, 0b111]),
+        ('.', vec![0b000, 0b000, 0b000, 0b000, 0b010]),
+        ('-', vec![0b000, 0b000, 0b111, 0b000, 0b000]),
+        ('+', vec![0b000, 0b010, 0b111, 0b010, 0b000]),
+        ('/', vec![0b001, 0b001, 0b010, 0b100, 0b100]),
+        ('%', vec![0b101, 0b001, 0b010, 0b100, 0b101]),
     ]);
     
     for (i, ch) in text.chars().enumerate() {
@@ -619,7 +1008,7 @@ fn draw_simple_text(x: usize, y: usize, text: &str, color: u32, buffer: &mut Vec
 }
 
 /// Draw a volume level bar
-fn draw_volume_bar(x: usize, y: usize, width: usize, height: usize, color: u32, buffer: &mut Vec<u32>) {
+fn draw_volume_bar(x: usize, y: usize, width: usize, height: usize, color: u32, buffer: &mut [u32]) {
     for dy in 0..height {
         for dx in 0..width {
             let pixel_x = x + dx;
@@ -1,53 +1,257 @@
-use rodio::Source;
-use std::time::Duration;
-
-/// ADSR envelope wrapper that applies envelope shaping to any source
-pub struct ADSREnvelope<S>
-where
-    S: Source<Item = f32>,
-{
-    source: S,
-    sample_count: usize,
+use std::fmt;
+
+/// How strongly a full curvature of 1.0 bends an envelope segment
+const CURVE_STEEPNESS: f32 = 5.0;
+
+/// Curvature of the attack, decay and release segments.
+///
+/// Each value ranges from -1.0 to 1.0: 0.0 is a straight line, positive values bend the
+/// segment exponentially (rises start slowly, falls drop quickly) and negative values
+/// bend it logarithmically (rises start quickly, falls drop slowly).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EnvelopeCurves {
+    pub attack: f32,
+    pub decay: f32,
+    pub release: f32,
+}
+
+impl EnvelopeCurves {
+    /// Straight segments, matching the classic linear envelope
+    pub fn linear() -> Self {
+        Self {
+            attack: 0.0,
+            decay: 0.0,
+            release: 0.0,
+        }
+    }
+}
+
+/// Level of an envelope segment moving from `start` to `end` at `progress` (0.0 - 1.0).
+///
+/// Shared by the audio envelope and the envelope graph so both draw the same shape.
+pub fn curve_segment(start: f32, end: f32, progress: f32, curvature: f32) -> f32 {
+    let progress = progress.clamp(0.0, 1.0);
+    let steepness = curvature.abs().min(1.0) * CURVE_STEEPNESS;
+    if steepness < 0.001 {
+        return start + (end - start) * progress;
+    }
+
+    // Exponential curve that starts slowly and finishes quickly
+    let slow_start = |t: f32| (steepness * t).exp_m1() / steepness.exp_m1();
+
+    // Exponential rises and logarithmic falls start slowly; the other two start quickly
+    let rising = end > start;
+    let shape = if (curvature > 0.0) == rising {
+        slow_start(progress)
+    } else {
+        1.0 - slow_start(1.0 - progress)
+    };
+
+    start + (end - start) * shape
+}
+
+/// How a new note treats the envelope of a note that is still sounding on the same track
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TriggerMode {
+    Retrigger, // Restart the attack from the current level
+    Legato,    // Keep the envelope running and only change the pitch while a key is held
+}
+
+impl TriggerMode {
+    /// Returns the other mode
+    pub fn toggled(self) -> Self {
+        match self {
+            TriggerMode::Retrigger => TriggerMode::Legato,
+            TriggerMode::Legato => TriggerMode::Retrigger,
+        }
+    }
+}
+
+/// Implements the [Display] trait for [TriggerMode] using the label shown on the trigger button
+impl fmt::Display for TriggerMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TriggerMode::Retrigger => write!(f, "RETRIG"),
+            TriggerMode::Legato => write!(f, "LEGATO"),
+        }
+    }
+}
+
+/// Longest time in seconds a delay, attack, hold, decay or release fader can be set to
+pub const MAX_STAGE_SECONDS: f32 = 2.0;
+
+/// Rate the envelope is run at when it is evaluated for display rather than audio
+pub const DISPLAY_RATE: u32 = 1000;
+
+/// Converts a 0-99 time fader to seconds
+pub fn fader_seconds(value: u8) -> f32 {
+    value as f32 / 99.0 * MAX_STAGE_SECONDS
+}
+
+/// Converts a 0-99 level fader to a level (0.0 - 1.0)
+pub fn fader_level(value: u8) -> f32 {
+    value as f32 / 99.0
+}
+
+/// The single envelope model shared by the audio engine and the display,
+/// with every stage in seconds and the sustain as a level
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EnvelopeSettings {
+    pub delay: f32,
+    pub attack: f32,
+    pub hold: f32,
+    pub decay: f32,
+    pub sustain: f32, // 0.0 - 1.0
+    pub release: f32,
+    pub curves: EnvelopeCurves,
+    pub looping: bool, // Repeat attack, hold and decay until released
+}
+
+impl EnvelopeSettings {
+    /// Level of a held note `time_since_start` seconds after it started, or, when the key has been
+    /// let go, `time_since_release` seconds into its release.
+    ///
+    /// Runs the same [EnvelopeGenerator] as the audio engine, so the result is exactly what is heard.
+    pub fn level_at(&self, time_since_start: f32, time_since_release: Option<f32>) -> f32 {
+        let mut envelope = EnvelopeGenerator::new(self, DISPLAY_RATE).without_auto_release();
+        let to_samples = |seconds: f32| (seconds.max(0.0) * DISPLAY_RATE as f32) as usize;
+
+        let held_samples = to_samples(time_since_start - time_since_release.unwrap_or(0.0));
+        let mut level = 0.0;
+        for _ in 0..=held_samples {
+            level = envelope.next_amplitude();
+        }
+
+        if let Some(time_since_release) = time_since_release {
+            envelope.release();
+            for _ in 0..to_samples(time_since_release) {
+                if envelope.is_finished() {
+                    return 0.0;
+                }
+                level = envelope.next_amplitude();
+            }
+        }
+
+        level
+    }
+}
+
+/// Sample-accurate DAHDSR state machine, independent of any audio source.
+/// Drives both the amplitude envelope and the filter envelope.
+///
+/// Delay and hold default to zero, which makes it a plain ADSR. When looping, the envelope
+/// jumps back from the end of the decay to the start of the attack until it is released,
+/// so it can be used as a rhythmic modulator.
+///
+/// The envelope remembers the level it last produced, so the release and a retriggered
+/// attack both continue from wherever the envelope was instead of jumping.
+#[derive(Debug, Clone)]
+pub struct EnvelopeGenerator {
+    sample_count: usize,   // Position within the current pass through the stages
+    elapsed_samples: usize, // Time since the note started, for the auto-release
+    delay_samples: usize,
     attack_samples: usize,
+    hold_samples: usize,
     decay_samples: usize,
     sustain_level: f32,
     release_samples: usize,
     release_start_sample: Option<usize>,
-    is_released: bool,
-    max_sustain_samples: usize, // Maximum time to hold sustain before auto-release
+    max_sustain_samples: Option<usize>, // Maximum time to hold sustain before auto-release, None to hold until released
+    looping: bool,
+    curves: EnvelopeCurves,
+    current_level: f32,       // Most recent output
+    attack_start_level: f32,  // Level the attack rises from
+    release_start_level: f32, // Level the release falls from
 }
 
-impl<S> ADSREnvelope<S>
-where
-    S: Source<Item = f32>,
-{
-    pub fn new(
-        source: S,
-        attack: f32,    // Attack time in seconds
-        decay: f32,     // Decay time in seconds
-        sustain: f32,   // Sustain level (0.0 to 1.0)
-        release: f32,   // Release time in seconds
-    ) -> Self {
-        let sample_rate = source.sample_rate() as f32;
-        
+impl EnvelopeGenerator {
+    /// Create an envelope from its settings
+    ///
+    /// # Parameters
+    /// - `settings`: Stage times in seconds, sustain level, curves and looping
+    /// - `sample_rate`: Rate at which `next_amplitude` is called
+    pub fn new(settings: &EnvelopeSettings, sample_rate: u32) -> Self {
+        let sample_rate = sample_rate as f32;
+        let to_samples = |seconds: f32| (seconds.max(0.0) * sample_rate) as usize;
+
         Self {
-            source,
             sample_count: 0,
-            attack_samples: (attack * sample_rate) as usize,
-            decay_samples: (decay * sample_rate) as usize,
-            sustain_level: sustain,
-            release_samples: ((release * sample_rate) as usize).max(1), // Minimum 1 sample for release
+            elapsed_samples: 0,
+            delay_samples: to_samples(settings.delay),
+            attack_samples: to_samples(settings.attack),
+            hold_samples: to_samples(settings.hold),
+            decay_samples: to_samples(settings.decay),
+            sustain_level: settings.sustain.clamp(0.0, 1.0),
+            release_samples: to_samples(settings.release).max(1), // Minimum 1 sample for release
             release_start_sample: None,
-            is_released: false,
-            max_sustain_samples: ((release * 0.5 + 0.05) * sample_rate) as usize, // Shorter auto-release based on release setting
+            max_sustain_samples: Some(to_samples(settings.release * 0.5 + 0.05)), // Shorter auto-release based on release setting
+            looping: settings.looping,
+            curves: settings.curves,
+            current_level: 0.0,
+            attack_start_level: 0.0,
+            release_start_level: 0.0,
         }
     }
 
+    /// Hold the sustain until [EnvelopeGenerator::release] is called instead of releasing automatically
+    pub fn without_auto_release(mut self) -> Self {
+        self.max_sustain_samples = None;
+        self
+    }
+
+    /// Start the attack from `level` instead of silence, used to take over from a note that is still sounding
+    pub fn starting_at(mut self, level: f32) -> Self {
+        self.attack_start_level = level.clamp(0.0, 1.0);
+        self.current_level = self.attack_start_level;
+        self
+    }
+
+    /// Enter the release phase, falling from the current level
     pub fn release(&mut self) {
-        if !self.is_released {
+        if self.release_start_sample.is_none() {
             self.release_start_sample = Some(self.sample_count);
-            self.is_released = true;
+            self.release_start_level = self.current_level;
+        }
+    }
+
+    /// Whether the envelope has entered its release phase
+    pub fn is_released(&self) -> bool {
+        self.release_start_sample.is_some()
+    }
+
+    /// Whether the release phase has run to completion
+    pub fn is_finished(&self) -> bool {
+        match self.release_start_sample {
+            Some(release_start) => self.sample_count - release_start >= self.release_samples,
+            None => false,
+        }
+    }
+
+    /// Returns the amplitude for the current sample and advances the envelope by one sample
+    pub fn next_amplitude(&mut self) -> f32 {
+        let envelope_amplitude = self.calculate_envelope_amplitude();
+        self.current_level = envelope_amplitude;
+
+        self.sample_count += 1;
+        self.elapsed_samples += 1;
+
+        let decay_end = self.delay_samples + self.attack_samples + self.hold_samples + self.decay_samples;
+
+        // Jump back to the attack at the end of the decay, skipping the delay
+        let cycle_samples = self.attack_samples + self.hold_samples + self.decay_samples;
+        if self.looping && cycle_samples > 0 && !self.is_released() && self.sample_count > decay_end {
+            self.sample_count = self.delay_samples;
+            self.attack_start_level = envelope_amplitude;
         }
+
+        // Auto-release after max sustain time
+        if let Some(max_sustain_samples) = self.max_sustain_samples {
+            if self.elapsed_samples > decay_end + max_sustain_samples {
+                self.release();
+            }
+        }
+
+        envelope_amplitude
     }
 
     fn calculate_envelope_amplitude(&self) -> f32 {
@@ -62,82 +266,37 @@ where
                 return 0.0;
             }
             
-            let release_factor = 1.0 - (release_progress as f32 / self.release_samples as f32);
-            return self.sustain_level * release_factor;
+            let release_progress = release_progress as f32 / self.release_samples as f32;
+            return curve_segment(self.release_start_level, 0.0, release_progress, self.curves.release);
         }
 
-        if self.sample_count <= self.attack_samples {
+        let attack_start = self.delay_samples;
+        let hold_start = attack_start + self.attack_samples;
+        let decay_start = hold_start + self.hold_samples;
+
+        if self.sample_count < attack_start {
+            // Delay phase
+            self.attack_start_level
+        } else if self.sample_count <= hold_start {
             // Attack phase
             if self.attack_samples == 0 {
                 return 1.0;
             }
-            let attack_progress = self.sample_count as f32 / self.attack_samples as f32;
-            return attack_progress.min(1.0);
-        } else if self.sample_count <= self.attack_samples + self.decay_samples {
+            let attack_progress = (self.sample_count - attack_start) as f32 / self.attack_samples as f32;
+            curve_segment(self.attack_start_level, 1.0, attack_progress, self.curves.attack)
+        } else if self.sample_count <= decay_start {
+            // Hold phase
+            1.0
+        } else if self.sample_count <= decay_start + self.decay_samples {
             // Decay phase
             if self.decay_samples == 0 {
                 return self.sustain_level;
             }
-            let decay_progress = (self.sample_count - self.attack_samples) as f32 / self.decay_samples as f32;
-            return 1.0 - (1.0 - self.sustain_level) * decay_progress;
+            let decay_progress = (self.sample_count - decay_start) as f32 / self.decay_samples as f32;
+            curve_segment(1.0, self.sustain_level, decay_progress, self.curves.decay)
         } else {
             // Sustain phase 
-            return self.sustain_level;
+            self.sustain_level
         }
     }
 }
-
-impl<S> Iterator for ADSREnvelope<S>
-where
-    S: Source<Item = f32>,
-{
-    type Item = f32;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        let sample = self.source.next()?;
-        let envelope_amplitude = self.calculate_envelope_amplitude();
-        
-        self.sample_count += 1;
-        
-        // Auto-release after max sustain time
-        if self.sample_count > self.attack_samples + self.decay_samples + self.max_sustain_samples && !self.is_released {
-            self.release_start_sample = Some(self.sample_count);
-            self.is_released = true;
-        }
-        
-        // If we're in release phase and envelope is finished, return None to end the sound
-        if let Some(release_start) = self.release_start_sample {
-            if self.sample_count - release_start >= self.release_samples {
-                return None;
-            }
-        }
-        
-        // Only end sound if we're in release phase and amplitude is effectively zero
-        if envelope_amplitude < 0.0001 && self.release_start_sample.is_some() {
-            return None;
-        }
-        
-        Some(sample * envelope_amplitude)
-    }
-}
-
-impl<S> Source for ADSREnvelope<S>
-where
-    S: Source<Item = f32>,
-{
-    fn current_frame_len(&self) -> Option<usize> {
-        self.source.current_frame_len()
-    }
-
-    fn channels(&self) -> u16 {
-        self.source.channels()
-    }
-
-    fn sample_rate(&self) -> u32 {
-        self.source.sample_rate()
-    }
-
-    fn total_duration(&self) -> Option<Duration> {
-        None // ADSR envelope can vary in duration
-    }
-}
\ No newline at end of file
@@ -1,12 +1,11 @@
 use std::fmt;
 
 pub mod sine_wave;
-pub mod square_wave;
 pub mod triangle_wave;
 pub mod sawtooth_wave;
 pub mod adsr_envelope;
+pub mod oscillator;
 
-pub const MONO: u16 = 1;
 pub const SAMPLE_RATE: f32 = 48000.0;
 pub const AMPLITUDE: f32 = 0.20;
 pub const DURATION: f32 = 0.19;
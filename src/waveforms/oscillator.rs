@@ -0,0 +1,49 @@
+use std::f32::consts::PI;
+use crate::waveforms::Waveform;
+
+/// Phase-accumulating oscillator used by synth voices.
+///
+/// Unlike the sample-counting wave functions, the frequency and pulse width may change
+/// on every sample without discontinuities, which is what pitch modulation needs.
+#[derive(Debug, Clone)]
+pub struct Oscillator {
+    waveform: Waveform,
+    phase: f32,       // Position within the current period (0.0 - 1.0)
+    sample_rate: f32,
+}
+
+impl Oscillator {
+    pub fn new(waveform: Waveform, sample_rate: f32) -> Self {
+        Self {
+            waveform,
+            phase: 0.0,
+            sample_rate,
+        }
+    }
+
+    /// Generate the next sample and advance the phase
+    ///
+    /// # Parameters
+    /// - `frequency`: Frequency in Hz for this sample
+    /// - `pulse_width`: Duty cycle of the square wave (0.0 - 1.0), ignored by the other waveforms
+    pub fn next_sample(&mut self, frequency: f32, pulse_width: f32) -> f32 {
+        let phase = self.phase;
+
+        let sample = match self.waveform {
+            Waveform::SINE => (2.0 * PI * phase).sin(),
+            Waveform::SQUARE => if phase < pulse_width { 1.0 } else { -1.0 },
+            Waveform::TRIANGLE => {
+                if phase < 0.5 {
+                    4.0 * phase - 1.0  // Rising edge: -1 to 1
+                } else {
+                    3.0 - 4.0 * phase  // Falling edge: 1 to -1
+                }
+            }
+            Waveform::SAWTOOTH => 2.0 * phase - 1.0,
+        };
+
+        self.phase = (self.phase + frequency.max(0.0) / self.sample_rate).fract();
+
+        sample
+    }
+}
@@ -1,56 +1,5 @@
-use rodio::Source;
-use std::{f32::consts::PI, time::Duration};
-use crate::{
-    waveforms::{MONO, SAMPLE_RATE}
-};
-
-#[derive(Debug)]
-pub struct SineWave {
-    freq: f32,
-    num_sample: usize
-}
-
-impl SineWave {
-    pub fn new(freq: f32) -> SineWave {
-        SineWave { freq, num_sample: 0}
-    }
-    pub fn generate_sine_wave(&mut self) -> f32 {
-        calculate_sine(self.freq, self.num_sample)
-    }
-}
-
-/// Implementation of the [Iterator] trait for the [SineWave]
-impl Iterator for SineWave {
-    type Item = f32;
-    fn next(&mut self) -> Option<f32> {
-        // increment sample counter by 1
-        self.num_sample = self.num_sample.wrapping_add(1);
-
-        // Generates a sine wave
-        let sine_wave = self.generate_sine_wave();
-
-        Some(sine_wave)
-    }
-}
-
-/// Implementation of the [Source] trait for the [SineWave]
-impl Source for SineWave {
-    fn current_frame_len(&self) -> Option<usize> {
-        None
-    }
-
-    fn channels(&self) -> u16 {
-        MONO
-    }
-
-    fn sample_rate(&self) -> u32 {
-        SAMPLE_RATE as u32
-    }
-
-    fn total_duration(&self) -> Option<Duration> {
-        None
-    }
-}
+use std::f32::consts::PI;
+use crate::waveforms::SAMPLE_RATE;
 
 ///  The formula for calculating a sine wave is 'y(t) = sin(2πft)', whereby:
 /// '2πf' is two times pi the frequency (ie 2 * 3.14~ * 440 hz)
@@ -1,56 +1,4 @@
-use rodio::Source;
-use std::time::Duration;
-use crate::{
-    waveforms::{MONO, SAMPLE_RATE}
-};
-
-#[derive(Debug)]
-pub struct TriangleWave {
-    freq: f32,
-    num_sample: usize
-}
-
-impl TriangleWave {
-    pub fn new(freq: f32) -> TriangleWave {
-        TriangleWave { freq, num_sample: 0}
-    }
-    pub fn generate_triangle_wave(&mut self) -> f32 {
-        calculate_triangle(self.freq, self.num_sample)
-    }
-}
-
-/// Implementation of the [Iterator] trait for the [TriangleWave]
-impl Iterator for TriangleWave {
-    type Item = f32;
-    fn next(&mut self) -> Option<f32> {
-        // increment sample counter by 1
-        self.num_sample = self.num_sample.wrapping_add(1);
-
-        // Generates a triangle wave
-        let triangle_wave = self.generate_triangle_wave();
-
-        Some(triangle_wave)
-    }
-}
-
-/// Implementation of the [Source] trait for the [TriangleWave]
-impl Source for TriangleWave {
-    fn current_frame_len(&self) -> Option<usize> {
-        None
-    }
-
-    fn channels(&self) -> u16 {
-        MONO
-    }
-
-    fn sample_rate(&self) -> u32 {
-        SAMPLE_RATE as u32
-    }
-
-    fn total_duration(&self) -> Option<Duration> {
-        None
-    }
-}
+use crate::waveforms::SAMPLE_RATE;
 
 /// Calculates a triangle wave value for a given frequency and sample number.
 /// Triangle wave oscillates linearly between -1 and 1, creating a triangular shape.